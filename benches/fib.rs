@@ -0,0 +1,92 @@
+//! Times recursive programs end to end through the interpreter binary.
+//!
+//! Run with `cargo bench`. Each program is timed on both engines, relative to the
+//! evaluator. Setting `BENCH_BASELINE` to another build of the interpreter, such as one
+//! from before a change, times it too and compares against it instead. The baseline runs
+//! its default engine, so builds from before `--engine` existed work.
+
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+const RUNS: u32 = 5;
+
+const ENGINES: &[&str] = &["evaluator", "vm"];
+
+const PROGRAMS: &[(&str, &str, &str)] = &[
+    (
+        "fib(25)",
+        "fn fib(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } } fib(25)",
+        "75025",
+    ),
+    (
+        "string lookups",
+        r#"fn repeat(n, s) { n == 0 ? s : repeat(n - 1, s + "ab") }
+        let s = repeat(5000, "");
+        fn count(n, acc) { n == 0 ? acc : count(n - 1, s == s ? acc + 1 : acc) }
+        count(100000, 0)"#,
+        "100000",
+    ),
+];
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("rinonguci-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create a directory for the bench scripts");
+    let baseline = std::env::var("BENCH_BASELINE").ok();
+
+    for (name, source, expected) in PROGRAMS {
+        let script = dir.join("bench.rs");
+        fs::write(&script, source).expect("failed to write the bench script");
+
+        let mut timings = Vec::new();
+        if let Some(baseline) = &baseline {
+            let best = best_time(Command::new(baseline), &script, name, expected);
+            timings.push(("baseline", best));
+        }
+        for engine in ENGINES {
+            let mut command = Command::new(env!("CARGO_BIN_EXE_rinonguci-script"));
+            command.args(["--engine", engine]);
+            timings.push((engine, best_time(command, &script, name, expected)));
+        }
+
+        let (reference, reference_time) = timings[0];
+        for (label, best) in timings {
+            println!(
+                "{:<16} {:<9} best of {}: {:>12?}  {:>5.2}x {}",
+                name,
+                label,
+                RUNS,
+                best,
+                best.as_secs_f64() / reference_time.as_secs_f64(),
+                reference
+            );
+        }
+    }
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+/// The shortest of `RUNS` runs of `command` on `script`, checking that each printed
+/// `expected`.
+fn best_time(mut command: Command, script: &Path, name: &str, expected: &str) -> Duration {
+    command.arg(script);
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let output = command.output().expect("failed to run the interpreter");
+        best = best.min(start.elapsed());
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success() && stdout.trim() == expected,
+            "{} printed {:?}, stderr {:?}",
+            name,
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    best
+}
@@ -4,8 +4,9 @@ use std::fmt::Debug;
 
 use enum_as_inner::EnumAsInner;
 use node::{
-    Boolean, CallExpression, FunctionLiteral, Identifier, IfExpression, InfixExpression,
-    IntegerLiteral, PrefixExpression, StringLiteral,
+    ArrayLiteral, BigIntLiteral, Boolean, CallExpression, ConditionalExpression, FunctionLiteral,
+    HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression, IntegerLiteral,
+    MatchExpression, PrefixExpression, SpreadExpression, StringLiteral,
 };
 
 use crate::ast::TNode;
@@ -16,13 +17,20 @@ use super::Node;
 pub enum ExpressionType {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
+    BigIntLiteral(BigIntLiteral),
     StringLiteral(StringLiteral),
     Prefix(PrefixExpression),
     Infix(InfixExpression),
     Boolean(Boolean),
     If(IfExpression),
+    Conditional(ConditionalExpression),
     Fn(FunctionLiteral),
     Call(CallExpression),
+    Array(ArrayLiteral),
+    Hash(HashLiteral),
+    Index(IndexExpression),
+    Match(MatchExpression),
+    Spread(SpreadExpression),
 }
 
 impl TNode for ExpressionType {
@@ -30,13 +38,20 @@ impl TNode for ExpressionType {
         match self {
             ExpressionType::Identifier(expr) => expr.token_literal(),
             ExpressionType::IntegerLiteral(expr) => expr.token_literal(),
+            ExpressionType::BigIntLiteral(expr) => expr.token_literal(),
             ExpressionType::StringLiteral(expr) => expr.token_literal(),
             ExpressionType::Prefix(expr) => expr.token_literal(),
             ExpressionType::Infix(expr) => expr.token_literal(),
             ExpressionType::Boolean(expr) => expr.token_literal(),
             ExpressionType::If(expr) => expr.token_literal(),
+            ExpressionType::Conditional(expr) => expr.token_literal(),
             ExpressionType::Fn(expr) => expr.token_literal(),
             ExpressionType::Call(expr) => expr.token_literal(),
+            ExpressionType::Array(expr) => expr.token_literal(),
+            ExpressionType::Hash(expr) => expr.token_literal(),
+            ExpressionType::Match(expr) => expr.token_literal(),
+            ExpressionType::Index(expr) => expr.token_literal(),
+            ExpressionType::Spread(expr) => expr.token_literal(),
         }
     }
 
@@ -44,18 +59,27 @@ impl TNode for ExpressionType {
         match self {
             ExpressionType::Identifier(expr) => expr.string(),
             ExpressionType::IntegerLiteral(expr) => expr.string(),
+            ExpressionType::BigIntLiteral(expr) => expr.string(),
             ExpressionType::StringLiteral(expr) => expr.string(),
             ExpressionType::Prefix(expr) => expr.string(),
             ExpressionType::Infix(expr) => expr.string(),
             ExpressionType::Boolean(expr) => expr.string(),
             ExpressionType::If(expr) => expr.string(),
+            ExpressionType::Conditional(expr) => expr.string(),
             ExpressionType::Fn(expr) => expr.string(),
             ExpressionType::Call(expr) => expr.string(),
+            ExpressionType::Array(expr) => expr.string(),
+            ExpressionType::Hash(expr) => expr.string(),
+            ExpressionType::Match(expr) => expr.string(),
+            ExpressionType::Index(expr) => expr.string(),
+            ExpressionType::Spread(expr) => expr.string(),
         }
     }
 }
 
 impl ExpressionType {
+    // Takes the box the parser builds nodes in.
+    #[allow(clippy::boxed_local)]
     pub fn to_node(self: Box<ExpressionType>) -> Box<Node> {
         Box::new(Node::Expression(*self))
     }
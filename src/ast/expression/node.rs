@@ -1,5 +1,8 @@
+use std::rc::Rc;
+
 use crate::{
-    ast::{statement::StatementType, TNode},
+    ast::{pattern::Pattern, statement::StatementType, Scope, Slot, TNode},
+    lexer::Position,
     token::Token,
 };
 
@@ -8,6 +11,9 @@ use super::ExpressionType;
 #[derive(Debug, PartialEq, Clone)]
 pub struct Identifier {
     pub token: Token,
+    pub position: Position,
+    /// Set by the resolver.
+    pub slot: Option<Slot>,
 }
 
 impl TNode for Identifier {
@@ -35,6 +41,21 @@ impl TNode for IntegerLiteral {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct BigIntLiteral {
+    pub token: Token,
+}
+
+impl TNode for BigIntLiteral {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+
+    fn string(&self) -> String {
+        self.token.to_string()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct StringLiteral {
     pub token: Token,
@@ -54,6 +75,7 @@ impl TNode for StringLiteral {
 pub struct PrefixExpression {
     pub operator: Token,
     pub right: Box<ExpressionType>,
+    pub position: Position,
 }
 
 impl TNode for PrefixExpression {
@@ -63,10 +85,10 @@ impl TNode for PrefixExpression {
 
     fn string(&self) -> String {
         let mut out = String::new();
-        out.push_str("(");
+        out.push('(');
         out.push_str(&self.operator.to_string());
         out.push_str(&self.right.as_ref().string());
-        out.push_str(")");
+        out.push(')');
 
         out
     }
@@ -77,6 +99,8 @@ pub struct InfixExpression {
     pub operator: Token,
     pub left: Box<ExpressionType>,
     pub right: Box<ExpressionType>,
+    /// Where the operator appears, which runtime errors from applying it point at.
+    pub position: Position,
 }
 
 impl TNode for InfixExpression {
@@ -86,13 +110,13 @@ impl TNode for InfixExpression {
 
     fn string(&self) -> String {
         let mut out = String::new();
-        out.push_str("(");
+        out.push('(');
         out.push_str(&self.left.as_ref().string());
-        out.push_str(" ");
+        out.push(' ');
         out.push_str(&self.operator.to_string());
-        out.push_str(" ");
+        out.push(' ');
         out.push_str(&self.right.as_ref().string());
-        out.push_str(")");
+        out.push(')');
 
         out
     }
@@ -129,23 +153,58 @@ impl TNode for IfExpression {
         let mut out = String::new();
         out.push_str("if ");
         out.push_str(&self.condition.string());
-        out.push_str(" ");
+        out.push(' ');
         out.push_str("{ ");
         out.push_str(&self.consequence.string());
         out.push_str(" }");
         if let Some(alt) = &self.alternative {
-            out.push_str("else ");
-            out.push_str("{ ");
-            out.push_str(&alt.string());
+            out.push_str(" else ");
+            match alt.as_ref() {
+                StatementType::Expression(else_if) => out.push_str(&else_if.string()),
+                _ => {
+                    out.push_str("{ ");
+                    out.push_str(&alt.string());
+                    out.push_str(" }");
+                }
+            }
         }
         out
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConditionalExpression {
+    pub condition: Box<ExpressionType>,
+    pub consequence: Box<ExpressionType>,
+    pub alternative: Box<ExpressionType>,
+}
+
+impl TNode for ConditionalExpression {
+    fn token_literal(&self) -> String {
+        "?".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        out.push('(');
+        out.push_str(&self.condition.string());
+        out.push_str(" ? ");
+        out.push_str(&self.consequence.string());
+        out.push_str(" : ");
+        out.push_str(&self.alternative.string());
+        out.push(')');
+        out
+    }
+}
+
+/// The parameters and body are shared with every closure created from the literal.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionLiteral {
-    pub parameters: Vec<Box<ExpressionType>>,
-    pub body: Box<StatementType>,
+    pub name: Option<String>,
+    pub parameters: Rc<[Parameter]>,
+    pub body: Rc<StatementType>,
+    /// The parameters followed by the names the body declares.
+    pub scope: Scope,
 }
 
 impl TNode for FunctionLiteral {
@@ -156,23 +215,55 @@ impl TNode for FunctionLiteral {
     fn string(&self) -> String {
         let mut out = String::new();
         let mut params = vec![];
-        for p in &self.parameters {
+        for p in self.parameters.iter() {
             params.push(p.string());
         }
         out.push_str(&self.token_literal());
-        out.push_str("(");
+        if let Some(name) = &self.name {
+            out.push(' ');
+            out.push_str(name);
+        }
+        out.push('(');
         out.push_str(&params.join(","));
-        out.push_str(")");
+        out.push(')');
         out.push_str(&self.body.string());
         out
     }
 }
 
+/// A single entry in a function's parameter list: `name`, `name = default` or `...name`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Box<ExpressionType>>,
+    pub rest: bool,
+}
+
+impl TNode for Parameter {
+    fn token_literal(&self) -> String {
+        self.name.clone()
+    }
+
+    fn string(&self) -> String {
+        match (&self.default, self.rest) {
+            (_, true) => format!("...{}", self.name),
+            (Some(default), false) => format!("{} = {}", self.name, default.string()),
+            (None, false) => self.name.clone(),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct CallExpression {
     pub token: Token,
     pub function: Box<ExpressionType>,
     pub arguments: Vec<Box<ExpressionType>>,
+    /// The `name: value` arguments, which follow every positional one.
+    pub named: Vec<NamedArgument>,
+    pub position: Position,
+    /// Whether the enclosing function returns this call's value directly. Set by the parser
+    /// once the function is complete.
+    pub tail: bool,
 }
 
 impl TNode for CallExpression {
@@ -186,10 +277,165 @@ impl TNode for CallExpression {
         for a in &self.arguments {
             args.push(a.string());
         }
+        for a in &self.named {
+            args.push(a.string());
+        }
         out.push_str(&self.function.string());
-        out.push_str("(");
+        out.push('(');
         out.push_str(&args.join(", "));
-        out.push_str(")");
+        out.push(')');
         out
     }
 }
+
+/// An argument passed by the name of the parameter it binds: `name: value`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NamedArgument {
+    pub name: String,
+    pub value: Box<ExpressionType>,
+    pub position: Position,
+}
+
+impl TNode for NamedArgument {
+    fn token_literal(&self) -> String {
+        self.name.clone()
+    }
+
+    fn string(&self) -> String {
+        format!("{}: {}", self.name, self.value.string())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArrayLiteral {
+    pub elements: Vec<Box<ExpressionType>>,
+}
+
+impl TNode for ArrayLiteral {
+    fn token_literal(&self) -> String {
+        "[".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        let elements: Vec<String> = self.elements.iter().map(|e| e.string()).collect();
+        out.push('[');
+        out.push_str(&elements.join(", "));
+        out.push(']');
+        out
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchExpression {
+    pub subject: Box<ExpressionType>,
+    pub arms: Vec<MatchArm>,
+    pub position: Position,
+}
+
+impl TNode for MatchExpression {
+    fn token_literal(&self) -> String {
+        "match".into()
+    }
+
+    fn string(&self) -> String {
+        let arms: Vec<String> = self.arms.iter().map(|arm| arm.string()).collect();
+        format!(
+            "match ({}) {{ {} }}",
+            self.subject.string(),
+            arms.join(", ")
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Box<ExpressionType>>,
+    pub body: Box<StatementType>,
+    /// The names the pattern binds.
+    pub scope: Scope,
+}
+
+impl TNode for MatchArm {
+    fn token_literal(&self) -> String {
+        self.pattern.token_literal()
+    }
+
+    fn string(&self) -> String {
+        let mut out = self.pattern.string();
+        if let Some(guard) = &self.guard {
+            out.push_str(" if ");
+            out.push_str(&guard.string());
+        }
+        out.push_str(" => ");
+        out.push_str(&self.body.string());
+        out
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HashLiteral {
+    pub pairs: Vec<(Box<ExpressionType>, Box<ExpressionType>)>,
+    pub position: Position,
+}
+
+impl TNode for HashLiteral {
+    fn token_literal(&self) -> String {
+        "{".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.string(), value.string()))
+            .collect();
+        out.push('{');
+        out.push_str(&pairs.join(", "));
+        out.push('}');
+        out
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IndexExpression {
+    pub left: Box<ExpressionType>,
+    pub index: Box<ExpressionType>,
+    pub position: Position,
+}
+
+impl TNode for IndexExpression {
+    fn token_literal(&self) -> String {
+        "[".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        out.push('(');
+        out.push_str(&self.left.string());
+        out.push('[');
+        out.push_str(&self.index.string());
+        out.push_str("])");
+        out
+    }
+}
+
+/// `...value`, expanded in place inside call arguments and array literals. In a parameter
+/// list it marks the rest parameter.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpreadExpression {
+    pub value: Box<ExpressionType>,
+    pub position: Position,
+}
+
+impl TNode for SpreadExpression {
+    fn token_literal(&self) -> String {
+        "...".into()
+    }
+
+    fn string(&self) -> String {
+        format!("...{}", self.value.string())
+    }
+}
@@ -1,10 +1,11 @@
-use std::{any::Any, fmt::Debug};
+use std::{any::Any, fmt::Debug, rc::Rc};
 
 use enum_as_inner::EnumAsInner;
 use expression::ExpressionType;
 use statement::StatementType;
 
 pub mod expression;
+pub mod pattern;
 pub mod statement;
 
 pub trait TNode: Any {
@@ -12,6 +13,27 @@ pub trait TNode: Any {
     fn string(&self) -> String;
 }
 
+/// Where the resolver found a variable: `depth` scopes out from the one it is used in,
+/// at `index` among that scope's slots.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
+/// The names a scope declares, in slot order. Filled in by the resolver and shared with
+/// every environment created for the scope.
+pub type ScopeNames = Rc<[String]>;
+
+/// The variables a scope declares, filled in by the resolver.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Scope {
+    pub names: ScopeNames,
+    /// Whether a function nested in the scope refers to each variable, by slot. The virtual
+    /// machine keeps only those in an environment.
+    pub captured: Rc<[bool]>,
+}
+
 #[derive(Debug, EnumAsInner)]
 pub enum Node {
     Statement(StatementType),
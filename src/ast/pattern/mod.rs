@@ -0,0 +1,83 @@
+pub mod node;
+
+use enum_as_inner::EnumAsInner;
+use node::{ArrayPattern, HashPattern};
+
+use crate::ast::{expression::ExpressionType, TNode};
+
+/// The target of a binding: a plain name, or a destructuring pattern that pulls values out
+/// of an array or hash. `match` arms may also use literals, `_` and `a | b` alternatives,
+/// which can fail to match and so are rejected in `let`.
+#[derive(Debug, EnumAsInner, PartialEq, Clone)]
+pub enum Pattern {
+    Identifier(String),
+    Wildcard,
+    Literal(Box<ExpressionType>),
+    Or(Vec<Pattern>),
+    Array(ArrayPattern),
+    Hash(HashPattern),
+}
+
+impl TNode for Pattern {
+    fn token_literal(&self) -> String {
+        match self {
+            Pattern::Identifier(name) => name.clone(),
+            Pattern::Wildcard => "_".into(),
+            Pattern::Literal(literal) => literal.token_literal(),
+            Pattern::Or(alternatives) => alternatives[0].token_literal(),
+            Pattern::Array(pattern) => pattern.token_literal(),
+            Pattern::Hash(pattern) => pattern.token_literal(),
+        }
+    }
+
+    fn string(&self) -> String {
+        match self {
+            Pattern::Identifier(name) => name.clone(),
+            Pattern::Wildcard => "_".into(),
+            Pattern::Literal(literal) => match literal.as_ref() {
+                ExpressionType::StringLiteral(_) => format!("{:?}", literal.string()),
+                literal => literal.string(),
+            },
+            Pattern::Or(alternatives) => {
+                let alternatives: Vec<String> = alternatives.iter().map(|p| p.string()).collect();
+                alternatives.join(" | ")
+            }
+            Pattern::Array(pattern) => pattern.string(),
+            Pattern::Hash(pattern) => pattern.string(),
+        }
+    }
+}
+
+impl Pattern {
+    /// Every name the pattern binds, in source order.
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Pattern::Identifier(name) => vec![name.clone()],
+            Pattern::Wildcard | Pattern::Literal(_) => Vec::new(),
+            // Every alternative binds the same names; the parser checks this.
+            Pattern::Or(alternatives) => alternatives[0].names(),
+            Pattern::Array(pattern) => pattern
+                .elements
+                .iter()
+                .flat_map(|element| element.names())
+                .chain(pattern.rest.clone())
+                .collect(),
+            Pattern::Hash(pattern) => pattern
+                .entries
+                .iter()
+                .flat_map(|entry| entry.value.names())
+                .chain(pattern.rest.clone())
+                .collect(),
+        }
+    }
+
+    /// Whether some value of the right shape could still fail to match, as with a literal.
+    pub fn is_refutable(&self) -> bool {
+        match self {
+            Pattern::Identifier(_) | Pattern::Wildcard => false,
+            Pattern::Literal(_) | Pattern::Or(_) => true,
+            Pattern::Array(pattern) => pattern.elements.iter().any(|p| p.is_refutable()),
+            Pattern::Hash(pattern) => pattern.entries.iter().any(|e| e.value.is_refutable()),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::ast::TNode;
+
+use super::Pattern;
+
+/// `[a, b, ...rest]`: binds elements by position, with the rest collected into an array.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArrayPattern {
+    pub elements: Vec<Pattern>,
+    pub rest: Option<String>,
+}
+
+impl TNode for ArrayPattern {
+    fn token_literal(&self) -> String {
+        "[".into()
+    }
+
+    fn string(&self) -> String {
+        let mut elements: Vec<String> = self.elements.iter().map(|e| e.string()).collect();
+        if let Some(rest) = &self.rest {
+            elements.push(format!("...{}", rest));
+        }
+        format!("[{}]", elements.join(", "))
+    }
+}
+
+/// `{name, age: years, ...rest}`: binds values by key, with the remaining pairs collected
+/// into a hash.
+#[derive(Debug, PartialEq, Clone)]
+pub struct HashPattern {
+    pub entries: Vec<HashPatternEntry>,
+    pub rest: Option<String>,
+}
+
+impl TNode for HashPattern {
+    fn token_literal(&self) -> String {
+        "{".into()
+    }
+
+    fn string(&self) -> String {
+        let mut entries: Vec<String> = self.entries.iter().map(|e| e.string()).collect();
+        if let Some(rest) = &self.rest {
+            entries.push(format!("...{}", rest));
+        }
+        format!("{{{}}}", entries.join(", "))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct HashPatternEntry {
+    pub key: String,
+    pub value: Pattern,
+}
+
+impl TNode for HashPatternEntry {
+    fn token_literal(&self) -> String {
+        self.key.clone()
+    }
+
+    fn string(&self) -> String {
+        match &self.value {
+            Pattern::Identifier(name) if *name == self.key => name.clone(),
+            value => format!("{}: {}", self.key, value.string()),
+        }
+    }
+}
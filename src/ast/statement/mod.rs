@@ -1,7 +1,11 @@
 pub mod node;
 
 use enum_as_inner::EnumAsInner;
-use node::{BlockStatement, ExpressionStatement, LetStatement, ReturnStatement};
+use node::{
+    BlockStatement, BreakStatement, ContinueStatement, ExpressionStatement, ForStatement,
+    FunctionDeclaration, LetStatement, ReturnStatement, SwitchStatement, ThrowStatement,
+    TryStatement, WhileStatement,
+};
 use std::fmt::Debug;
 
 use crate::ast::TNode;
@@ -16,6 +20,14 @@ pub enum StatementType {
     Return(ReturnStatement),
     Expression(ExpressionStatement),
     Block(BlockStatement),
+    Function(FunctionDeclaration),
+    While(WhileStatement),
+    For(ForStatement),
+    Switch(SwitchStatement),
+    Throw(ThrowStatement),
+    Try(TryStatement),
+    Break(BreakStatement),
+    Continue(ContinueStatement),
 }
 
 impl TNode for StatementType {
@@ -25,6 +37,14 @@ impl TNode for StatementType {
             StatementType::Return(stmt) => stmt.token_literal(),
             StatementType::Expression(stmt) => stmt.token_literal(),
             StatementType::Block(stmt) => stmt.token_literal(),
+            StatementType::Function(stmt) => stmt.token_literal(),
+            StatementType::While(stmt) => stmt.token_literal(),
+            StatementType::For(stmt) => stmt.token_literal(),
+            StatementType::Switch(stmt) => stmt.token_literal(),
+            StatementType::Throw(stmt) => stmt.token_literal(),
+            StatementType::Try(stmt) => stmt.token_literal(),
+            StatementType::Break(stmt) => stmt.token_literal(),
+            StatementType::Continue(stmt) => stmt.token_literal(),
         }
     }
 
@@ -34,11 +54,21 @@ impl TNode for StatementType {
             StatementType::Return(stmt) => stmt.string(),
             StatementType::Expression(stmt) => stmt.string(),
             StatementType::Block(stmt) => stmt.string(),
+            StatementType::Function(stmt) => stmt.string(),
+            StatementType::While(stmt) => stmt.string(),
+            StatementType::For(stmt) => stmt.string(),
+            StatementType::Switch(stmt) => stmt.string(),
+            StatementType::Throw(stmt) => stmt.string(),
+            StatementType::Try(stmt) => stmt.string(),
+            StatementType::Break(stmt) => stmt.string(),
+            StatementType::Continue(stmt) => stmt.string(),
         }
     }
 }
 
 impl StatementType {
+    // Takes the box the parser builds nodes in.
+    #[allow(clippy::boxed_local)]
     pub fn to_node(self: Box<StatementType>) -> Box<Node> {
         Box::new(Node::Statement(*self))
     }
@@ -1,5 +1,10 @@
 use crate::{
-    ast::{expression::ExpressionType, TNode},
+    ast::{
+        expression::{node::FunctionLiteral, ExpressionType},
+        pattern::Pattern,
+        Scope, Slot, TNode,
+    },
+    lexer::Position,
     token::Token,
 };
 
@@ -8,8 +13,11 @@ use super::StatementType;
 #[derive(Debug, PartialEq, Clone)]
 pub struct LetStatement {
     pub token: Option<Token>,
-    pub name: String,
+    pub pattern: Pattern,
     pub value: Box<ExpressionType>,
+    pub position: Position,
+    /// The variable a reassignment, which has no `token`, writes to. Set by the resolver.
+    pub slot: Option<Slot>,
 }
 
 impl TNode for LetStatement {
@@ -23,12 +31,12 @@ impl TNode for LetStatement {
     fn string(&self) -> String {
         let mut out = String::new();
         out.push_str(&self.token_literal());
-        out.push_str(&self.name.to_string());
+        out.push_str(&self.pattern.string());
         out.push_str(" = ");
 
         out.push_str(&self.value.string());
 
-        out.push_str(";");
+        out.push(';');
 
         out
     }
@@ -47,11 +55,11 @@ impl TNode for ReturnStatement {
     fn string(&self) -> String {
         let mut out = String::new();
         out.push_str(&self.token_literal());
-        out.push_str(" ");
+        out.push(' ');
 
         out.push_str(&self.value.string());
 
-        out.push_str(";");
+        out.push(';');
 
         out
     }
@@ -75,6 +83,8 @@ impl TNode for ExpressionStatement {
 #[derive(Debug, PartialEq, Clone)]
 pub struct BlockStatement {
     pub statements: Vec<Box<StatementType>>,
+    /// Empty for a function, `for` or `catch` body, which shares the enclosing scope.
+    pub scope: Scope,
 }
 
 impl TNode for BlockStatement {
@@ -85,10 +95,223 @@ impl TNode for BlockStatement {
     fn string(&self) -> String {
         let mut out = String::new();
         for stmt in &self.statements {
-            out.push_str("{");
+            out.push('{');
+            out.push_str(&stmt.string());
+            out.push('}');
+        }
+        out
+    }
+}
+
+/// A `fn name(params) { body }` statement, hoisted to the top of its scope.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionDeclaration {
+    pub function: FunctionLiteral,
+}
+
+impl FunctionDeclaration {
+    pub fn name(&self) -> String {
+        self.function.name.clone().unwrap_or_default()
+    }
+}
+
+impl TNode for FunctionDeclaration {
+    fn token_literal(&self) -> String {
+        self.function.token_literal()
+    }
+
+    fn string(&self) -> String {
+        self.function.string()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct WhileStatement {
+    pub condition: Box<ExpressionType>,
+    pub body: Box<StatementType>,
+}
+
+impl TNode for WhileStatement {
+    fn token_literal(&self) -> String {
+        "while".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        out.push_str("while ");
+        out.push_str(&self.condition.string());
+        out.push_str(" { ");
+        out.push_str(&self.body.string());
+        out.push_str(" }");
+        out
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThrowStatement {
+    pub value: Box<ExpressionType>,
+    pub position: Position,
+}
+
+impl TNode for ThrowStatement {
+    fn token_literal(&self) -> String {
+        "throw".into()
+    }
+
+    fn string(&self) -> String {
+        format!("throw {};", self.value.string())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryStatement {
+    pub block: Box<StatementType>,
+    pub catch: Option<CatchClause>,
+    pub finally: Option<Box<StatementType>>,
+}
+
+impl TNode for TryStatement {
+    fn token_literal(&self) -> String {
+        "try".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = format!("try {}", self.block.string());
+        if let Some(catch) = &self.catch {
+            out.push(' ');
+            out.push_str(&catch.string());
+        }
+        if let Some(finally) = &self.finally {
+            out.push_str(" finally ");
+            out.push_str(&finally.string());
+        }
+        out
+    }
+}
+
+/// `catch (name) { ... }`, where the binding may be left out as in `catch { ... }`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CatchClause {
+    pub name: Option<String>,
+    pub body: Box<StatementType>,
+    /// The binding followed by the names the body declares.
+    pub scope: Scope,
+}
+
+impl TNode for CatchClause {
+    fn token_literal(&self) -> String {
+        "catch".into()
+    }
+
+    fn string(&self) -> String {
+        match &self.name {
+            Some(name) => format!("catch ({}) {}", name, self.body.string()),
+            None => format!("catch {}", self.body.string()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwitchStatement {
+    pub discriminant: Box<ExpressionType>,
+    pub cases: Vec<SwitchCase>,
+    /// The names declared by every clause, which share one scope.
+    pub scope: Scope,
+}
+
+impl TNode for SwitchStatement {
+    fn token_literal(&self) -> String {
+        "switch".into()
+    }
+
+    fn string(&self) -> String {
+        let cases: Vec<String> = self.cases.iter().map(|c| c.string()).collect();
+        format!(
+            "switch ({}) {{ {} }}",
+            self.discriminant.string(),
+            cases.join(" ")
+        )
+    }
+}
+
+/// One `case test:` clause, or the `default:` clause when `test` is `None`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwitchCase {
+    pub test: Option<Box<ExpressionType>>,
+    pub body: Vec<Box<StatementType>>,
+}
+
+impl TNode for SwitchCase {
+    fn token_literal(&self) -> String {
+        match self.test {
+            Some(_) => "case".into(),
+            None => "default".into(),
+        }
+    }
+
+    fn string(&self) -> String {
+        let mut out = match &self.test {
+            Some(test) => format!("case {}:", test.string()),
+            None => "default:".into(),
+        };
+        for stmt in &self.body {
+            out.push(' ');
             out.push_str(&stmt.string());
-            out.push_str("}");
         }
         out
     }
 }
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForStatement {
+    pub name: String,
+    pub iterable: Box<ExpressionType>,
+    pub body: Box<StatementType>,
+    pub position: Position,
+    /// The loop variable followed by the names the body declares.
+    pub scope: Scope,
+}
+
+impl TNode for ForStatement {
+    fn token_literal(&self) -> String {
+        "for".into()
+    }
+
+    fn string(&self) -> String {
+        let mut out = String::new();
+        out.push_str("for (");
+        out.push_str(&self.name);
+        out.push_str(" in ");
+        out.push_str(&self.iterable.string());
+        out.push_str(") { ");
+        out.push_str(&self.body.string());
+        out.push_str(" }");
+        out
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BreakStatement;
+
+impl TNode for BreakStatement {
+    fn token_literal(&self) -> String {
+        "break".into()
+    }
+
+    fn string(&self) -> String {
+        "break;".into()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContinueStatement;
+
+impl TNode for ContinueStatement {
+    fn token_literal(&self) -> String {
+        "continue".into()
+    }
+
+    fn string(&self) -> String {
+        "continue;".into()
+    }
+}
@@ -0,0 +1,552 @@
+use std::rc::Rc;
+
+use super::{
+    DecodeError, ExpressionTag, PatternTag, StatementTag, DECLARATIONS, MAGIC, MAX_DEPTH,
+    OPERATORS, VERSION,
+};
+use crate::{
+    ast::{
+        expression::{
+            node::{
+                ArrayLiteral, BigIntLiteral, Boolean, CallExpression, ConditionalExpression,
+                FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression,
+                InfixExpression, IntegerLiteral, MatchArm, MatchExpression, NamedArgument,
+                Parameter, PrefixExpression, SpreadExpression, StringLiteral,
+            },
+            ExpressionType,
+        },
+        pattern::{
+            node::{ArrayPattern, HashPattern, HashPatternEntry},
+            Pattern,
+        },
+        statement::{
+            node::{
+                BlockStatement, BreakStatement, CatchClause, ContinueStatement,
+                ExpressionStatement, ForStatement, FunctionDeclaration, LetStatement,
+                ReturnStatement, SwitchCase, SwitchStatement, ThrowStatement, TryStatement,
+                WhileStatement,
+            },
+            StatementType,
+        },
+        Program, Scope, TNode,
+    },
+    lexer::Position,
+    parser::tail_call::mark_tail_calls,
+    token::Token,
+};
+
+/// Reads a program back, leaving the scopes and slots empty as the parser does.
+///
+/// The engines trust the tree they are given, so the decoder rejects anything the parser
+/// would have reported as an error or never builds.
+pub(super) struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    names: Vec<String>,
+    /// How many nodes enclose the one being read.
+    depth: usize,
+    /// Calls read with their tail flag set since the enclosing function started.
+    tail_calls: usize,
+    /// Whether the next expression is an array element or a positional argument, the only
+    /// places a spread may appear.
+    spread_allowed: bool,
+}
+
+type Result<T> = std::result::Result<T, DecodeError>;
+
+impl<'a> Decoder<'a> {
+    pub(super) fn new(bytes: &'a [u8]) -> Self {
+        Decoder {
+            bytes,
+            offset: 0,
+            names: Vec::new(),
+            depth: 0,
+            tail_calls: 0,
+            spread_allowed: false,
+        }
+    }
+
+    pub(super) fn decode(mut self) -> Result<Program> {
+        if self.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(DecodeError::NotEncoded);
+        }
+        let version = u16::from_le_bytes(self.array()?);
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        for _ in 0..self.usize()? {
+            let name = self.string()?;
+            self.names.push(name);
+        }
+        let statements = self.statements()?;
+        if self.tail_calls != 0 {
+            return Err(malformed("tail call outside a function"));
+        }
+
+        if self.offset != self.bytes.len() {
+            return Err(malformed("unexpected bytes after the last statement"));
+        }
+        Ok(Program { statements })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.offset.checked_add(len).ok_or(DecodeError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(DecodeError::Truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            byte => Err(malformed(format!("invalid flag {}", byte))),
+        }
+    }
+
+    fn usize(&mut self) -> Result<usize> {
+        let mut value = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as usize;
+            if bits.checked_shl(shift).map(|shifted| shifted >> shift) != Some(bits) {
+                break;
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(malformed("number too large"))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.usize()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| malformed("invalid UTF-8 in a string"))
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let index = self.usize()?;
+        self.names
+            .get(index)
+            .cloned()
+            .ok_or_else(|| malformed(format!("identifier {} is not in the table", index)))
+    }
+
+    fn optional_name(&mut self) -> Result<Option<String>> {
+        self.optional(Self::name)
+    }
+
+    fn optional<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        match self.bool()? {
+            true => read(self).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    /// Reads a count followed by that many items.
+    fn list<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.usize()?;
+        // Every item takes at least a byte, which keeps a corrupt count from allocating.
+        if len > self.bytes.len() - self.offset {
+            return Err(DecodeError::Truncated);
+        }
+        let mut items = Vec::with_capacity(len);
+        for _ in 0..len {
+            items.push(read(self)?);
+        }
+        Ok(items)
+    }
+
+    fn position(&mut self) -> Result<Position> {
+        Ok(Position {
+            line: self.usize()?,
+            column: self.usize()?,
+        })
+    }
+
+    fn operator(&mut self) -> Result<Token> {
+        let index = self.usize()?;
+        OPERATORS
+            .get(index)
+            .cloned()
+            .ok_or_else(|| malformed(format!("unknown operator {}", index)))
+    }
+
+    fn prefix_operator(&mut self) -> Result<Token> {
+        match self.operator()? {
+            operator @ (Token::BANG | Token::MINUS) => Ok(operator),
+            operator => Err(malformed(format!(
+                "{} is not a prefix operator",
+                operator.to_string()
+            ))),
+        }
+    }
+
+    fn infix_operator(&mut self) -> Result<Token> {
+        match self.operator()? {
+            Token::BANG => Err(malformed("! is not an infix operator")),
+            operator => Ok(operator),
+        }
+    }
+
+    /// Reads a call's tail flag. The flag is recomputed once the enclosing function is
+    /// read, and `function` checks that the same number of calls were set.
+    fn tail_flag(&mut self) -> Result<bool> {
+        if self.bool()? {
+            self.tail_calls += 1;
+        }
+        Ok(false)
+    }
+
+    /// Counts a level of nesting, to be undone by `leave` once the node is read.
+    fn enter(&mut self) -> Result<()> {
+        if self.depth == MAX_DEPTH {
+            return Err(malformed(format!(
+                "nodes nested more than {} deep",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    // The AST holds its statements and expressions boxed.
+    #[allow(clippy::vec_box)]
+    fn statements(&mut self) -> Result<Vec<Box<StatementType>>> {
+        self.list(Self::statement)
+    }
+
+    fn optional_statement(&mut self) -> Result<Option<Box<StatementType>>> {
+        self.optional(Self::statement)
+    }
+
+    fn statement(&mut self) -> Result<Box<StatementType>> {
+        self.enter()?;
+        let byte = self.byte()?;
+        let tag = StatementTag::from_byte(byte)
+            .ok_or_else(|| malformed(format!("unknown statement tag {}", byte)))?;
+        let stmt = match tag {
+            StatementTag::Let => {
+                let token = match self.usize()? {
+                    0 => None,
+                    declaration => {
+                        Some(DECLARATIONS.get(declaration - 1).cloned().ok_or_else(|| {
+                            malformed(format!("unknown declaration {}", declaration))
+                        })?)
+                    }
+                };
+                let pattern = self.pattern()?;
+                match (&token, &pattern) {
+                    (None, Pattern::Identifier(_)) => {}
+                    (None, _) => return Err(malformed("assignment to a pattern")),
+                    (Some(_), pattern) if pattern.is_refutable() => {
+                        return Err(malformed(format!(
+                            "refutable pattern in let binding: {}",
+                            pattern.string()
+                        )))
+                    }
+                    (Some(_), _) => {}
+                }
+                StatementType::Let(LetStatement {
+                    token,
+                    pattern,
+                    value: self.expression()?,
+                    position: self.position()?,
+                    slot: None,
+                })
+            }
+            StatementTag::Return => StatementType::Return(ReturnStatement {
+                value: self.expression()?,
+            }),
+            StatementTag::Expression => StatementType::Expression(ExpressionStatement {
+                expression: self.expression()?,
+            }),
+            StatementTag::Block => StatementType::Block(BlockStatement {
+                statements: self.statements()?,
+                scope: Scope::default(),
+            }),
+            StatementTag::Function => StatementType::Function(FunctionDeclaration {
+                function: self.function()?,
+            }),
+            StatementTag::While => StatementType::While(WhileStatement {
+                condition: self.expression()?,
+                body: self.statement()?,
+            }),
+            StatementTag::For => StatementType::For(ForStatement {
+                name: self.name()?,
+                iterable: self.expression()?,
+                body: self.statement()?,
+                position: self.position()?,
+                scope: Scope::default(),
+            }),
+            StatementTag::Switch => StatementType::Switch(SwitchStatement {
+                discriminant: self.expression()?,
+                cases: self.list(|decoder| {
+                    Ok(SwitchCase {
+                        test: decoder.optional_expression()?,
+                        body: decoder.statements()?,
+                    })
+                })?,
+                scope: Scope::default(),
+            }),
+            StatementTag::Throw => StatementType::Throw(ThrowStatement {
+                value: self.expression()?,
+                position: self.position()?,
+            }),
+            StatementTag::Try => StatementType::Try(TryStatement {
+                block: self.statement()?,
+                catch: self.optional(|decoder| {
+                    Ok(CatchClause {
+                        name: decoder.optional_name()?,
+                        body: decoder.statement()?,
+                        scope: Scope::default(),
+                    })
+                })?,
+                finally: self.optional_statement()?,
+            }),
+            StatementTag::Break => StatementType::Break(BreakStatement),
+            StatementTag::Continue => StatementType::Continue(ContinueStatement),
+        };
+        self.leave();
+        Ok(Box::new(stmt))
+    }
+
+    /// Reads array elements or positional arguments, which may be spreads.
+    #[allow(clippy::vec_box)]
+    fn elements(&mut self) -> Result<Vec<Box<ExpressionType>>> {
+        self.list(|decoder| {
+            decoder.spread_allowed = true;
+            decoder.expression()
+        })
+    }
+
+    fn optional_expression(&mut self) -> Result<Option<Box<ExpressionType>>> {
+        self.optional(Self::expression)
+    }
+
+    fn expression(&mut self) -> Result<Box<ExpressionType>> {
+        let spread_allowed = std::mem::take(&mut self.spread_allowed);
+        self.enter()?;
+        let byte = self.byte()?;
+        let tag = ExpressionTag::from_byte(byte)
+            .ok_or_else(|| malformed(format!("unknown expression tag {}", byte)))?;
+        let expr = match tag {
+            ExpressionTag::Identifier => ExpressionType::Identifier(Identifier {
+                token: Token::IDENT(self.name()?),
+                position: self.position()?,
+                slot: None,
+            }),
+            ExpressionTag::IntegerLiteral => ExpressionType::IntegerLiteral(IntegerLiteral {
+                token: Token::INT(i64::from_le_bytes(self.array()?)),
+            }),
+            ExpressionTag::BigIntLiteral => ExpressionType::BigIntLiteral(BigIntLiteral {
+                token: Token::BIGINT(self.string()?),
+            }),
+            ExpressionTag::StringLiteral => ExpressionType::StringLiteral(StringLiteral {
+                token: Token::STRING(self.string()?),
+            }),
+            ExpressionTag::Prefix => ExpressionType::Prefix(PrefixExpression {
+                operator: self.prefix_operator()?,
+                right: self.expression()?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Infix => ExpressionType::Infix(InfixExpression {
+                operator: self.infix_operator()?,
+                left: self.expression()?,
+                right: self.expression()?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Boolean => ExpressionType::Boolean(Boolean {
+                token: match self.bool()? {
+                    true => Token::TRUE,
+                    false => Token::FALSE,
+                },
+            }),
+            ExpressionTag::If => ExpressionType::If(IfExpression {
+                condition: self.expression()?,
+                consequence: self.statement()?,
+                alternative: self.optional_statement()?,
+            }),
+            ExpressionTag::Conditional => ExpressionType::Conditional(ConditionalExpression {
+                condition: self.expression()?,
+                consequence: self.expression()?,
+                alternative: self.expression()?,
+            }),
+            ExpressionTag::Fn => ExpressionType::Fn(self.function()?),
+            ExpressionTag::Call => ExpressionType::Call(CallExpression {
+                token: Token::LPAREN,
+                function: self.expression()?,
+                arguments: self.elements()?,
+                named: self.list(|decoder| {
+                    Ok(NamedArgument {
+                        name: decoder.name()?,
+                        value: decoder.expression()?,
+                        position: decoder.position()?,
+                    })
+                })?,
+                position: self.position()?,
+                tail: self.tail_flag()?,
+            }),
+            ExpressionTag::Array => ExpressionType::Array(ArrayLiteral {
+                elements: self.elements()?,
+            }),
+            ExpressionTag::Hash => ExpressionType::Hash(HashLiteral {
+                pairs: self.list(|decoder| Ok((decoder.expression()?, decoder.expression()?)))?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Index => ExpressionType::Index(IndexExpression {
+                left: self.expression()?,
+                index: self.expression()?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Match => ExpressionType::Match(MatchExpression {
+                subject: self.expression()?,
+                arms: self.list(|decoder| {
+                    Ok(MatchArm {
+                        pattern: decoder.pattern()?,
+                        guard: decoder.optional_expression()?,
+                        body: decoder.statement()?,
+                        scope: Scope::default(),
+                    })
+                })?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Spread if spread_allowed => ExpressionType::Spread(SpreadExpression {
+                value: self.expression()?,
+                position: self.position()?,
+            }),
+            ExpressionTag::Spread => {
+                return Err(malformed(
+                    "spread outside an array literal or call arguments",
+                ))
+            }
+        };
+        self.leave();
+        Ok(Box::new(expr))
+    }
+
+    fn function(&mut self) -> Result<FunctionLiteral> {
+        let outer_tail_calls = std::mem::take(&mut self.tail_calls);
+        let name = self.optional_name()?;
+        let parameters = self.list(|decoder| {
+            Ok(Parameter {
+                name: decoder.name()?,
+                default: decoder.optional_expression()?,
+                rest: decoder.bool()?,
+            })
+        })?;
+        for (idx, parameter) in parameters.iter().enumerate() {
+            if parameters[..idx].iter().any(|p| p.name == parameter.name) {
+                return Err(malformed(format!(
+                    "duplicate parameter name {:?}",
+                    parameter.name
+                )));
+            }
+            if parameter.rest && idx + 1 != parameters.len() {
+                return Err(malformed(format!(
+                    "rest parameter {:?} is not last",
+                    parameter.name
+                )));
+            }
+            if parameter.rest && parameter.default.is_some() {
+                return Err(malformed(format!(
+                    "rest parameter {:?} has a default value",
+                    parameter.name
+                )));
+            }
+        }
+
+        let mut body = self.statement()?;
+        if !matches!(*body, StatementType::Block(_)) {
+            return Err(malformed("function body is not a block"));
+        }
+        let tail_calls = std::mem::replace(&mut self.tail_calls, outer_tail_calls);
+        if mark_tail_calls(&mut body) != tail_calls {
+            return Err(malformed("tail calls do not match the function body"));
+        }
+        Ok(FunctionLiteral {
+            name,
+            parameters: parameters.into(),
+            body: Rc::from(body),
+            scope: Scope::default(),
+        })
+    }
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        self.enter()?;
+        let byte = self.byte()?;
+        let tag = PatternTag::from_byte(byte)
+            .ok_or_else(|| malformed(format!("unknown pattern tag {}", byte)))?;
+        let pattern = match tag {
+            PatternTag::Identifier => Pattern::Identifier(self.name()?),
+            PatternTag::Wildcard => Pattern::Wildcard,
+            PatternTag::Literal => {
+                let literal = self.expression()?;
+                if !is_pattern_literal(&literal) {
+                    return Err(malformed(format!(
+                        "{} is not a literal pattern",
+                        literal.string()
+                    )));
+                }
+                Pattern::Literal(literal)
+            }
+            PatternTag::Or => Pattern::Or(self.list(Self::pattern)?),
+            PatternTag::Array => Pattern::Array(ArrayPattern {
+                elements: self.list(Self::pattern)?,
+                rest: self.optional_name()?,
+            }),
+            PatternTag::Hash => Pattern::Hash(HashPattern {
+                entries: self.list(|decoder| {
+                    Ok(HashPatternEntry {
+                        key: decoder.name()?,
+                        value: decoder.pattern()?,
+                    })
+                })?,
+                rest: self.optional_name()?,
+            }),
+        };
+        self.leave();
+        Ok(pattern)
+    }
+}
+
+/// Whether `expr` is one of the literals the parser allows in a pattern: a number,
+/// possibly negated, a string or a boolean.
+fn is_pattern_literal(expr: &ExpressionType) -> bool {
+    match expr {
+        ExpressionType::IntegerLiteral(_)
+        | ExpressionType::BigIntLiteral(_)
+        | ExpressionType::StringLiteral(_)
+        | ExpressionType::Boolean(_) => true,
+        ExpressionType::Prefix(prefix) => {
+            prefix.operator == Token::MINUS
+                && matches!(
+                    *prefix.right,
+                    ExpressionType::IntegerLiteral(_) | ExpressionType::BigIntLiteral(_)
+                )
+        }
+        _ => false,
+    }
+}
+
+fn malformed(message: impl Into<String>) -> DecodeError {
+    DecodeError::Malformed(message.into())
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use super::{ExpressionTag, PatternTag, StatementTag, DECLARATIONS, MAGIC, OPERATORS, VERSION};
+use crate::{
+    ast::{
+        expression::{
+            node::{FunctionLiteral, MatchArm},
+            ExpressionType,
+        },
+        pattern::Pattern,
+        statement::{
+            node::{CatchClause, SwitchCase},
+            StatementType,
+        },
+        Program,
+    },
+    lexer::Position,
+    token::Token,
+};
+
+/// Writes the node stream while collecting the identifier table, which goes in front of
+/// it once every name is known.
+#[derive(Default)]
+pub(super) struct Encoder {
+    out: Vec<u8>,
+    names: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl Encoder {
+    pub(super) fn encode(mut self, program: &Program) -> Vec<u8> {
+        self.statements(&program.statements);
+        let nodes = std::mem::take(&mut self.out);
+
+        self.out.extend(MAGIC);
+        self.out.extend(VERSION.to_le_bytes());
+        self.usize(self.names.len());
+        for name in std::mem::take(&mut self.names) {
+            self.string(&name);
+        }
+        self.out.extend(nodes);
+        self.out
+    }
+
+    fn byte(&mut self, byte: u8) {
+        self.out.push(byte);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.byte(value as u8);
+    }
+
+    fn usize(&mut self, mut value: usize) {
+        while value >= 0x80 {
+            self.byte(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.byte(value as u8);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.usize(value.len());
+        self.out.extend(value.as_bytes());
+    }
+
+    fn name(&mut self, name: &str) {
+        let index = match self.indexes.get(name) {
+            Some(&index) => index,
+            None => {
+                self.names.push(name.to_string());
+                self.indexes.insert(name.to_string(), self.names.len() - 1);
+                self.names.len() - 1
+            }
+        };
+        self.usize(index);
+    }
+
+    fn optional_name(&mut self, name: &Option<String>) {
+        self.bool(name.is_some());
+        if let Some(name) = name {
+            self.name(name);
+        }
+    }
+
+    fn position(&mut self, position: Position) {
+        self.usize(position.line);
+        self.usize(position.column);
+    }
+
+    fn operator(&mut self, operator: &Token) {
+        let index = OPERATORS
+            .iter()
+            .position(|op| op == operator)
+            .unwrap_or_else(|| panic!("not an operator: {:?}", operator));
+        self.usize(index);
+    }
+
+    fn statements(&mut self, stmts: &[Box<StatementType>]) {
+        self.usize(stmts.len());
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+    }
+
+    fn optional_statement(&mut self, stmt: &Option<Box<StatementType>>) {
+        self.bool(stmt.is_some());
+        if let Some(stmt) = stmt {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &StatementType) {
+        match stmt {
+            StatementType::Let(let_stmt) => {
+                self.byte(StatementTag::Let as u8);
+                let declaration = match &let_stmt.token {
+                    Some(token) => {
+                        DECLARATIONS
+                            .iter()
+                            .position(|keyword| keyword == token)
+                            .unwrap_or_else(|| panic!("not a declaration: {:?}", token))
+                            + 1
+                    }
+                    None => 0,
+                };
+                self.usize(declaration);
+                self.pattern(&let_stmt.pattern);
+                self.expression(&let_stmt.value);
+                self.position(let_stmt.position);
+            }
+            StatementType::Return(rs) => {
+                self.byte(StatementTag::Return as u8);
+                self.expression(&rs.value);
+            }
+            StatementType::Expression(es) => {
+                self.byte(StatementTag::Expression as u8);
+                self.expression(&es.expression);
+            }
+            StatementType::Block(block) => {
+                self.byte(StatementTag::Block as u8);
+                self.statements(&block.statements);
+            }
+            StatementType::Function(decl) => {
+                self.byte(StatementTag::Function as u8);
+                self.function(&decl.function);
+            }
+            StatementType::While(ws) => {
+                self.byte(StatementTag::While as u8);
+                self.expression(&ws.condition);
+                self.statement(&ws.body);
+            }
+            StatementType::For(fs) => {
+                self.byte(StatementTag::For as u8);
+                self.name(&fs.name);
+                self.expression(&fs.iterable);
+                self.statement(&fs.body);
+                self.position(fs.position);
+            }
+            StatementType::Switch(ss) => {
+                self.byte(StatementTag::Switch as u8);
+                self.expression(&ss.discriminant);
+                self.usize(ss.cases.len());
+                for SwitchCase { test, body } in &ss.cases {
+                    self.optional_expression(test);
+                    self.statements(body);
+                }
+            }
+            StatementType::Throw(ts) => {
+                self.byte(StatementTag::Throw as u8);
+                self.expression(&ts.value);
+                self.position(ts.position);
+            }
+            StatementType::Try(ts) => {
+                self.byte(StatementTag::Try as u8);
+                self.statement(&ts.block);
+                self.bool(ts.catch.is_some());
+                if let Some(CatchClause { name, body, .. }) = &ts.catch {
+                    self.optional_name(name);
+                    self.statement(body);
+                }
+                self.optional_statement(&ts.finally);
+            }
+            StatementType::Break(_) => self.byte(StatementTag::Break as u8),
+            StatementType::Continue(_) => self.byte(StatementTag::Continue as u8),
+        }
+    }
+
+    fn expressions(&mut self, exprs: &[Box<ExpressionType>]) {
+        self.usize(exprs.len());
+        for expr in exprs {
+            self.expression(expr);
+        }
+    }
+
+    fn optional_expression(&mut self, expr: &Option<Box<ExpressionType>>) {
+        self.bool(expr.is_some());
+        if let Some(expr) = expr {
+            self.expression(expr);
+        }
+    }
+
+    fn expression(&mut self, expr: &ExpressionType) {
+        match expr {
+            ExpressionType::Identifier(ident) => {
+                self.byte(ExpressionTag::Identifier as u8);
+                self.name(&ident.token.to_string());
+                self.position(ident.position);
+            }
+            ExpressionType::IntegerLiteral(literal) => {
+                self.byte(ExpressionTag::IntegerLiteral as u8);
+                let value = *literal
+                    .token
+                    .as_int()
+                    .expect("failed to convert token to int");
+                self.out.extend(value.to_le_bytes());
+            }
+            ExpressionType::BigIntLiteral(literal) => {
+                self.byte(ExpressionTag::BigIntLiteral as u8);
+                let digits = literal
+                    .token
+                    .as_bigint()
+                    .expect("failed to convert token to bigint");
+                self.string(digits);
+            }
+            ExpressionType::StringLiteral(literal) => {
+                self.byte(ExpressionTag::StringLiteral as u8);
+                let value = literal
+                    .token
+                    .as_string()
+                    .expect("failed to convert token to string");
+                self.string(value);
+            }
+            ExpressionType::Prefix(prefix) => {
+                self.byte(ExpressionTag::Prefix as u8);
+                self.operator(&prefix.operator);
+                self.expression(&prefix.right);
+                self.position(prefix.position);
+            }
+            ExpressionType::Infix(infix) => {
+                self.byte(ExpressionTag::Infix as u8);
+                self.operator(&infix.operator);
+                self.expression(&infix.left);
+                self.expression(&infix.right);
+                self.position(infix.position);
+            }
+            ExpressionType::Boolean(boolean) => {
+                self.byte(ExpressionTag::Boolean as u8);
+                self.bool(boolean.token == Token::TRUE);
+            }
+            ExpressionType::If(ie) => {
+                self.byte(ExpressionTag::If as u8);
+                self.expression(&ie.condition);
+                self.statement(&ie.consequence);
+                self.optional_statement(&ie.alternative);
+            }
+            ExpressionType::Conditional(ce) => {
+                self.byte(ExpressionTag::Conditional as u8);
+                self.expression(&ce.condition);
+                self.expression(&ce.consequence);
+                self.expression(&ce.alternative);
+            }
+            ExpressionType::Fn(function) => {
+                self.byte(ExpressionTag::Fn as u8);
+                self.function(function);
+            }
+            ExpressionType::Call(call) => {
+                self.byte(ExpressionTag::Call as u8);
+                self.expression(&call.function);
+                self.expressions(&call.arguments);
+                self.usize(call.named.len());
+                for argument in &call.named {
+                    self.name(&argument.name);
+                    self.expression(&argument.value);
+                    self.position(argument.position);
+                }
+                self.position(call.position);
+                self.bool(call.tail);
+            }
+            ExpressionType::Array(array) => {
+                self.byte(ExpressionTag::Array as u8);
+                self.expressions(&array.elements);
+            }
+            ExpressionType::Hash(hash) => {
+                self.byte(ExpressionTag::Hash as u8);
+                self.usize(hash.pairs.len());
+                for (key, value) in &hash.pairs {
+                    self.expression(key);
+                    self.expression(value);
+                }
+                self.position(hash.position);
+            }
+            ExpressionType::Index(index) => {
+                self.byte(ExpressionTag::Index as u8);
+                self.expression(&index.left);
+                self.expression(&index.index);
+                self.position(index.position);
+            }
+            ExpressionType::Match(me) => {
+                self.byte(ExpressionTag::Match as u8);
+                self.expression(&me.subject);
+                self.usize(me.arms.len());
+                for MatchArm {
+                    pattern,
+                    guard,
+                    body,
+                    ..
+                } in &me.arms
+                {
+                    self.pattern(pattern);
+                    self.optional_expression(guard);
+                    self.statement(body);
+                }
+                self.position(me.position);
+            }
+            ExpressionType::Spread(spread) => {
+                self.byte(ExpressionTag::Spread as u8);
+                self.expression(&spread.value);
+                self.position(spread.position);
+            }
+        }
+    }
+
+    fn function(&mut self, function: &FunctionLiteral) {
+        self.optional_name(&function.name);
+        self.usize(function.parameters.len());
+        for param in function.parameters.iter() {
+            self.name(&param.name);
+            self.optional_expression(&param.default);
+            self.bool(param.rest);
+        }
+        self.statement(&function.body);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name) => {
+                self.byte(PatternTag::Identifier as u8);
+                self.name(name);
+            }
+            Pattern::Wildcard => self.byte(PatternTag::Wildcard as u8),
+            Pattern::Literal(literal) => {
+                self.byte(PatternTag::Literal as u8);
+                self.expression(literal);
+            }
+            Pattern::Or(alternatives) => {
+                self.byte(PatternTag::Or as u8);
+                self.usize(alternatives.len());
+                for alternative in alternatives {
+                    self.pattern(alternative);
+                }
+            }
+            Pattern::Array(array) => {
+                self.byte(PatternTag::Array as u8);
+                self.usize(array.elements.len());
+                for element in &array.elements {
+                    self.pattern(element);
+                }
+                self.optional_name(&array.rest);
+            }
+            Pattern::Hash(hash) => {
+                self.byte(PatternTag::Hash as u8);
+                self.usize(hash.entries.len());
+                for entry in &hash.entries {
+                    self.name(&entry.key);
+                    self.pattern(&entry.value);
+                }
+                self.optional_name(&hash.rest);
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! A binary encoding of parsed programs, which loads without lexing or parsing.
+//!
+//! An encoded program is laid out as:
+//!
+//! - `MAGIC`, then `VERSION` as a little-endian `u16`;
+//! - the identifier table: a count followed by each name as a length and UTF-8 bytes;
+//! - the node stream: a count followed by the program's statements.
+//!
+//! Each node starts with a tag byte naming its variant, followed by its fields in
+//! declaration order. Counts, lengths, positions and table indexes are unsigned LEB128
+//! varints, integer literals are little-endian `i64`s and options are a 0 or 1 byte in
+//! front of the value. Names refer to the identifier table by index, so each is stored
+//! once however often it is used.
+//!
+//! Only what the parser produces is stored: the scopes and slots the resolver fills in
+//! are computed again once the program is loaded into the environment it runs in.
+
+mod decode;
+mod encode;
+pub mod test;
+
+use std::fmt;
+
+use crate::{ast::Program, token::Token};
+
+/// The first bytes of every encoded program. The leading NUL can never start a source
+/// file the lexer accepts.
+pub const MAGIC: [u8; 4] = *b"\0RNS";
+
+/// The format version this build writes and reads. Bump it whenever the layout of the
+/// node stream changes, so programs encoded by other builds are rejected instead of
+/// misread.
+pub const VERSION: u16 = 2;
+
+/// Encodes a parsed program.
+pub fn encode(program: &Program) -> Vec<u8> {
+    encode::Encoder::default().encode(program)
+}
+
+/// Decodes a program written by `encode`, as the parser would have produced it.
+pub fn decode(bytes: &[u8]) -> Result<Program, DecodeError> {
+    decode::Decoder::new(bytes).decode()
+}
+
+/// Whether `bytes` hold an encoded program rather than source.
+pub fn is_encoded(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Why a program could not be decoded.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    /// The bytes do not start with `MAGIC`.
+    NotEncoded,
+    /// The program was encoded by a build using another format version.
+    UnsupportedVersion(u16),
+    /// The bytes end in the middle of the program.
+    Truncated,
+    /// The bytes hold something `encode` never writes.
+    Malformed(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NotEncoded => write!(f, "not a compiled program"),
+            DecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "compiled with format version {}, but this build reads version {}; \
+                 compile the source again",
+                version, VERSION
+            ),
+            DecodeError::Truncated => write!(f, "compiled program is truncated"),
+            DecodeError::Malformed(message) => {
+                write!(f, "compiled program is corrupt: {}", message)
+            }
+        }
+    }
+}
+
+/// Declares the tags that start the nodes of one kind, numbered in order.
+macro_rules! tags {
+    ($(#[$doc:meta])* $tag:ident { $($name:ident,)* }) => {
+        $(#[$doc])*
+        #[repr(u8)]
+        #[derive(Debug, Clone, Copy)]
+        enum $tag {
+            $($name,)*
+        }
+
+        impl $tag {
+            const ALL: &'static [$tag] = &[$($tag::$name,)*];
+
+            fn from_byte(byte: u8) -> Option<$tag> {
+                Self::ALL.get(byte as usize).copied()
+            }
+        }
+    };
+}
+
+tags! {
+    StatementTag {
+        Let,
+        Return,
+        Expression,
+        Block,
+        Function,
+        While,
+        For,
+        Switch,
+        Throw,
+        Try,
+        Break,
+        Continue,
+    }
+}
+
+tags! {
+    ExpressionTag {
+        Identifier,
+        IntegerLiteral,
+        BigIntLiteral,
+        StringLiteral,
+        Prefix,
+        Infix,
+        Boolean,
+        If,
+        Conditional,
+        Fn,
+        Call,
+        Array,
+        Hash,
+        Index,
+        Match,
+        Spread,
+    }
+}
+
+tags! {
+    PatternTag {
+        Identifier,
+        Wildcard,
+        Literal,
+        Or,
+        Array,
+        Hash,
+    }
+}
+
+/// The operators of prefix and infix expressions, stored as their index here.
+const OPERATORS: &[Token] = &[
+    Token::PLUS,
+    Token::MINUS,
+    Token::BANG,
+    Token::ASTERISK,
+    Token::SLASH,
+    Token::GT,
+    Token::LT,
+    Token::EQ,
+    Token::NOT_EQ,
+    Token::DOTDOT,
+    Token::DOTDOT_EQ,
+];
+
+/// How deeply nodes may nest in an encoded program. Decoding recurses once per level, so
+/// a corrupt file cannot exhaust the stack with endless nesting.
+const MAX_DEPTH: usize = 1024;
+
+/// The keywords that start a declaration, stored as their index here plus one, with 0
+/// for a reassignment.
+const DECLARATIONS: &[Token] = &[Token::LET, Token::CONST];
@@ -0,0 +1,298 @@
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::{
+        ast::{
+            expression::{
+                node::{FunctionLiteral, Identifier, IntegerLiteral},
+                ExpressionType,
+            },
+            pattern::Pattern,
+            statement::StatementType,
+            Program, TNode,
+        },
+        binary::{
+            decode, encode, is_encoded, DecodeError, ExpressionTag, MAGIC, MAX_DEPTH, VERSION,
+        },
+        evaluator::{eval, Context},
+        lexer::Lexer,
+        object::environment::Environment,
+        parser::Parser,
+        resolver::resolve,
+        token::Token,
+    };
+
+    const PROGRAM: &str = r#"
+        let total = 0;
+        const limit = 5;
+        fn add(a, b = 2, ...rest) { a + b }
+        let twice = x => x * 2;
+        let [first, ...others] = [1, 2, 3];
+        let {name, age: years, ...extra} = {"name": "ada", "age": 36, "x": true};
+        for (i in 0..=limit) {
+            if (i == 2) { continue; } else if (i > 4) { break; }
+            total = total + twice(i);
+        }
+        while (false) {}
+        switch (total) { case 1: total = 0; default: total = total + 1; }
+        try { throw "oops"; } catch (e) { total = total + 1; } finally { total; }
+        try { 1 / 0; } catch { -1 }
+        let label = match ([first, years]) {
+            [1, 36] if (true) => "ok",
+            [_, -1] | [_, 0] => "no",
+            _ => { "other" },
+        };
+        fn count(n) { n == 0 ? 0 : count(n - 1) }
+        [total, add(1), add(b: 3, a: 1), add(...others), label, others[0], extra.x, !false, 12345678901234567890n, count(3)]
+    "#;
+
+    #[test]
+    fn test_round_trip() {
+        let program = parse(PROGRAM);
+        let bytes = encode(&program);
+        assert!(is_encoded(&bytes));
+        assert!(!is_encoded(PROGRAM.as_bytes()));
+
+        let decoded = decode(&bytes).unwrap_or_else(|err| panic!("decode failed: {}", err));
+        assert_eq!(decoded.statements, program.statements);
+        assert_eq!(decoded.string(), program.string());
+    }
+
+    #[test]
+    fn test_decoded_program_runs() {
+        let expected = run(parse(PROGRAM));
+        let decoded = decode(&encode(&parse(PROGRAM))).unwrap();
+        assert_eq!(run(decoded), expected);
+    }
+
+    #[test]
+    fn test_header() {
+        let bytes = encode(&parse("let abc = 1; abc + abc"));
+        assert_eq!(&bytes[..4], &MAGIC);
+        assert_eq!(&bytes[4..6], &VERSION.to_le_bytes());
+        // The identifier table holds `abc` once, and the node stream refers to it.
+        assert_eq!(&bytes[6..11], &[1, 3, b'a', b'b', b'c']);
+        assert_eq!(bytes.windows(3).filter(|w| w == b"abc").count(), 1);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = encode(&parse(PROGRAM));
+
+        assert_eq!(decode(b"let x = 1;").err(), Some(DecodeError::NotEncoded));
+        assert_eq!(decode(&bytes[..2]).err(), Some(DecodeError::NotEncoded));
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let err = decode(&newer).unwrap_err();
+        assert_eq!(err, DecodeError::UnsupportedVersion(VERSION + 1));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "compiled with format version {}, but this build reads version {}; \
+                 compile the source again",
+                VERSION + 1,
+                VERSION
+            )
+        );
+
+        // Every prefix of a program fails cleanly rather than decoding or panicking.
+        for len in MAGIC.len()..bytes.len() {
+            assert_eq!(
+                decode(&bytes[..len]).err(),
+                Some(DecodeError::Truncated),
+                "{}",
+                len
+            );
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(decode(&trailing), Err(DecodeError::Malformed(_))));
+
+        // The header, an empty identifier table and a count of one statement come first.
+        let mut unknown_tag = encode(&parse("1"));
+        unknown_tag[8] = 0xff;
+        assert_eq!(
+            decode(&unknown_tag).err(),
+            Some(DecodeError::Malformed("unknown statement tag 255".into()))
+        );
+    }
+
+    #[test]
+    fn test_decode_rejects_trees_the_parser_never_builds() {
+        // The operator of `1 + 2` replaced by `!`, and that of `-1` by `+`.
+        let mut infix = encode(&parse("1 + 2"));
+        infix[10] = 2;
+        assert_eq!(
+            decode(&infix).err(),
+            Some(DecodeError::Malformed("! is not an infix operator".into()))
+        );
+        let mut prefix = encode(&parse("-1"));
+        prefix[10] = 0;
+        assert_eq!(
+            decode(&prefix).err(),
+            Some(DecodeError::Malformed("+ is not a prefix operator".into()))
+        );
+
+        let duplicate = encode_function("fn f(a, b) { a }", |function| {
+            let mut parameters = function.parameters.to_vec();
+            parameters[1].name = "a".into();
+            function.parameters = parameters.into();
+        });
+        assert_eq!(
+            decode(&duplicate).err(),
+            Some(DecodeError::Malformed(
+                "duplicate parameter name \"a\"".into()
+            ))
+        );
+
+        let rest_first = encode_function("fn f(a, ...b) { a }", |function| {
+            let mut parameters = function.parameters.to_vec();
+            parameters.reverse();
+            function.parameters = parameters.into();
+        });
+        assert_eq!(
+            decode(&rest_first).err(),
+            Some(DecodeError::Malformed(
+                "rest parameter \"b\" is not last".into()
+            ))
+        );
+
+        let mut refutable = parse("let x = 1;");
+        let StatementType::Let(let_stmt) = &mut *refutable.statements[0] else {
+            panic!("expected a let statement");
+        };
+        let_stmt.pattern =
+            Pattern::Literal(Box::new(ExpressionType::IntegerLiteral(IntegerLiteral {
+                token: Token::INT(1),
+            })));
+        assert_eq!(
+            decode(&encode(&refutable)).err(),
+            Some(DecodeError::Malformed(
+                "refutable pattern in let binding: 1".into()
+            ))
+        );
+
+        let mut not_literal = parse("match (1) { 1 => 2 }");
+        let StatementType::Expression(stmt) = &mut *not_literal.statements[0] else {
+            panic!("expected an expression statement");
+        };
+        let ExpressionType::Match(match_expr) = &mut *stmt.expression else {
+            panic!("expected a match expression");
+        };
+        match_expr.arms[0].pattern =
+            Pattern::Literal(Box::new(ExpressionType::Identifier(Identifier {
+                token: Token::IDENT("x".into()),
+                position: Default::default(),
+                slot: None,
+            })));
+        assert_eq!(
+            decode(&encode(&not_literal)).err(),
+            Some(DecodeError::Malformed("x is not a literal pattern".into()))
+        );
+
+        // `[...xs]` with the spread taken out of the array.
+        let mut spread = parse("[...xs]");
+        let StatementType::Expression(stmt) = &mut *spread.statements[0] else {
+            panic!("expected an expression statement");
+        };
+        let ExpressionType::Array(array) = &mut *stmt.expression else {
+            panic!("expected an array literal");
+        };
+        stmt.expression = array.elements.remove(0);
+        assert_eq!(
+            decode(&encode(&spread)).err(),
+            Some(DecodeError::Malformed(
+                "spread outside an array literal or call arguments".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_checks_tail_calls() {
+        let not_tail = encode_function("fn f(n) { g(n); 1 }", |function| {
+            let body = Rc::get_mut(&mut function.body).unwrap();
+            let StatementType::Block(block) = body else {
+                panic!("expected a block");
+            };
+            set_tail(&mut block.statements[0]);
+        });
+        assert_eq!(
+            decode(&not_tail).err(),
+            Some(DecodeError::Malformed(
+                "tail calls do not match the function body".into()
+            ))
+        );
+
+        let mut top_level = parse("g(1)");
+        set_tail(&mut top_level.statements[0]);
+        assert_eq!(
+            decode(&encode(&top_level)).err(),
+            Some(DecodeError::Malformed(
+                "tail call outside a function".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_limits_nesting() {
+        // `- - - ... 1` nested past the limit, which fails before the operand is read.
+        let mut nested = encode(&parse("1"));
+        nested.truncate(9);
+        for _ in 0..MAX_DEPTH {
+            nested.extend([ExpressionTag::Prefix as u8, 1]);
+        }
+        // The limit fits in the stack the CLI decodes on.
+        let err = std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || decode(&nested).err())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(
+            err,
+            Some(DecodeError::Malformed(format!(
+                "nodes nested more than {} deep",
+                MAX_DEPTH
+            )))
+        );
+    }
+
+    /// Encodes the program `input` after `change` has edited the function it declares first.
+    fn encode_function(input: &str, change: impl FnOnce(&mut FunctionLiteral)) -> Vec<u8> {
+        let mut program = parse(input);
+        let StatementType::Function(declaration) = &mut *program.statements[0] else {
+            panic!("expected a function declaration");
+        };
+        change(&mut declaration.function);
+        encode(&program)
+    }
+
+    /// Sets the tail flag of the call `stmt` evaluates.
+    fn set_tail(stmt: &mut StatementType) {
+        let StatementType::Expression(stmt) = stmt else {
+            panic!("expected an expression statement");
+        };
+        let ExpressionType::Call(call) = &mut *stmt.expression else {
+            panic!("expected a call");
+        };
+        call.tail = true;
+    }
+
+    fn parse(input: &str) -> Program {
+        Parser::new(Lexer::new(input.to_string()))
+            .parse_program()
+            .unwrap_or_else(|err| panic!("parse_program() returned an error: {}", err))
+    }
+
+    fn run(program: Program) -> String {
+        let env = Environment::new();
+        let program = resolve(program, &env).unwrap();
+        match eval(&program, env, &mut Context::new()) {
+            Ok(value) => value.inspect(),
+            Err(err) => err.to_string(),
+        }
+    }
+}
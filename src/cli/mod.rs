@@ -0,0 +1,265 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::Path,
+    process::ExitCode,
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ast::Program,
+    binary,
+    compiler::compile,
+    evaluator::{eval, Context, RuntimeError},
+    lexer::Lexer,
+    object::{environment::Environment, Object},
+    parser::Parser,
+    repl::run_repl,
+    resolver::{resolve, ResolvedProgram},
+    vm,
+};
+
+const USAGE: &str = "usage: rinonguci-script [run] [--engine evaluator|vm] [--max-call-depth N] \
+[--max-steps N] [--timeout MS] [--max-string-len N] [--max-collection-len N] \
+[--max-bigint-bytes N] [--backtrace-depth N] [FILE]
+       rinonguci-script compile FILE [-o OUTPUT]";
+
+/// The extension `compile` gives its output unless told otherwise.
+const COMPILED_EXTENSION: &str = "rsc";
+
+/// What runs programs. Both give the same results and errors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Engine {
+    /// Walks the syntax tree.
+    #[default]
+    Evaluator,
+    /// Compiles to bytecode and runs it on the virtual machine.
+    Vm,
+}
+
+impl Engine {
+    /// Runs a program in the environment it was resolved against.
+    pub fn run(
+        self,
+        program: ResolvedProgram,
+        env: Rc<RefCell<Environment>>,
+        ctx: &mut Context,
+    ) -> Result<Object, RuntimeError> {
+        match self {
+            Engine::Evaluator => eval(&program, env, ctx),
+            Engine::Vm => vm::run(Rc::new(compile(&program)), env, ctx),
+        }
+    }
+}
+
+impl FromStr for Engine {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "evaluator" => Ok(Engine::Evaluator),
+            "vm" => Ok(Engine::Vm),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Runs `FILE` when one is given and the REPL otherwise. `compile` instead writes `FILE`
+/// in the binary program format, and `run`, the default command, may be spelled out.
+pub fn run(args: impl Iterator<Item = String>) -> ExitCode {
+    let mut args = args.peekable();
+    match args.peek().map(String::as_str) {
+        Some("compile") => compile_command(args.skip(1)),
+        Some("run") => run_command(args.skip(1)),
+        _ => run_command(args),
+    }
+}
+
+fn run_command(mut args: impl Iterator<Item = String>) -> ExitCode {
+    let mut ctx = Context::new();
+    let mut engine = Engine::default();
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => match args.next().and_then(|name| name.parse().ok()) {
+                Some(name) => engine = name,
+                None => return usage_error("--engine expects evaluator or vm"),
+            },
+            "--max-call-depth" => match number(&arg, args.next()) {
+                Ok(depth) => ctx.limits.max_call_depth = depth,
+                Err(code) => return code,
+            },
+            "--max-steps" => match number(&arg, args.next()) {
+                Ok(steps) => ctx.limits.max_steps = Some(steps),
+                Err(code) => return code,
+            },
+            "--timeout" => match number(&arg, args.next()) {
+                Ok(millis) => {
+                    ctx.limits.deadline = Some(Instant::now() + Duration::from_millis(millis))
+                }
+                Err(code) => return code,
+            },
+            "--max-string-len" => match number(&arg, args.next()) {
+                Ok(len) => ctx.limits.max_string_len = Some(len),
+                Err(code) => return code,
+            },
+            "--max-collection-len" => match number(&arg, args.next()) {
+                Ok(len) => ctx.limits.max_collection_len = Some(len),
+                Err(code) => return code,
+            },
+            "--max-bigint-bytes" => match number(&arg, args.next()) {
+                Ok(bytes) => ctx.limits.max_bigint_bytes = bytes,
+                Err(code) => return code,
+            },
+            "--backtrace-depth" => match number(&arg, args.next()) {
+                Ok(depth) => ctx.max_backtrace_depth = depth,
+                Err(code) => return code,
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => return usage_error(&format!("unknown option {}", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return usage_error("expected at most one file"),
+        }
+    }
+
+    match path {
+        Some(path) => run_file(&path, ctx, engine),
+        None => {
+            run_repl(ctx, engine);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Runs a script and prints its final value unless it is `null`.
+fn run_file(path: &str, mut ctx: Context, engine: Engine) -> ExitCode {
+    let env = Environment::new();
+    let program = match load(path).and_then(|program| resolve_program(program, &env)) {
+        Ok(program) => program,
+        Err(code) => return code,
+    };
+
+    match engine.run(program, env, &mut ctx) {
+        Ok(value) => {
+            if !value.is_null() {
+                println!("{}", value.inspect());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {}", err.report());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn compile_command(mut args: impl Iterator<Item = String>) -> ExitCode {
+    let mut path = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => match args.next() {
+                Some(file) => output = Some(file),
+                None => return usage_error(&format!("{} expects a file", arg)),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => return usage_error(&format!("unknown option {}", arg)),
+            _ if path.is_none() => path = Some(arg),
+            _ => return usage_error("expected one file"),
+        }
+    }
+
+    match path {
+        Some(path) => {
+            let output = output.unwrap_or_else(|| {
+                Path::new(&path)
+                    .with_extension(COMPILED_EXTENSION)
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            compile_file(&path, &output)
+        }
+        None => usage_error("compile expects a file"),
+    }
+}
+
+/// Writes a script in the binary program format, once it is known to resolve as it
+/// would when run.
+fn compile_file(path: &str, output: &str) -> ExitCode {
+    let env = Environment::new();
+    let program = match load(path).and_then(|program| resolve_program(program, &env)) {
+        Ok(program) => program,
+        Err(code) => return code,
+    };
+
+    match fs::write(output, binary::encode(&program)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: cannot write {}: {}", output, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Reads a script, decoding it when it was compiled and parsing it otherwise.
+fn load(path: &str) -> Result<Program, ExitCode> {
+    let bytes = fs::read(path).map_err(|err| {
+        eprintln!("error: cannot read {}: {}", path, err);
+        ExitCode::FAILURE
+    })?;
+
+    if binary::is_encoded(&bytes) {
+        return binary::decode(&bytes).map_err(|err| {
+            eprintln!("error: cannot load {}: {}", path, err);
+            ExitCode::FAILURE
+        });
+    }
+
+    let input = String::from_utf8(bytes).map_err(|_| {
+        eprintln!(
+            "error: cannot read {}: stream did not contain valid UTF-8",
+            path
+        );
+        ExitCode::FAILURE
+    })?;
+    Parser::new(Lexer::new(input))
+        .parse_program()
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        })
+}
+
+fn resolve_program(
+    program: Program,
+    env: &Rc<RefCell<Environment>>,
+) -> Result<ResolvedProgram, ExitCode> {
+    resolve(program, env).map_err(|errors| {
+        for err in errors {
+            eprintln!("error: {}", err.report());
+        }
+        ExitCode::FAILURE
+    })
+}
+
+/// Parses the value following `option`, reporting a usage error when it is not a number.
+fn number<T: FromStr>(option: &str, value: Option<String>) -> Result<T, ExitCode> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| usage_error(&format!("{} expects a number", option)))
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("error: {}\n{}", message, USAGE);
+    ExitCode::from(2)
+}
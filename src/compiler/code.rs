@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+/// Declares the opcodes together with the byte width of each of their operands.
+macro_rules! opcodes {
+    ($($(#[$doc:meta])* $name:ident($($width:expr),*),)*) => {
+        /// One virtual machine instruction. It is encoded as its byte followed by its
+        /// operands, little-endian, in the widths `operand_widths` lists.
+        #[repr(u8)]
+        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        pub enum Opcode {
+            $($(#[$doc])* $name,)*
+        }
+
+        impl Opcode {
+            const ALL: &'static [Opcode] = &[$(Opcode::$name,)*];
+
+            #[inline]
+            pub fn from_byte(byte: u8) -> Option<Opcode> {
+                Self::ALL.get(byte as usize).copied()
+            }
+
+            #[inline]
+            pub fn operand_widths(self) -> &'static [usize] {
+                match self {
+                    $(Opcode::$name => &[$($width),*],)*
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    /// Pushes the constant at `[index]`.
+    Constant(4),
+    Null(),
+    True(),
+    False(),
+    Pop(),
+
+    // Operators pop their operands and push the result.
+    Add(),
+    Sub(),
+    Mul(),
+    Div(),
+    Eq(),
+    NotEq(),
+    Lt(),
+    Gt(),
+    Range(),
+    RangeInclusive(),
+    Not(),
+    Negate(),
+
+    /// Continues at `[target]`.
+    Jump(4),
+    /// Pops a value and continues at `[target]` when it is falsy.
+    JumpIfFalse(4),
+
+    /// Pushes the variable in slot `[depth, index]`.
+    GetVar(2, 2),
+    /// Fails unless the variable in slot `[depth, index]` is declared, before it is
+    /// reassigned.
+    CheckVar(2, 2),
+    /// Stores the value on top of the stack in slot `[depth, index]`, leaving it there.
+    SetVar(2, 2),
+    /// Declares `[name]` in the current scope with the value on top of the stack, leaving
+    /// it there. `[mutable]` is 0 for a constant.
+    Define(4, 1),
+    /// Binds the pattern at `[index]` to the value on top of the stack like `Define`.
+    Destructure(4, 1),
+
+    /// Pushes the local `[index]`.
+    GetLocal(2),
+    /// Fails unless the local `[index]` is declared, before it is reassigned.
+    CheckLocal(2),
+    /// Stores the value on top of the stack in the local `[index]`, leaving it there.
+    SetLocal(2),
+    /// Declares the local `[index]` like `Define`.
+    DefineLocal(2, 1),
+    /// Undeclares the `[count]` locals from `[index]` when their scope is entered.
+    ClearLocals(2, 2),
+
+    /// Enters a new scope declaring the names at `[index]`.
+    EnterScope(4),
+    LeaveScope(),
+
+    /// Pushes a closure of the function at `[index]` over the current scope.
+    Closure(4),
+    /// Calls the function below `[count]` arguments.
+    Call(4),
+    /// Calls the function below an array holding its arguments.
+    CallSpread(),
+    /// Like `Call`, but the call replaces the current one.
+    TailCall(4),
+    TailCallSpread(),
+    /// Calls the function below an array of positional arguments and the values of the
+    /// named arguments at `[names]`.
+    CallNamed(4),
+    TailCallNamed(4),
+    /// Continues at `[target]` when the call passed an argument for parameter `[index]`,
+    /// by position or by name.
+    SkipIfArg(4, 4),
+    /// Like `SkipIfArg` for a parameter bound in the local `[index]`.
+    SkipIfLocalArg(2, 4),
+    /// Pops a value and hands it to the caller.
+    Return(),
+    /// Sets aside the value being returned while `finally` clauses run.
+    SaveReturn(),
+    LoadReturn(),
+    /// Wraps the value on top of the stack as the result of a top-level `return`.
+    MakeReturn(),
+
+    /// Collects the top `[count]` values into an array.
+    Array(4),
+    /// Pops a value and adds it to the array below it.
+    Append(),
+    /// Pops an iterable and adds its items to the array below it.
+    Extend(),
+    /// Checks the length of the array on top of the stack against the limit.
+    CheckLen(),
+    /// Pushes an empty hash.
+    Hash(),
+    /// Fails when the value on top of the stack cannot be a hash key.
+    HashKey(),
+    /// Pops a value and its key and adds them to the hash below them.
+    InsertPair(),
+    Index(),
+
+    /// Starts a loop or `switch`.
+    PushLoop(),
+    /// Pops an iterable and starts a `for` loop over it.
+    PushIter(),
+    /// Pushes the next item of the `for` loop, or continues at `[target]` after the last.
+    Next(4),
+    /// Starts a `try` block whose errors continue at `[handler]`.
+    PushTry(4),
+    /// Ends the innermost loop, `switch` or `try`.
+    PopBlock(),
+    /// Leaves the innermost loop, `switch` or `try` early, dropping what it left on the
+    /// stack and the scopes it entered.
+    UnwindBlock(),
+    /// Like `UnwindBlock`, but stays in the loop for its next iteration.
+    RestoreBlock(),
+
+    /// Pops a case value and continues at `[target]`, popping the `switch` value too, when
+    /// they are equal.
+    CaseEq(4),
+    /// Binds the pattern at `[index]` to the value on top of the stack, or continues at
+    /// `[target]` when it does not match.
+    Match(4, 4),
+    /// Fails because no arm matched the value on top of the stack.
+    NoMatch(),
+
+    /// Pops a value and throws it.
+    Throw(),
+    /// Pushes the value a `catch` clause binds for the error being handled.
+    Caught(),
+    /// Raises the error being handled again once a `finally` clause ran.
+    Rethrow(),
+    /// Raises the error at `[index]`, which the compiler already knew would happen.
+    Error(4),
+}
+
+/// Encodes an instruction. Panics when `operands` does not fit the opcode.
+pub fn make(op: Opcode, operands: &[usize]) -> Vec<u8> {
+    let widths = op.operand_widths();
+    assert_eq!(
+        operands.len(),
+        widths.len(),
+        "wrong operand count for {:?}",
+        op
+    );
+
+    let mut instruction = vec![op as u8];
+    for (&operand, &width) in operands.iter().zip(widths) {
+        match width {
+            1 => instruction.push(u8::try_from(operand).expect("operand does not fit a byte")),
+            2 => instruction.extend(
+                u16::try_from(operand)
+                    .expect("operand does not fit two bytes")
+                    .to_le_bytes(),
+            ),
+            _ => instruction.extend(
+                u32::try_from(operand)
+                    .expect("operand does not fit four bytes")
+                    .to_le_bytes(),
+            ),
+        }
+    }
+    instruction
+}
+
+/// Decodes the operands of the instruction `op` whose operands start at `offset`, returning
+/// them and the offset of the next instruction.
+#[inline]
+pub fn read_operands(op: Opcode, code: &[u8], mut offset: usize) -> ([usize; 2], usize) {
+    let mut operands = [0; 2];
+    for (operand, &width) in operands.iter_mut().zip(op.operand_widths()) {
+        *operand = match width {
+            1 => code[offset] as usize,
+            2 => u16::from_le_bytes([code[offset], code[offset + 1]]) as usize,
+            _ => u32::from_le_bytes(code[offset..offset + 4].try_into().unwrap()) as usize,
+        };
+        offset += width;
+    }
+    (operands, offset)
+}
+
+/// Points the jump at `offset`, whose target is its last operand, at `target`.
+pub fn patch_target(code: &mut [u8], offset: usize, target: usize) {
+    let op = Opcode::from_byte(code[offset]).expect("invalid opcode");
+    let widths = op.operand_widths();
+    let start = offset + 1 + widths[..widths.len() - 1].iter().sum::<usize>();
+    let target = u32::try_from(target).expect("jump target does not fit four bytes");
+    code[start..start + 4].copy_from_slice(&target.to_le_bytes());
+}
+
+/// Lists the instructions in `code`, one per line with its offset.
+pub fn disassemble(code: &[u8]) -> String {
+    let mut out = String::new();
+    let mut offset = 0;
+    while offset < code.len() {
+        let op = Opcode::from_byte(code[offset]).expect("invalid opcode");
+        let (operands, next) = read_operands(op, code, offset + 1);
+        write!(out, "{:04} {:?}", offset, op).unwrap();
+        for operand in &operands[..op.operand_widths().len()] {
+            write!(out, " {}", operand).unwrap();
+        }
+        out.push('\n');
+        offset = next;
+    }
+    out
+}
@@ -0,0 +1,1018 @@
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use crate::{
+    ast::{
+        expression::{
+            node::{
+                ArrayLiteral, CallExpression, FunctionLiteral, HashLiteral, IfExpression,
+                MatchExpression, Parameter,
+            },
+            ExpressionType,
+        },
+        pattern::Pattern,
+        statement::{
+            node::{
+                BlockStatement, ForStatement, LetStatement, SwitchStatement, TryStatement,
+                WhileStatement,
+            },
+            StatementType,
+        },
+        Scope, ScopeNames, Slot, TNode,
+    },
+    evaluator::{eval_bigint_literal, ErrorKind, RuntimeError},
+    lexer::Position,
+    object::{Integer, Object, ObjectType, StringObj},
+    resolver::ResolvedProgram,
+    token::Token,
+};
+
+pub use code::Opcode;
+
+pub mod code;
+pub mod test;
+
+/// Compiles a resolved program to bytecode for the virtual machine.
+///
+/// The code keeps the evaluator's semantics: every statement leaves a value, function
+/// declarations are bound when their scope is entered and variables are read from the
+/// slots the resolver assigned. Variables no closure captures are kept in the locals of
+/// the call instead, so only scopes with captured variables create an environment. Loops,
+/// `switch` and `try` record what they must undo when `break`, `continue`, `return` or an
+/// error leaves them early, and the `finally` clauses an early exit passes are compiled
+/// inline on its path.
+pub fn compile(program: &ResolvedProgram) -> Chunk {
+    let mut compiler = Compiler::new(true, Vec::new());
+    compiler.compile_statements(&program.statements);
+    compiler.emit(Opcode::Return, &[]);
+    compiler.chunk
+}
+
+/// The code of the program or of one function, with the values its instructions refer to
+/// by index. Each function has its own chunk, so a closure keeps working after the program
+/// that created it is gone.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<u8>,
+    pub constants: Vec<Object>,
+    pub names: Vec<String>,
+    pub scopes: Vec<ScopeNames>,
+    pub functions: Vec<Rc<CompiledFunction>>,
+    /// The names of the named arguments of each call that has them, in source order.
+    pub argument_names: Vec<Rc<[String]>>,
+    pub patterns: Vec<CompiledPattern>,
+    /// The name of each local, by index.
+    pub locals: Vec<String>,
+    pub errors: Vec<RuntimeError>,
+    /// The source position of each instruction whose errors point at its node, by offset.
+    positions: Vec<(usize, Position)>,
+}
+
+impl Chunk {
+    /// Where the instruction at `offset` came from, when its errors carry a position.
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        let index = self
+            .positions
+            .binary_search_by_key(&offset, |&(offset, _)| offset)
+            .ok()?;
+        Some(self.positions[index].1)
+    }
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", code::disassemble(&self.code))
+    }
+}
+
+/// A function literal compiled to its own chunk. The parameters and body are kept to print
+/// the function.
+#[derive(Debug)]
+pub struct CompiledFunction {
+    pub name: Option<String>,
+    pub parameters: Rc<[Parameter]>,
+    pub body: Rc<StatementType>,
+    /// The names of the environment each call runs in, when closures capture any of the
+    /// function's variables.
+    pub scope: Option<ScopeNames>,
+    /// Where each parameter is bound.
+    pub locations: Vec<Location>,
+    pub chunk: Rc<Chunk>,
+}
+
+/// A pattern with the local each name it binds is stored in. The other names are bound in
+/// the environment of the scope.
+#[derive(Debug)]
+pub struct CompiledPattern {
+    pub pattern: Pattern,
+    pub locals: Vec<(String, usize)>,
+}
+
+/// Where a variable lives while its scope runs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Location {
+    /// Among the locals of the call, at this index.
+    Local(usize),
+    /// In the environment of the scope, at this slot, where closures can capture it.
+    Env(usize),
+}
+
+/// How a scope the resolver counted is laid out at runtime.
+#[derive(Clone)]
+struct ScopeLayout {
+    names: ScopeNames,
+    /// Where each variable lives, by slot.
+    locations: Vec<Location>,
+    /// The names of the environment created for the scope, when it has captured variables.
+    env: Option<ScopeNames>,
+}
+
+impl ScopeLayout {
+    /// The first of the scope's locals, which are numbered consecutively, and their count.
+    fn locals(&self) -> Option<[usize; 2]> {
+        let mut locals = self.locations.iter().filter_map(|location| match location {
+            Location::Local(local) => Some(*local),
+            Location::Env(_) => None,
+        });
+        let first = locals.next()?;
+        Some([first, locals.count() + 1])
+    }
+}
+
+/// How compiled code reaches a resolved variable.
+enum Access {
+    Local(usize),
+    Var([usize; 2]),
+}
+
+/// A loop, `switch` or `try` enclosing the code being compiled.
+enum Control<'a> {
+    /// A loop, or a `switch` when there is no `continue_target`. `breaks` are the jumps to
+    /// patch to its end.
+    Loop {
+        breaks: Vec<usize>,
+        continue_target: Option<usize>,
+    },
+    Try {
+        finally: Option<&'a StatementType>,
+        /// How many scopes enclose the `try`, which is where its `finally` clause runs.
+        scopes: usize,
+    },
+}
+
+struct Compiler<'a> {
+    chunk: Chunk,
+    names: HashMap<String, usize>,
+    /// Innermost last.
+    controls: Vec<Control<'a>>,
+    /// The layout of each scope the resolver counted around the code being compiled,
+    /// innermost last.
+    scopes: Vec<ScopeLayout>,
+    /// Whether this compiles the program itself rather than a function, so `return` hands
+    /// back a return value and calls are never tail calls.
+    program: bool,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(program: bool, scopes: Vec<ScopeLayout>) -> Self {
+        Compiler {
+            chunk: Chunk::default(),
+            names: HashMap::new(),
+            controls: Vec::new(),
+            scopes,
+            program,
+        }
+    }
+
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> usize {
+        let offset = self.chunk.code.len();
+        self.chunk.code.extend(code::make(op, operands));
+        offset
+    }
+
+    /// Emits an instruction whose errors point at `position`.
+    fn emit_at(&mut self, op: Opcode, operands: &[usize], position: Position) -> usize {
+        let offset = self.emit(op, operands);
+        self.chunk.positions.push((offset, position));
+        offset
+    }
+
+    /// Points the jump at `offset` at the next instruction.
+    fn patch_jump(&mut self, offset: usize) {
+        let target = self.chunk.code.len();
+        code::patch_target(&mut self.chunk.code, offset, target);
+    }
+
+    fn emit_error(&mut self, err: RuntimeError) {
+        self.chunk.errors.push(err);
+        self.emit(Opcode::Error, &[self.chunk.errors.len() - 1]);
+    }
+
+    fn constant(&mut self, value: Object) -> usize {
+        self.chunk.constants.push(value);
+        self.chunk.constants.len() - 1
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.names.get(name) {
+            return index;
+        }
+        self.chunk.names.push(name.to_string());
+        self.names
+            .insert(name.to_string(), self.chunk.names.len() - 1);
+        self.chunk.names.len() - 1
+    }
+
+    /// Lays out `scope`, giving each variable no closure captures a local of its own.
+    fn layout(&mut self, scope: &Scope) -> ScopeLayout {
+        let mut env = Vec::new();
+        let locations = scope
+            .names
+            .iter()
+            .zip(scope.captured.iter())
+            .map(|(name, &captured)| match captured {
+                true => {
+                    env.push(name.clone());
+                    Location::Env(env.len() - 1)
+                }
+                false => {
+                    self.chunk.locals.push(name.clone());
+                    Location::Local(self.chunk.locals.len() - 1)
+                }
+            })
+            .collect();
+        ScopeLayout {
+            names: Rc::clone(&scope.names),
+            locations,
+            env: (!env.is_empty()).then(|| env.into()),
+        }
+    }
+
+    /// Enters `scope` for the code compiled until `leave_scope`.
+    fn enter_scope(&mut self, scope: &Scope) {
+        let layout = self.layout(scope);
+        self.emit_enter(&layout);
+        self.scopes.push(layout);
+    }
+
+    /// Clears the locals of a scope being entered, which an earlier pass through a loop may
+    /// have set, and creates its environment if it has one.
+    fn emit_enter(&mut self, layout: &ScopeLayout) {
+        if let Some(locals) = layout.locals() {
+            self.emit(Opcode::ClearLocals, &locals);
+        }
+        if let Some(names) = &layout.env {
+            self.chunk.scopes.push(Rc::clone(names));
+            self.emit(Opcode::EnterScope, &[self.chunk.scopes.len() - 1]);
+        }
+    }
+
+    fn leave_scope(&mut self) {
+        let layout = self
+            .scopes
+            .pop()
+            .expect("scopes are left in the order they were entered");
+        self.emit_leave(&layout);
+    }
+
+    fn emit_leave(&mut self, layout: &ScopeLayout) {
+        if layout.env.is_some() {
+            self.emit(Opcode::LeaveScope, &[]);
+        }
+    }
+
+    /// How to reach `slot`. The depth of an environment skips the scopes that have none.
+    fn access(&self, Slot { depth, index }: Slot) -> Access {
+        let mut index = index;
+        if let Some(scope) = self.scopes.iter().rev().nth(depth) {
+            match scope.locations[index] {
+                Location::Local(local) => return Access::Local(local),
+                Location::Env(slot) => index = slot,
+            }
+        }
+        let without_env = self
+            .scopes
+            .iter()
+            .rev()
+            .take(depth)
+            .filter(|scope| scope.env.is_none());
+        Access::Var([depth - without_env.count(), index])
+    }
+
+    /// Where `name` lives in the innermost scope, or `None` for the environment the program
+    /// runs in.
+    fn location(&self, name: &str) -> Option<Location> {
+        let scope = self.scopes.last()?;
+        let index = scope.names.iter().position(|n| n == name)?;
+        Some(scope.locations[index])
+    }
+
+    /// Declares `name` in the innermost scope with the value on top of the stack, leaving
+    /// it there.
+    fn emit_define(&mut self, name: &str, mutable: bool, position: Option<Position>) {
+        let (op, operands) = match self.location(name) {
+            Some(Location::Local(local)) => (Opcode::DefineLocal, [local, usize::from(mutable)]),
+            _ => (Opcode::Define, [self.name(name), usize::from(mutable)]),
+        };
+        match position {
+            Some(position) => self.emit_at(op, &operands, position),
+            None => self.emit(op, &operands),
+        };
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> usize {
+        let locals = pattern
+            .names()
+            .into_iter()
+            .filter_map(|name| match self.location(&name) {
+                Some(Location::Local(local)) => Some((name, local)),
+                _ => None,
+            })
+            .collect();
+        self.chunk.patterns.push(CompiledPattern {
+            pattern: pattern.clone(),
+            locals,
+        });
+        self.chunk.patterns.len() - 1
+    }
+
+    /// Compiles statements to code leaving the value of the last one, or `null` when there
+    /// are none.
+    fn compile_statements(
+        &mut self,
+        stmts: impl IntoIterator<Item = &'a Box<StatementType>> + Clone,
+    ) {
+        self.hoist_function_declarations(stmts.clone());
+
+        let mut empty = true;
+        for stmt in stmts {
+            if !empty {
+                self.emit(Opcode::Pop, &[]);
+            }
+            self.compile_statement(stmt);
+            empty = false;
+        }
+        if empty {
+            self.emit(Opcode::Null, &[]);
+        }
+    }
+
+    fn hoist_function_declarations(
+        &mut self,
+        stmts: impl IntoIterator<Item = &'a Box<StatementType>>,
+    ) {
+        for stmt in stmts {
+            if let StatementType::Function(decl) = stmt.as_ref() {
+                self.compile_function(&decl.function);
+                self.emit_define(&decl.name(), true, None);
+                self.emit(Opcode::Pop, &[]);
+            }
+        }
+    }
+
+    /// Compiles a function, `for` or `catch` body in the scope already entered for it.
+    fn compile_body(&mut self, body: &'a StatementType) {
+        match body {
+            StatementType::Block(BlockStatement { statements, .. }) => {
+                self.compile_statements(statements)
+            }
+            body => self.compile_statement(body),
+        }
+    }
+
+    fn compile_statement(&mut self, stmt: &'a StatementType) {
+        match stmt {
+            StatementType::Expression(stmt) => self.compile_expression(&stmt.expression),
+            StatementType::Block(BlockStatement { statements, scope }) => {
+                self.enter_scope(scope);
+                self.compile_statements(statements);
+                self.leave_scope();
+            }
+            StatementType::Function(_) => {
+                self.emit(Opcode::Null, &[]);
+            }
+            StatementType::While(ws) => self.compile_while(ws),
+            StatementType::For(fs) => self.compile_for(fs),
+            StatementType::Switch(ss) => self.compile_switch(ss),
+            StatementType::Throw(ts) => {
+                self.compile_expression(&ts.value);
+                self.emit_at(Opcode::Throw, &[], ts.position);
+            }
+            StatementType::Try(ts) => self.compile_try(ts),
+            StatementType::Break(_) => self.compile_break(),
+            StatementType::Continue(_) => self.compile_continue(),
+            StatementType::Return(rs) => {
+                self.compile_expression(&rs.value);
+                self.compile_return();
+            }
+            StatementType::Let(let_stmt) => self.compile_let(let_stmt),
+        }
+    }
+
+    fn compile_let(&mut self, let_stmt: &'a LetStatement) {
+        let position = let_stmt.position;
+        if let Some(token) = &let_stmt.token {
+            let mutable = *token != Token::CONST;
+            self.compile_expression(&let_stmt.value);
+            match &let_stmt.pattern {
+                Pattern::Identifier(name) => self.emit_define(name, mutable, Some(position)),
+                pattern => {
+                    let pattern = self.pattern(pattern);
+                    let mutable = usize::from(mutable);
+                    self.emit_at(Opcode::Destructure, &[pattern, mutable], position);
+                }
+            }
+            return;
+        }
+
+        let name = let_stmt.pattern.string();
+        match let_stmt.slot.map(|slot| self.access(slot)) {
+            Some(Access::Local(local)) => {
+                self.emit_at(Opcode::CheckLocal, &[local], position);
+                self.compile_expression(&let_stmt.value);
+                self.emit_at(Opcode::SetLocal, &[local], position);
+            }
+            Some(Access::Var(slot)) => {
+                self.emit_at(Opcode::CheckVar, &slot, position);
+                self.compile_expression(&let_stmt.value);
+                self.emit_at(Opcode::SetVar, &slot, position);
+            }
+            None => self.emit_error(ErrorKind::UndefinedIdentifier(name).at(position)),
+        }
+    }
+
+    fn compile_while(&mut self, ws: &'a WhileStatement) {
+        self.emit(Opcode::PushLoop, &[]);
+        let top = self.chunk.code.len();
+        self.compile_expression(&ws.condition);
+        let exit = self.emit(Opcode::JumpIfFalse, &[0]);
+
+        self.controls.push(Control::Loop {
+            breaks: Vec::new(),
+            continue_target: Some(top),
+        });
+        self.compile_statement(&ws.body);
+        self.emit(Opcode::Pop, &[]);
+        let breaks = self.pop_loop();
+        self.emit(Opcode::Jump, &[top]);
+
+        self.patch_jump(exit);
+        self.emit(Opcode::PopBlock, &[]);
+        self.finish_loop(breaks);
+    }
+
+    fn compile_for(&mut self, fs: &'a ForStatement) {
+        self.compile_expression(&fs.iterable);
+        self.emit_at(Opcode::PushIter, &[], fs.position);
+        let top = self.chunk.code.len();
+        let exit = self.emit(Opcode::Next, &[0]);
+
+        self.enter_scope(&fs.scope);
+        self.emit_define(&fs.name, true, None);
+        self.emit(Opcode::Pop, &[]);
+        self.controls.push(Control::Loop {
+            breaks: Vec::new(),
+            continue_target: Some(top),
+        });
+        self.compile_body(&fs.body);
+        self.emit(Opcode::Pop, &[]);
+        let breaks = self.pop_loop();
+        self.leave_scope();
+        self.emit(Opcode::Jump, &[top]);
+
+        self.patch_jump(exit);
+        self.emit(Opcode::PopBlock, &[]);
+        self.finish_loop(breaks);
+    }
+
+    /// Each clause a `switch` can start at gets an entry that hoists the function
+    /// declarations from that clause on, as the evaluator only runs those, and then jumps
+    /// into the clause bodies, which fall through to each other.
+    fn compile_switch(&mut self, ss: &'a SwitchStatement) {
+        self.compile_expression(&ss.discriminant);
+        let mut entries = Vec::new();
+        for (idx, case) in ss.cases.iter().enumerate() {
+            if let Some(test) = &case.test {
+                self.compile_expression(test);
+                entries.push((idx, self.emit(Opcode::CaseEq, &[0])));
+            }
+        }
+        self.emit(Opcode::Pop, &[]);
+        let mut no_match = None;
+        match ss.cases.iter().position(|case| case.test.is_none()) {
+            Some(idx) => entries.push((idx, self.emit(Opcode::Jump, &[0]))),
+            None => {
+                self.emit(Opcode::Null, &[]);
+                no_match = Some(self.emit(Opcode::Jump, &[0]));
+            }
+        }
+
+        let layout = self.layout(&ss.scope);
+        self.scopes.push(layout.clone());
+        let mut starts = Vec::new();
+        for (idx, jump) in entries {
+            self.patch_jump(jump);
+            self.emit(Opcode::PushLoop, &[]);
+            self.emit_enter(&layout);
+            self.hoist_function_declarations(ss.cases[idx..].iter().flat_map(|case| &case.body));
+            starts.push((idx, self.emit(Opcode::Jump, &[0])));
+        }
+
+        self.controls.push(Control::Loop {
+            breaks: Vec::new(),
+            continue_target: None,
+        });
+        for (idx, case) in ss.cases.iter().enumerate() {
+            for &(_, jump) in starts.iter().filter(|(start, _)| *start == idx) {
+                self.patch_jump(jump);
+            }
+            for stmt in &case.body {
+                self.compile_statement(stmt);
+                self.emit(Opcode::Pop, &[]);
+            }
+        }
+        let breaks = self.pop_loop();
+        self.leave_scope();
+        self.emit(Opcode::PopBlock, &[]);
+        self.finish_loop(breaks);
+
+        if let Some(no_match) = no_match {
+            self.patch_jump(no_match);
+        }
+    }
+
+    fn pop_loop(&mut self) -> Vec<usize> {
+        match self.controls.pop() {
+            Some(Control::Loop { breaks, .. }) => breaks,
+            _ => unreachable!("loops are popped in the order they were pushed"),
+        }
+    }
+
+    /// Lands the `break`s of a finished loop or `switch`, which like its normal end
+    /// leave `null`.
+    fn finish_loop(&mut self, breaks: Vec<usize>) {
+        for jump in breaks {
+            self.patch_jump(jump);
+        }
+        self.emit(Opcode::Null, &[]);
+    }
+
+    /// An error in the `try` block continues at a handler that runs the `catch` clause,
+    /// or else the `finally` clause before raising the error again. An error in a `catch`
+    /// clause that has a `finally` does the same.
+    fn compile_try(&mut self, ts: &'a TryStatement) {
+        let finally = ts.finally.as_deref();
+        let handler = self.emit(Opcode::PushTry, &[0]);
+        let scopes = self.scopes.len();
+        self.controls.push(Control::Try { finally, scopes });
+        self.compile_statement(&ts.block);
+        self.controls.pop();
+        self.emit(Opcode::PopBlock, &[]);
+        let mut done = vec![self.emit(Opcode::Jump, &[0])];
+        self.patch_jump(handler);
+
+        if let Some(catch) = &ts.catch {
+            let rethrow = finally.map(|_| self.emit(Opcode::PushTry, &[0]));
+            self.enter_scope(&catch.scope);
+            self.emit(Opcode::Caught, &[]);
+            if let Some(name) = &catch.name {
+                self.emit_define(name, true, None);
+            }
+            self.emit(Opcode::Pop, &[]);
+
+            if finally.is_some() {
+                self.controls.push(Control::Try { finally, scopes });
+            }
+            self.compile_body(&catch.body);
+            if finally.is_some() {
+                self.controls.pop();
+            }
+            self.leave_scope();
+
+            match rethrow {
+                Some(rethrow) => {
+                    self.emit(Opcode::PopBlock, &[]);
+                    done.push(self.emit(Opcode::Jump, &[0]));
+                    self.patch_jump(rethrow);
+                }
+                None => {
+                    for jump in done {
+                        self.patch_jump(jump);
+                    }
+                    return;
+                }
+            }
+        }
+
+        if let Some(finally) = finally {
+            self.compile_statement(finally);
+            self.emit(Opcode::Pop, &[]);
+        }
+        self.emit(Opcode::Rethrow, &[]);
+
+        for jump in done {
+            self.patch_jump(jump);
+        }
+        if let Some(finally) = finally {
+            self.compile_statement(finally);
+            self.emit(Opcode::Pop, &[]);
+        }
+    }
+
+    /// Compiles the `finally` clause of the control at `level` on the path of an early
+    /// exit, where a `break` or `continue` in it refers to the controls outside it.
+    fn compile_finally(&mut self, level: usize, finally: &'a StatementType) {
+        let scopes = match self.controls[level] {
+            Control::Try { scopes, .. } => scopes,
+            Control::Loop { .. } => unreachable!("only a `try` has a `finally` clause"),
+        };
+        let inner = self.controls.split_off(level);
+        let inner_scopes = self.scopes.split_off(scopes);
+        self.compile_statement(finally);
+        self.emit(Opcode::Pop, &[]);
+        self.controls.extend(inner);
+        self.scopes.extend(inner_scopes);
+    }
+
+    fn compile_break(&mut self) {
+        for level in (0..self.controls.len()).rev() {
+            self.emit(Opcode::UnwindBlock, &[]);
+            match &self.controls[level] {
+                Control::Loop { .. } => {
+                    let jump = self.emit(Opcode::Jump, &[0]);
+                    if let Control::Loop { breaks, .. } = &mut self.controls[level] {
+                        breaks.push(jump);
+                    }
+                    return;
+                }
+                Control::Try {
+                    finally: Some(finally),
+                    ..
+                } => {
+                    let finally = *finally;
+                    self.compile_finally(level, finally);
+                }
+                Control::Try { finally: None, .. } => {}
+            }
+        }
+        self.emit_error(ErrorKind::BreakOutsideLoop.into());
+    }
+
+    fn compile_continue(&mut self) {
+        for level in (0..self.controls.len()).rev() {
+            match &self.controls[level] {
+                Control::Loop {
+                    continue_target: Some(top),
+                    ..
+                } => {
+                    let top = *top;
+                    self.emit(Opcode::RestoreBlock, &[]);
+                    self.emit(Opcode::Jump, &[top]);
+                    return;
+                }
+                Control::Loop { .. } | Control::Try { finally: None, .. } => {
+                    self.emit(Opcode::UnwindBlock, &[]);
+                }
+                Control::Try {
+                    finally: Some(finally),
+                    ..
+                } => {
+                    let finally = *finally;
+                    self.emit(Opcode::UnwindBlock, &[]);
+                    self.compile_finally(level, finally);
+                }
+            }
+        }
+        self.emit_error(ErrorKind::ContinueOutsideLoop.into());
+    }
+
+    /// Returns the value on top of the stack, first running every enclosing `finally`.
+    fn compile_return(&mut self) {
+        let outermost = self.controls.iter().position(|control| {
+            matches!(
+                control,
+                Control::Try {
+                    finally: Some(_),
+                    ..
+                }
+            )
+        });
+        if let Some(outermost) = outermost {
+            self.emit(Opcode::SaveReturn, &[]);
+            for level in (outermost..self.controls.len()).rev() {
+                self.emit(Opcode::UnwindBlock, &[]);
+                if let Control::Try {
+                    finally: Some(finally),
+                    ..
+                } = &self.controls[level]
+                {
+                    let finally = *finally;
+                    self.compile_finally(level, finally);
+                }
+            }
+            self.emit(Opcode::LoadReturn, &[]);
+        }
+
+        if self.program {
+            self.emit(Opcode::MakeReturn, &[]);
+        }
+        self.emit(Opcode::Return, &[]);
+    }
+
+    fn compile_expression(&mut self, expr: &'a ExpressionType) {
+        match expr {
+            ExpressionType::IntegerLiteral(literal) => {
+                let value = *literal
+                    .token
+                    .as_int()
+                    .expect("failed to convert token to int");
+                let constant = self.constant(Object::Integer(Integer { value }));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            ExpressionType::BigIntLiteral(literal) => match eval_bigint_literal(&literal.token) {
+                Ok(value) => {
+                    let constant = self.constant(value);
+                    self.emit(Opcode::Constant, &[constant]);
+                }
+                Err(err) => self.emit_error(err),
+            },
+            ExpressionType::StringLiteral(literal) => {
+                let value = literal
+                    .token
+                    .as_string()
+                    .expect("failed to convert token to string")
+                    .as_str()
+                    .into();
+                let constant = self.constant(Object::String(StringObj { value }));
+                self.emit(Opcode::Constant, &[constant]);
+            }
+            ExpressionType::Boolean(literal) => {
+                let op = match literal.token {
+                    Token::TRUE => Opcode::True,
+                    Token::FALSE => Opcode::False,
+                    _ => Opcode::Null,
+                };
+                self.emit(op, &[]);
+            }
+            ExpressionType::Prefix(prefix) => {
+                self.compile_expression(&prefix.right);
+                let op = match prefix.operator {
+                    Token::BANG => Opcode::Not,
+                    Token::MINUS => Opcode::Negate,
+                    _ => {
+                        let err = ErrorKind::UnknownOperator {
+                            left: None,
+                            operator: prefix.operator.clone(),
+                            right: literal_type(&prefix.right),
+                        };
+                        return self.emit_error(err.at(prefix.position));
+                    }
+                };
+                self.emit_at(op, &[], prefix.position);
+            }
+            ExpressionType::Infix(infix) => {
+                self.compile_expression(&infix.left);
+                self.compile_expression(&infix.right);
+                let op = match infix.operator {
+                    Token::PLUS => Opcode::Add,
+                    Token::MINUS => Opcode::Sub,
+                    Token::ASTERISK => Opcode::Mul,
+                    Token::SLASH => Opcode::Div,
+                    Token::EQ => Opcode::Eq,
+                    Token::NOT_EQ => Opcode::NotEq,
+                    Token::LT => Opcode::Lt,
+                    Token::GT => Opcode::Gt,
+                    Token::DOTDOT => Opcode::Range,
+                    Token::DOTDOT_EQ => Opcode::RangeInclusive,
+                    _ => {
+                        let err = ErrorKind::UnknownOperator {
+                            left: Some(literal_type(&infix.left)),
+                            operator: infix.operator.clone(),
+                            right: literal_type(&infix.right),
+                        };
+                        return self.emit_error(err.at(infix.position));
+                    }
+                };
+                self.emit_at(op, &[], infix.position);
+            }
+            ExpressionType::If(ie) => self.compile_if(ie),
+            ExpressionType::Conditional(ce) => {
+                self.compile_expression(&ce.condition);
+                let alternative = self.emit(Opcode::JumpIfFalse, &[0]);
+                self.compile_expression(&ce.consequence);
+                let end = self.emit(Opcode::Jump, &[0]);
+                self.patch_jump(alternative);
+                self.compile_expression(&ce.alternative);
+                self.patch_jump(end);
+            }
+            ExpressionType::Identifier(ident) => match ident.slot.map(|slot| self.access(slot)) {
+                Some(Access::Local(local)) => {
+                    self.emit_at(Opcode::GetLocal, &[local], ident.position);
+                }
+                Some(Access::Var(slot)) => {
+                    self.emit_at(Opcode::GetVar, &slot, ident.position);
+                }
+                None => {
+                    let name = ident.token.to_string();
+                    self.emit_error(ErrorKind::UndefinedIdentifier(name).at(ident.position))
+                }
+            },
+            ExpressionType::Fn(function) => self.compile_function(function),
+            ExpressionType::Call(call) => self.compile_call(call),
+            ExpressionType::Array(array) => self.compile_array(array),
+            ExpressionType::Hash(hash) => self.compile_hash(hash),
+            ExpressionType::Match(me) => self.compile_match(me),
+            ExpressionType::Index(ie) => {
+                self.compile_expression(&ie.left);
+                self.compile_expression(&ie.index);
+                self.emit_at(Opcode::Index, &[], ie.position);
+            }
+            ExpressionType::Spread(spread) => {
+                self.emit_error(ErrorKind::MisplacedSpread.at(spread.position))
+            }
+        }
+    }
+
+    fn compile_if(&mut self, ie: &'a IfExpression) {
+        self.compile_expression(&ie.condition);
+        let alternative = self.emit(Opcode::JumpIfFalse, &[0]);
+        self.compile_statement(&ie.consequence);
+        let end = self.emit(Opcode::Jump, &[0]);
+        self.patch_jump(alternative);
+        match &ie.alternative {
+            Some(alternative) => self.compile_statement(alternative),
+            None => {
+                self.emit(Opcode::Null, &[]);
+            }
+        }
+        self.patch_jump(end);
+    }
+
+    /// Compiles a function to its own chunk and pushes a closure of it. The code starts by
+    /// evaluating the defaults of the parameters the call left out, then binds the rest
+    /// parameter, which the call pushed.
+    fn compile_function(&mut self, function: &'a FunctionLiteral) {
+        let mut compiler = Compiler::new(false, self.scopes.clone());
+        let layout = compiler.layout(&function.scope);
+        let locations = layout.locations[..function.parameters.len()].to_vec();
+        let scope = layout.env.clone();
+        compiler.scopes.push(layout);
+        for (param, &location) in function.parameters.iter().zip(&locations) {
+            if let (false, Some(default)) = (param.rest, &param.default) {
+                let skip = match location {
+                    Location::Local(local) => compiler.emit(Opcode::SkipIfLocalArg, &[local, 0]),
+                    Location::Env(slot) => compiler.emit(Opcode::SkipIfArg, &[slot, 0]),
+                };
+                compiler.compile_expression(default);
+                compiler.emit_define(&param.name, true, None);
+                compiler.emit(Opcode::Pop, &[]);
+                compiler.patch_jump(skip);
+            }
+        }
+        if let Some(rest) = function.parameters.iter().find(|param| param.rest) {
+            compiler.emit_define(&rest.name, true, None);
+            compiler.emit(Opcode::Pop, &[]);
+        }
+        compiler.compile_body(&function.body);
+        compiler.emit(Opcode::Return, &[]);
+
+        self.chunk.functions.push(Rc::new(CompiledFunction {
+            name: function.name.clone(),
+            parameters: Rc::clone(&function.parameters),
+            body: Rc::clone(&function.body),
+            scope,
+            locations,
+            chunk: Rc::new(compiler.chunk),
+        }));
+        self.emit(Opcode::Closure, &[self.chunk.functions.len() - 1]);
+    }
+
+    fn compile_call(&mut self, call: &'a CallExpression) {
+        self.compile_expression(&call.function);
+        let tail = call.tail && !self.program;
+        if !call.named.is_empty() {
+            self.compile_spread_elements(&call.arguments);
+            for argument in &call.named {
+                self.compile_expression(&argument.value);
+            }
+            let names = call.named.iter().map(|arg| arg.name.clone()).collect();
+            self.chunk.argument_names.push(names);
+            let op = match tail {
+                true => Opcode::TailCallNamed,
+                false => Opcode::CallNamed,
+            };
+            self.emit_at(op, &[self.chunk.argument_names.len() - 1], call.position);
+            return;
+        }
+        if has_spread(&call.arguments) {
+            self.compile_spread_elements(&call.arguments);
+            let op = match tail {
+                true => Opcode::TailCallSpread,
+                false => Opcode::CallSpread,
+            };
+            self.emit_at(op, &[], call.position);
+            return;
+        }
+
+        for argument in &call.arguments {
+            self.compile_expression(argument);
+        }
+        let op = match tail {
+            true => Opcode::TailCall,
+            false => Opcode::Call,
+        };
+        self.emit_at(op, &[call.arguments.len()], call.position);
+    }
+
+    fn compile_array(&mut self, array: &'a ArrayLiteral) {
+        if has_spread(&array.elements) {
+            self.compile_spread_elements(&array.elements);
+            self.emit(Opcode::CheckLen, &[]);
+            return;
+        }
+
+        for element in &array.elements {
+            self.compile_expression(element);
+        }
+        self.emit(Opcode::Array, &[array.elements.len()]);
+    }
+
+    /// Builds an array of `elements`, spreading the items of `...` elements into it.
+    fn compile_spread_elements(&mut self, elements: &'a [Box<ExpressionType>]) {
+        self.emit(Opcode::Array, &[0]);
+        for element in elements {
+            match element.as_ref() {
+                ExpressionType::Spread(spread) => {
+                    self.compile_expression(&spread.value);
+                    self.emit_at(Opcode::Extend, &[], spread.position);
+                }
+                element => {
+                    self.compile_expression(element);
+                    self.emit(Opcode::Append, &[]);
+                }
+            }
+        }
+    }
+
+    fn compile_hash(&mut self, hash: &'a HashLiteral) {
+        self.emit(Opcode::Hash, &[]);
+        for (key, value) in &hash.pairs {
+            self.compile_expression(key);
+            self.emit_at(Opcode::HashKey, &[], hash.position);
+            self.compile_expression(value);
+            self.emit_at(Opcode::InsertPair, &[], hash.position);
+        }
+    }
+
+    /// Each arm binds its pattern in its own scope, and a mismatch or a falsy guard leaves
+    /// that scope for the next arm. The subject stays on the stack until an arm runs.
+    fn compile_match(&mut self, me: &'a MatchExpression) {
+        self.compile_expression(&me.subject);
+        let mut ends = Vec::new();
+        for arm in &me.arms {
+            self.enter_scope(&arm.scope);
+            let pattern = self.pattern(&arm.pattern);
+            let mut next_arm = vec![self.emit(Opcode::Match, &[pattern, 0])];
+            if let Some(guard) = &arm.guard {
+                self.compile_expression(guard);
+                next_arm.push(self.emit(Opcode::JumpIfFalse, &[0]));
+            }
+            self.emit(Opcode::Pop, &[]);
+            self.compile_statement(&arm.body);
+            let layout = self.scopes.pop().expect("the arm's scope");
+            self.emit_leave(&layout);
+            ends.push(self.emit(Opcode::Jump, &[0]));
+
+            for jump in next_arm {
+                self.patch_jump(jump);
+            }
+            self.emit_leave(&layout);
+        }
+        self.emit_at(Opcode::NoMatch, &[], me.position);
+
+        for jump in ends {
+            self.patch_jump(jump);
+        }
+    }
+}
+
+/// The type of an operand as far as the compiler can tell, for reporting an operator it
+/// has no instruction for. Only literals are known before they run; anything else is
+/// reported as `NULL`, the type functions report too.
+fn literal_type(expr: &ExpressionType) -> ObjectType {
+    match expr {
+        ExpressionType::IntegerLiteral(_) => ObjectType::INTEGER,
+        ExpressionType::BigIntLiteral(literal) => eval_bigint_literal(&literal.token)
+            .map_or(ObjectType::NULL, |value| value.object_type()),
+        ExpressionType::StringLiteral(_) => ObjectType::STRING,
+        ExpressionType::Boolean(_) => ObjectType::BOOLEAN,
+        ExpressionType::Array(_) => ObjectType::ARRAY,
+        ExpressionType::Hash(_) => ObjectType::HASH,
+        _ => ObjectType::NULL,
+    }
+}
+
+fn has_spread(elements: &[Box<ExpressionType>]) -> bool {
+    elements
+        .iter()
+        .any(|element| matches!(element.as_ref(), ExpressionType::Spread(_)))
+}
@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            code::{disassemble, make, read_operands},
+            compile, Chunk, Opcode,
+        },
+        lexer::{Lexer, Position},
+        object::{environment::Environment, Integer, Object},
+        parser::Parser,
+        resolver::resolve,
+    };
+
+    #[test]
+    fn test_instructions() {
+        let tests = vec![
+            (Opcode::Constant, vec![65534], vec![0, 254, 255, 0, 0]),
+            (
+                Opcode::GetVar,
+                vec![1, 258],
+                vec![Opcode::GetVar as u8, 1, 0, 2, 1],
+            ),
+            (
+                Opcode::Define,
+                vec![3, 1],
+                vec![Opcode::Define as u8, 3, 0, 0, 0, 1],
+            ),
+            (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+        ];
+        for (op, operands, expected) in tests {
+            let instruction = make(op, &operands);
+            assert_eq!(instruction, expected, "{:?}", op);
+
+            assert_eq!(Opcode::from_byte(instruction[0]), Some(op));
+            let (read, next) = read_operands(op, &instruction, 1);
+            assert_eq!(&read[..operands.len()], operands.as_slice());
+            assert_eq!(next, instruction.len());
+        }
+
+        let code = [
+            make(Opcode::Constant, &[2]),
+            make(Opcode::SetVar, &[0, 1]),
+            make(Opcode::Pop, &[]),
+        ]
+        .concat();
+        assert_eq!(
+            disassemble(&code),
+            "0000 Constant 2\n0005 SetVar 0 1\n0010 Pop\n"
+        );
+    }
+
+    #[test]
+    fn test_compile() {
+        let chunk = test_compile_program("1 + 2");
+        assert_eq!(
+            chunk.to_string(),
+            "0000 Constant 0\n0005 Constant 1\n0010 Add\n0011 Return\n"
+        );
+        assert_eq!(
+            chunk.constants,
+            vec![
+                Object::Integer(Integer { value: 1 }),
+                Object::Integer(Integer { value: 2 })
+            ]
+        );
+
+        let chunk = test_compile_program("let x = 1; while (x < 3) { x = x + 1; }");
+        let expected = [
+            "0000 Constant 0",
+            "0005 Define 0 1",
+            "0011 Pop",
+            "0012 PushLoop",
+            "0013 GetVar 0 0",
+            "0018 Constant 1",
+            "0023 Lt",
+            "0024 JumpIfFalse 56",
+            "0029 CheckVar 0 0",
+            "0034 GetVar 0 0",
+            "0039 Constant 2",
+            "0044 Add",
+            "0045 SetVar 0 0",
+            "0050 Pop",
+            "0051 Jump 13",
+            "0056 PopBlock",
+            "0057 Null",
+            "0058 Return",
+        ];
+        assert_eq!(chunk.to_string(), expected.join("\n") + "\n");
+
+        // Variables no closure captures are locals, so their block needs no scope and adds
+        // no depth.
+        let chunk = test_compile_program("let x = 1; if (x) { let y = 2; if (y) { x + y } }");
+        let expected = [
+            "0000 Constant 0",
+            "0005 Define 0 1",
+            "0011 Pop",
+            "0012 GetVar 0 0",
+            "0017 JumpIfFalse 65",
+            "0022 ClearLocals 0 1",
+            "0027 Constant 1",
+            "0032 DefineLocal 0 1",
+            "0036 Pop",
+            "0037 GetLocal 0",
+            "0040 JumpIfFalse 59",
+            "0045 GetVar 0 0",
+            "0050 GetLocal 0",
+            "0053 Add",
+            "0054 Jump 60",
+            "0059 Null",
+            "0060 Jump 66",
+            "0065 Null",
+            "0066 Return",
+        ];
+        assert_eq!(chunk.to_string(), expected.join("\n") + "\n");
+
+        // A captured variable gets a scope of its own, holding only the captured names.
+        let chunk = test_compile_program("if (true) { let a = 1; let b = a; fn() { a } }");
+        let expected = [
+            "0000 True",
+            "0001 JumpIfFalse 49",
+            "0006 ClearLocals 0 1",
+            "0011 EnterScope 0",
+            "0016 Constant 0",
+            "0021 Define 0 1",
+            "0027 Pop",
+            "0028 GetVar 0 0",
+            "0033 DefineLocal 0 1",
+            "0037 Pop",
+            "0038 Closure 0",
+            "0043 LeaveScope",
+            "0044 Jump 50",
+            "0049 Null",
+            "0050 Return",
+        ];
+        assert_eq!(chunk.to_string(), expected.join("\n") + "\n");
+        assert_eq!(*chunk.scopes[0], ["a"]);
+        assert_eq!(
+            chunk.functions[0].chunk.to_string(),
+            "0000 GetVar 0 0\n0005 Return\n"
+        );
+
+        // A function gets its own chunk, which evaluates left-out defaults itself.
+        let chunk = test_compile_program("fn f(a, b = 2) { a + b } f(1)");
+        let expected = [
+            "0000 Closure 0",
+            "0005 Define 0 1",
+            "0011 Pop",
+            "0012 Null",
+            "0013 Pop",
+            "0014 GetVar 0 0",
+            "0019 Constant 0",
+            "0024 Call 1",
+            "0029 Return",
+        ];
+        assert_eq!(chunk.to_string(), expected.join("\n") + "\n");
+        let expected = [
+            "0000 SkipIfLocalArg 1 17",
+            "0007 Constant 0",
+            "0012 DefineLocal 1 1",
+            "0016 Pop",
+            "0017 GetLocal 0",
+            "0020 GetLocal 1",
+            "0023 Add",
+            "0024 Return",
+        ];
+        assert_eq!(
+            chunk.functions[0].chunk.to_string(),
+            expected.join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn test_finally_on_early_exits() {
+        // The `finally` clause is compiled on the normal path, the error path and the
+        // path of the `break` inside the `try` block.
+        let chunk = test_compile_program("while (true) { try { break; } finally { 7 } }");
+        assert_eq!(
+            chunk.constants,
+            vec![Object::Integer(Integer { value: 7 }); 3]
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let chunk = test_compile_program("let a = 1;\na + true");
+        let add = chunk
+            .to_string()
+            .lines()
+            .find(|line| line.ends_with("Add"))
+            .map(|line| line[..4].parse().unwrap())
+            .unwrap();
+        assert_eq!(chunk.position(add), Some(Position { line: 2, column: 3 }));
+        assert_eq!(chunk.position(0), None);
+    }
+
+    fn test_compile_program(input: &str) -> Chunk {
+        let program = Parser::new(Lexer::new(input.to_string()))
+            .parse_program()
+            .unwrap_or_else(|err| panic!("parse_program() returned an error: {}", err));
+        compile(&resolve(program, &Environment::new()).unwrap())
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    object::{BigInteger, Boolean, Integer, Object, ObjectType},
+    token::Token,
+};
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+
+use super::{ErrorKind, RuntimeError};
+
+/// Like arithmetic results, a literal that fits in an `i64` is an `Integer`, so `1n` can
+/// key a hash or bound a range wherever `1` can.
+pub fn eval_bigint_literal(digits: &str) -> Result<Object, RuntimeError> {
+    match digits.parse() {
+        Ok(value) => Ok(from_bigint(value)),
+        Err(_) => Err(ErrorKind::InvalidBigInt(digits.to_string()).into()),
+    }
+}
+
+/// Narrows an arithmetic result back to an `Integer` whenever it fits in an `i64`.
+pub fn from_bigint(value: BigInt) -> Object {
+    match value.to_i64() {
+        Some(value) => Object::Integer(Integer { value }),
+        None => Object::BigInt(BigInteger { value }),
+    }
+}
+
+pub fn to_bigint(obj: &Object) -> Option<BigInt> {
+    match obj {
+        Object::Integer(Integer { value }) => Some(BigInt::from(*value)),
+        Object::BigInt(BigInteger { value }) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// An upper bound on the bits of `left operator right` when either operand is a `BigInt`
+/// and the operator computes a new number, so its size can be checked before it is built.
+pub fn result_bits(operator: &Token, left: &Object, right: &Object) -> Option<u64> {
+    if !left.is_big_int() && !right.is_big_int() {
+        return None;
+    }
+    let (left, right) = (bits(left)?, bits(right)?);
+    match operator {
+        Token::PLUS | Token::MINUS => Some(left.max(right) + 1),
+        Token::ASTERISK => Some(left + right),
+        Token::SLASH => Some(left),
+        _ => None,
+    }
+}
+
+pub fn bits(obj: &Object) -> Option<u64> {
+    match obj {
+        Object::Integer(Integer { value }) => {
+            Some(u64::from(64 - value.unsigned_abs().leading_zeros()))
+        }
+        Object::BigInt(BigInteger { value }) => Some(value.bits()),
+        _ => None,
+    }
+}
+
+pub fn eval_bigint_minus_prefix_expression(value: BigInt) -> Object {
+    from_bigint(-value)
+}
+
+pub fn eval_bigint_infix_expression(
+    operator: Token,
+    left: BigInt,
+    right: BigInt,
+) -> Result<Object, RuntimeError> {
+    match operator {
+        Token::PLUS => Ok(from_bigint(left + right)),
+        Token::MINUS => Ok(from_bigint(left - right)),
+        Token::ASTERISK => Ok(from_bigint(left * right)),
+        Token::SLASH => {
+            if right.is_zero() {
+                return Err(ErrorKind::DivisionByZero.into());
+            }
+            Ok(from_bigint(left / right))
+        }
+        Token::LT => Ok(Object::Boolean(Boolean {
+            value: left < right,
+        })),
+        Token::GT => Ok(Object::Boolean(Boolean {
+            value: left > right,
+        })),
+        Token::EQ => Ok(Object::Boolean(Boolean {
+            value: left == right,
+        })),
+        Token::NOT_EQ => Ok(Object::Boolean(Boolean {
+            value: left != right,
+        })),
+        _ => Err(ErrorKind::UnknownOperator {
+            left: Some(ObjectType::BIGINT),
+            operator,
+            right: ObjectType::BIGINT,
+        }
+        .into()),
+    }
+}
@@ -0,0 +1,225 @@
+use std::{cell::RefCell, fmt, rc::Rc, time::Instant};
+
+use super::{
+    error::{ErrorKind, RuntimeError},
+    limits::{InterruptHandle, Limit, Limits},
+};
+use crate::{
+    lexer::Position,
+    object::{environment::Environment, gc::CycleCollector},
+};
+
+pub const DEFAULT_MAX_BACKTRACE_DEPTH: usize = 16;
+
+/// How many steps pass between two reads of the clock for `Limits::deadline`.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// The native stack a call leaves free for the evaluation it starts, which recurses natively
+/// until the next call, and for reporting an error.
+const NATIVE_STACK_RESERVE: usize = 256 * 1024;
+
+/// State shared by everything evaluated in one session, such as the calls in progress.
+#[derive(Debug)]
+pub struct Context {
+    call_stack: Vec<Frame>,
+    steps: u64,
+    interrupt: InterruptHandle,
+    collector: CycleCollector,
+    pub limits: Limits,
+    /// How many of the innermost frames a backtrace keeps. Deeper frames are only counted.
+    pub max_backtrace_depth: usize,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            call_stack: Vec::new(),
+            steps: 0,
+            interrupt: InterruptHandle::default(),
+            collector: CycleCollector::default(),
+            limits: Limits::default(),
+            max_backtrace_depth: DEFAULT_MAX_BACKTRACE_DEPTH,
+        }
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        Context {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    /// The number of calls in progress.
+    pub fn depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    /// The number of nodes evaluated with this context so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// A handle that stops whatever this context is evaluating, from any thread.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Frees the environments that only reference cycles between closures and their scopes
+    /// keep alive, returning how many there were. This also runs on its own as closures are
+    /// created.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.collector.collect()
+    }
+
+    /// Records the environment a new closure captures, collecting cycles when enough have
+    /// piled up since the last collection.
+    pub(crate) fn track_closure(&mut self, env: &Rc<RefCell<Environment>>) {
+        self.collector.track(env);
+        if self.collector.should_collect() {
+            self.collector.collect();
+        }
+    }
+
+    /// Counts one evaluation step, failing once the step budget or the deadline is used up
+    /// or an interrupt was requested.
+    pub(crate) fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if self.interrupt.take() {
+            return Err(ErrorKind::Interrupted.into());
+        }
+        if let Some(max_steps) = self.limits.max_steps {
+            if self.steps > max_steps {
+                return Err(ErrorKind::LimitExceeded(Limit::Steps(max_steps)).into());
+            }
+        }
+        if self.steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) {
+            self.check_deadline()?;
+        }
+        Ok(())
+    }
+
+    /// Fails when one more call would nest deeper than `Limits::max_call_depth`, or sooner
+    /// when the thread is running out of native stack, so a deep recursion is reported as
+    /// a stack overflow instead of crashing the host whatever stack the thread has.
+    pub(crate) fn check_call_depth(&self) -> Result<(), RuntimeError> {
+        if self.depth() >= self.limits.max_call_depth {
+            return Err(
+                ErrorKind::LimitExceeded(Limit::CallDepth(self.limits.max_call_depth)).into(),
+            );
+        }
+        if stacker::remaining_stack().is_some_and(|left| left < NATIVE_STACK_RESERVE) {
+            return Err(ErrorKind::LimitExceeded(Limit::CallDepth(self.depth())).into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_string_len(&self, len: usize) -> Result<(), RuntimeError> {
+        match self.limits.max_string_len {
+            Some(max) if len > max => {
+                Err(ErrorKind::LimitExceeded(Limit::StringLength(max)).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_collection_len(&self, len: usize) -> Result<(), RuntimeError> {
+        match self.limits.max_collection_len {
+            Some(max) if len > max => {
+                Err(ErrorKind::LimitExceeded(Limit::CollectionLength(max)).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails once the deadline passed. Steps only read the clock now and then, so work that
+    /// can take long within one step checks on its own.
+    pub(crate) fn check_deadline(&self) -> Result<(), RuntimeError> {
+        match self.limits.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                Err(ErrorKind::LimitExceeded(Limit::Deadline).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails when a big integer of `bits` bits would exceed the size limit.
+    #[cfg(feature = "bigint")]
+    pub(crate) fn check_bigint_bits(&self, bits: u64) -> Result<(), RuntimeError> {
+        let max = self.limits.max_bigint_bytes;
+        match bits.div_ceil(8) > max as u64 {
+            true => Err(ErrorKind::LimitExceeded(Limit::BigIntSize(max)).into()),
+            false => Ok(()),
+        }
+    }
+
+    pub(crate) fn push_frame(&mut self, frame: Frame) {
+        self.call_stack.push(frame);
+    }
+
+    pub(crate) fn pop_frame(&mut self) {
+        self.call_stack.pop();
+    }
+
+    /// A snapshot of the calls in progress, innermost first.
+    pub(crate) fn backtrace(&self) -> Backtrace {
+        let frames = self
+            .call_stack
+            .iter()
+            .rev()
+            .take(self.max_backtrace_depth)
+            .cloned()
+            .collect();
+        Backtrace {
+            frames,
+            omitted: self
+                .call_stack
+                .len()
+                .saturating_sub(self.max_backtrace_depth),
+        }
+    }
+}
+
+/// One function call in progress.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    /// `None` for an anonymous function.
+    pub function: Option<String>,
+    pub call_site: Position,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "at {}, called at {}", name, self.call_site),
+            None => write!(f, "at <anonymous>, called at {}", self.call_site),
+        }
+    }
+}
+
+/// The calls that were in progress when an error was raised, innermost first.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Backtrace {
+    pub frames: Vec<Frame>,
+    /// Outer frames left out because the stack was deeper than the maximum depth.
+    pub omitted: usize,
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines: Vec<String> = self
+            .frames
+            .iter()
+            .map(|frame| format!("    {}", frame))
+            .collect();
+        if self.omitted > 0 {
+            lines.push(format!("    ... {} more frames", self.omitted));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
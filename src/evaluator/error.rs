@@ -0,0 +1,261 @@
+use std::fmt;
+
+use super::{context::Backtrace, limits::Limit};
+use crate::{
+    lexer::Position,
+    object::{environment::BindingError, Object, ObjectType},
+    token::Token,
+};
+
+/// A failure raised while evaluating a program, with where it happened when known.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub position: Option<Position>,
+    /// The calls in progress when the error was raised. Recorded once the error leaves
+    /// the function that raised it, so it stays `None` for errors outside any function.
+    pub backtrace: Option<Box<Backtrace>>,
+}
+
+impl RuntimeError {
+    /// Points the error at `position` unless a more precise position was already recorded
+    /// closer to where it was raised.
+    pub fn at(mut self, position: Position) -> Self {
+        self.position.get_or_insert(position);
+        self
+    }
+
+    /// Whether a `catch` clause may handle the error. Exceeded limits and interrupts end
+    /// the whole evaluation so a script cannot ignore them. A stack overflow is the
+    /// exception: the calls it unwinds free the stack again, so a script may recover.
+    pub fn is_catchable(&self) -> bool {
+        match self.kind {
+            ErrorKind::LimitExceeded(Limit::CallDepth(_)) => true,
+            ErrorKind::LimitExceeded(_) | ErrorKind::Interrupted => false,
+            _ => true,
+        }
+    }
+
+    /// The error message followed by its backtrace, one frame per line.
+    pub fn report(&self) -> String {
+        match &self.backtrace {
+            Some(backtrace) if !backtrace.frames.is_empty() => format!("{}\n{}", self, backtrace),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl From<ErrorKind> for RuntimeError {
+    fn from(kind: ErrorKind) -> Self {
+        RuntimeError {
+            kind,
+            position: None,
+            backtrace: None,
+        }
+    }
+}
+
+impl From<BindingError> for RuntimeError {
+    fn from(err: BindingError) -> Self {
+        let kind = match err {
+            BindingError::AlreadyDeclared(name) => ErrorKind::AlreadyDeclared(name),
+            BindingError::AssignmentToConstant(name) => ErrorKind::AssignmentToConstant(name),
+            BindingError::Undeclared(name) => ErrorKind::UsedBeforeDeclaration(name),
+        };
+        kind.into()
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{}: {}", position, self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ErrorKind {
+    /// A binary operator applied to operands of two different types.
+    TypeMismatch {
+        left: ObjectType,
+        operator: Token,
+        right: ObjectType,
+    },
+    /// An operator that is not defined for its operand types. `left` is `None` for a
+    /// prefix operator.
+    UnknownOperator {
+        left: Option<ObjectType>,
+        operator: Token,
+        right: ObjectType,
+    },
+    UndefinedIdentifier(String),
+    /// A variable read or assigned in its scope before its declaration ran.
+    UsedBeforeDeclaration(String),
+    ArityMismatch {
+        function: Option<String>,
+        got: usize,
+        min: usize,
+        /// `None` when the function has a rest parameter.
+        max: Option<usize>,
+    },
+    /// A named argument that matches no parameter, or only a rest parameter.
+    UnknownArgument {
+        function: Option<String>,
+        name: String,
+    },
+    /// A parameter given a value both by position and by name.
+    DuplicateArgument {
+        function: Option<String>,
+        name: String,
+    },
+    /// A parameter without a default that a call with named arguments left out.
+    MissingArgument {
+        function: Option<String>,
+        name: String,
+    },
+    AlreadyDeclared(String),
+    AssignmentToConstant(String),
+    DivisionByZero,
+    #[cfg(not(feature = "bigint"))]
+    IntegerOverflow(String),
+    #[cfg(feature = "bigint")]
+    InvalidBigInt(String),
+    #[cfg(not(feature = "bigint"))]
+    BigIntDisabled(String),
+    NotAFunction(ObjectType),
+    NotIterable(ObjectType),
+    IndexNotSupported(ObjectType),
+    UnusableHashKey(ObjectType),
+    MisplacedSpread,
+    /// A value whose shape does not fit a destructuring pattern.
+    PatternMismatch(String),
+    NoMatchArm(Object),
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    /// A bound from `Limits` that the evaluation ran into.
+    LimitExceeded(Limit),
+    /// Evaluation stopped through an `InterruptHandle`.
+    Interrupted,
+    /// A value passed to `throw` that no `catch` handled.
+    Thrown(Object),
+}
+
+impl ErrorKind {
+    pub fn at(self, position: Position) -> RuntimeError {
+        RuntimeError {
+            kind: self,
+            position: Some(position),
+            backtrace: None,
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::TypeMismatch {
+                left,
+                operator,
+                right,
+            } => write!(
+                f,
+                "type mismatch: {:?} {} {:?}",
+                left,
+                operator.to_string(),
+                right
+            ),
+            ErrorKind::UnknownOperator {
+                left: Some(left),
+                operator,
+                right,
+            } => write!(
+                f,
+                "unknown operator: {:?} {} {:?}",
+                left,
+                operator.to_string(),
+                right
+            ),
+            ErrorKind::UnknownOperator {
+                left: None,
+                operator,
+                right,
+            } => write!(f, "unknown operator: {}{:?}", operator.to_string(), right),
+            ErrorKind::UndefinedIdentifier(name) => write!(f, "identifier not found: {}", name),
+            ErrorKind::UsedBeforeDeclaration(name) => {
+                write!(f, "`{}` used before its declaration", name)
+            }
+            ErrorKind::ArityMismatch {
+                function,
+                got,
+                min,
+                max,
+            } => {
+                write!(f, "wrong number of arguments")?;
+                write_function(f, function)?;
+                match max {
+                    Some(max) if max == min => write!(f, ". got={}, want={}", got, min),
+                    Some(max) => write!(f, ". got={}, want={}..{}", got, min, max),
+                    None => write!(f, ". got={}, want at least {}", got, min),
+                }
+            }
+            ErrorKind::UnknownArgument { function, name } => {
+                write!(f, "unknown argument `{}`", name)?;
+                write_function(f, function)
+            }
+            ErrorKind::DuplicateArgument { function, name } => {
+                write!(f, "argument `{}` given more than once", name)?;
+                write_function(f, function)
+            }
+            ErrorKind::MissingArgument { function, name } => {
+                write!(f, "missing argument `{}`", name)?;
+                write_function(f, function)
+            }
+            ErrorKind::AlreadyDeclared(name) => write!(f, "identifier already declared: {}", name),
+            ErrorKind::AssignmentToConstant(name) => write!(f, "assignment to constant: {}", name),
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            #[cfg(not(feature = "bigint"))]
+            ErrorKind::IntegerOverflow(expression) => write!(f, "integer overflow: {}", expression),
+            #[cfg(feature = "bigint")]
+            ErrorKind::InvalidBigInt(digits) => write!(f, "invalid bigint literal: {}n", digits),
+            #[cfg(not(feature = "bigint"))]
+            ErrorKind::BigIntDisabled(literal) => write!(
+                f,
+                "bigint literals require the `bigint` feature: {}",
+                literal
+            ),
+            ErrorKind::NotAFunction(object_type) => write!(f, "not a function: {:?}", object_type),
+            ErrorKind::NotIterable(object_type) => write!(f, "not iterable: {:?}", object_type),
+            ErrorKind::IndexNotSupported(object_type) => {
+                write!(f, "index operator not supported: {:?}", object_type)
+            }
+            ErrorKind::UnusableHashKey(object_type) => {
+                write!(f, "unusable as hash key: {:?}", object_type)
+            }
+            ErrorKind::MisplacedSpread => write!(
+                f,
+                "spread syntax is only allowed in calls and array literals"
+            ),
+            ErrorKind::PatternMismatch(message) => write!(f, "{}", message),
+            ErrorKind::NoMatchArm(value) => {
+                write!(f, "no match arm for value: {}", value.inspect())
+            }
+            ErrorKind::BreakOutsideLoop => write!(f, "break outside of a loop"),
+            ErrorKind::ContinueOutsideLoop => write!(f, "continue outside of a loop"),
+            ErrorKind::LimitExceeded(limit) => write!(f, "{}", limit),
+            ErrorKind::Interrupted => write!(f, "interrupted"),
+            ErrorKind::Thrown(value) => write!(f, "uncaught exception: {}", value.inspect()),
+        }
+    }
+}
+
+/// Names the function an argument error is about, when it has a name.
+fn write_function(f: &mut fmt::Formatter<'_>, function: &Option<String>) -> fmt::Result {
+    match function {
+        Some(name) => write!(f, " to `{}`", name),
+        None => Ok(()),
+    }
+}
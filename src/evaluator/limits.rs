@@ -0,0 +1,101 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1_000;
+pub const DEFAULT_MAX_BIGINT_BYTES: usize = 1 << 20;
+
+/// Bounds on the work and memory one evaluation may use, so untrusted scripts cannot run
+/// forever or exhaust the host. Every bound but the call depth and the size of big integers
+/// is off by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// How many nodes may be evaluated in total.
+    pub max_steps: Option<u64>,
+    /// When evaluation must stop, whatever it is doing.
+    pub deadline: Option<Instant>,
+    /// The longest string, in bytes, that may be built.
+    pub max_string_len: Option<usize>,
+    /// The most elements an array or hash may hold when it is built.
+    pub max_collection_len: Option<usize>,
+    /// How many calls may be in progress at once. The evaluator also takes native stack for
+    /// each call, and stops at a smaller depth when the thread runs low on it.
+    pub max_call_depth: usize,
+    /// The largest big integer, in bytes, that arithmetic may produce. Operations are
+    /// checked before they run, since a single multiplication of huge numbers cannot be
+    /// interrupted.
+    pub max_bigint_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: None,
+            deadline: None,
+            max_string_len: None,
+            max_collection_len: None,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_bigint_bytes: DEFAULT_MAX_BIGINT_BYTES,
+        }
+    }
+}
+
+/// The bound an evaluation ran into, with its configured value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Steps(u64),
+    Deadline,
+    StringLength(usize),
+    CollectionLength(usize),
+    /// The depth calls stopped at: the configured maximum, or less when the native stack
+    /// ran low first.
+    CallDepth(usize),
+    BigIntSize(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Steps(steps) => write!(f, "step limit exceeded: more than {} steps", steps),
+            Limit::Deadline => write!(f, "deadline exceeded"),
+            Limit::StringLength(len) => {
+                write!(f, "string length limit exceeded: more than {} bytes", len)
+            }
+            Limit::CollectionLength(len) => {
+                write!(
+                    f,
+                    "collection size limit exceeded: more than {} elements",
+                    len
+                )
+            }
+            Limit::CallDepth(depth) => {
+                write!(f, "stack overflow: more than {} nested calls", depth)
+            }
+            Limit::BigIntSize(bytes) => {
+                write!(f, "integer size limit exceeded: more than {} bytes", bytes)
+            }
+        }
+    }
+}
+
+/// Stops an evaluation running on another thread. The evaluation fails with
+/// `ErrorKind::Interrupted` at its next step, and the request is then cleared so the same
+/// context can evaluate again.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether an interrupt was requested, clearing the request.
+    pub(super) fn take(&self) -> bool {
+        self.0.load(Ordering::Relaxed) && self.0.swap(false, Ordering::Relaxed)
+    }
+}
@@ -1,149 +1,465 @@
-use anyhow::{anyhow, Result};
-use rinonguci_script::new_error;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
 use crate::{
     ast::{
-        expression::{self, node::IfExpression, ExpressionType},
+        expression::{
+            self,
+            node::{
+                ConditionalExpression, FunctionLiteral, HashLiteral, IfExpression, MatchExpression,
+                Parameter,
+            },
+            ExpressionType,
+        },
         statement::{
-            node::{BlockStatement, ExpressionStatement},
+            node::{
+                BlockStatement, ExpressionStatement, ForStatement, SwitchStatement, TryStatement,
+                WhileStatement,
+            },
             StatementType,
         },
-        Node, Program, TNode,
+        TNode,
     },
+    lexer::Position,
     object::{
-        environment::Environment, Boolean, Function, Integer, Null, Object, ObjectType,
-        ReturnValue, StringObj,
+        environment::Environment, Array, Boolean, Function, Hash, HashKey, HashPair, Integer, Null,
+        Object, ObjectType, Range, ReturnValue, StringObj, TailCall,
     },
+    resolver::ResolvedProgram,
     token::Token,
 };
 
+#[cfg(feature = "bigint")]
+mod bigint;
+mod context;
+mod error;
+mod limits;
+pub(crate) mod pattern;
 pub mod test;
 
-pub fn eval(node: Box<Node>, env: Rc<RefCell<Environment>>) -> Object {
-    match *node {
-        Node::Expression(expr) => eval_expression(expr, env),
-        Node::Statement(stmt) => eval_statement(stmt, env),
-        Node::Program(program) => eval_program(program, env),
-    }
+pub use context::{Backtrace, Context, Frame, DEFAULT_MAX_BACKTRACE_DEPTH};
+pub use error::{ErrorKind, RuntimeError};
+pub use limits::{
+    InterruptHandle, Limit, Limits, DEFAULT_MAX_BIGINT_BYTES, DEFAULT_MAX_CALL_DEPTH,
+};
+
+/// Runs a program in `env`, which must be the environment it was resolved against.
+pub fn eval(
+    program: &ResolvedProgram,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    check_loop_signal(eval_statements(&program.statements, env, ctx)?)
 }
 
-fn eval_expression(expr: ExpressionType, env: Rc<RefCell<Environment>>) -> Object {
+fn eval_expression(
+    expr: &ExpressionType,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    ctx.step()?;
     match expr {
         ExpressionType::IntegerLiteral(expression::node::IntegerLiteral { token }) => {
-            Object::Integer(Integer {
-                value: token.into_int().expect("failed to convert token to int"),
-            })
+            Ok(Object::Integer(Integer {
+                value: *token.as_int().expect("failed to convert token to int"),
+            }))
+        }
+        ExpressionType::BigIntLiteral(expression::node::BigIntLiteral { token }) => {
+            eval_bigint_literal(token)
         }
         ExpressionType::StringLiteral(expression::node::StringLiteral { token }) => {
-            Object::String(StringObj {
+            Ok(Object::String(StringObj {
                 value: token
-                    .into_string()
-                    .expect("failed to convert token to string"),
-            })
+                    .as_string()
+                    .expect("failed to convert token to string")
+                    .as_str()
+                    .into(),
+            }))
         }
         ExpressionType::Boolean(expression::node::Boolean { token }) => {
-            evel_boolean_expression(token)
+            Ok(evel_boolean_expression(token))
         }
-        ExpressionType::Prefix(expression::node::PrefixExpression { operator, right }) => {
-            eval_prefix_expression(operator, eval(right.to_node(), env))
+        ExpressionType::Prefix(expression::node::PrefixExpression {
+            operator,
+            right,
+            position,
+        }) => {
+            let right = eval_expression(right, env, ctx)?;
+            eval_prefix_expression(operator.clone(), right).map_err(|err| err.at(*position))
         }
         ExpressionType::Infix(expression::node::InfixExpression {
             left,
             operator,
             right,
-        }) => eval_infix_expression(operator, left.to_node(), right.to_node(), env),
-        ExpressionType::If(ie) => eval_if_expression(ie, env),
+            position,
+        }) => eval_infix_expression(operator.clone(), left, right, *position, env, ctx),
+        ExpressionType::If(ie) => eval_if_expression(ie, env, ctx),
+        ExpressionType::Conditional(ce) => eval_conditional_expression(ce, env, ctx),
         ExpressionType::Identifier(ident) => {
-            let val = env.borrow().get(ident.token.to_string());
-            match val {
-                Some(val) => val,
-                None => new_error!("identifier not found: {}", ident.token.to_string()),
+            let name = || ident.token.to_string();
+            match ident.slot {
+                Some(slot) => env
+                    .borrow()
+                    .get(slot)
+                    .ok_or_else(|| ErrorKind::UsedBeforeDeclaration(name()).at(ident.position)),
+                None => Err(ErrorKind::UndefinedIdentifier(name()).at(ident.position)),
             }
         }
-        ExpressionType::Fn(func) => {
-            let params = func.parameters;
-            let body = func.body;
-            Object::Function(Function {
-                parameters: params,
-                body: body,
-                env,
-            })
-        }
+        ExpressionType::Fn(func) => Ok(new_function(func, env, ctx)),
         ExpressionType::Call(call) => {
-            let func = eval(call.function.to_node(), Rc::clone(&env));
-            if func.is_error() {
-                return func;
+            let func = eval_expression(&call.function, Rc::clone(&env), ctx)?;
+            let args = eval_expressions(&call.arguments, Rc::clone(&env), ctx)?;
+            let mut named = Vec::with_capacity(call.named.len());
+            for argument in &call.named {
+                let value = eval_expression(&argument.value, Rc::clone(&env), ctx)?;
+                named.push((argument.name.clone(), value));
             }
-
-            let args = eval_expressions(call.arguments, Rc::clone(&env));
-            if args.len() == 1 && args[0].is_error() {
-                return args[0].clone();
+            if call.tail {
+                return Ok(Object::TailCall(Box::new(TailCall {
+                    function: func,
+                    arguments: args,
+                    named,
+                    call_site: call.position,
+                })));
             }
-
-            apply_function(func, args)
+            apply_function(func, args, named, call.position, ctx)
+        }
+        ExpressionType::Array(array) => {
+            let elements = eval_expressions(&array.elements, env, ctx)?;
+            ctx.check_collection_len(elements.len())?;
+            Ok(Object::Array(Array { elements }))
         }
+        ExpressionType::Hash(hash) => eval_hash_literal(hash, env, ctx),
+        ExpressionType::Match(me) => eval_match_expression(me, env, ctx),
+        ExpressionType::Index(ie) => {
+            let left = eval_expression(&ie.left, Rc::clone(&env), ctx)?;
+            let index = eval_expression(&ie.index, env, ctx)?;
+            eval_index_expression(left, index).map_err(|err| err.at(ie.position))
+        }
+        ExpressionType::Spread(spread) => Err(ErrorKind::MisplacedSpread.at(spread.position)),
     }
 }
 
-fn eval_statement(stmt: StatementType, env: Rc<RefCell<Environment>>) -> Object {
+fn eval_statement(
+    stmt: &StatementType,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    ctx.step()?;
     match stmt {
         StatementType::Expression(ExpressionStatement { expression }) => {
-            eval(expression.to_node(), env)
+            eval_expression(expression, env, ctx)
+        }
+        StatementType::Block(BlockStatement { statements, scope }) => eval_statements(
+            statements,
+            Environment::new_enclosed_environment(env, &scope.names),
+            ctx,
+        ),
+        // Declarations are bound when their scope is entered; see `hoist_function_declarations`.
+        StatementType::Function(_) => Ok(Object::Null(Null {})),
+        StatementType::While(ws) => eval_while_statement(ws, env, ctx),
+        StatementType::For(fs) => eval_for_statement(fs, env, ctx),
+        StatementType::Switch(ss) => eval_switch_statement(ss, env, ctx),
+        StatementType::Throw(ts) => {
+            let value = eval_expression(&ts.value, env, ctx)?;
+            Err(ErrorKind::Thrown(value).at(ts.position))
         }
-        StatementType::Block(BlockStatement { statements }) => eval_statements(statements, env),
+        StatementType::Try(ts) => eval_try_statement(ts, env, ctx),
+        StatementType::Break(_) => Ok(Object::Break),
+        StatementType::Continue(_) => Ok(Object::Continue),
         StatementType::Return(node) => {
-            let val = eval(node.value.to_node(), env);
-            Object::Return(ReturnValue {
+            let val = eval_expression(&node.value, env, ctx)?;
+            Ok(Object::Return(ReturnValue {
                 value: Box::new(val),
-            })
+            }))
         }
         StatementType::Let(let_stmt) => {
-            if let Some(_) = let_stmt.token {
-                let val = eval(let_stmt.value.to_node(), Rc::clone(&env));
-                if val.is_error() {
-                    return val;
-                }
-                env.borrow_mut().init(let_stmt.name.to_string(), val)
+            let position = let_stmt.position;
+            if let Some(token) = &let_stmt.token {
+                let val = eval_expression(&let_stmt.value, Rc::clone(&env), ctx)?;
+                let mutable = *token != Token::CONST;
+                let literal = &mut |expr: &ExpressionType, ctx: &mut Context| {
+                    eval_expression(expr, Rc::clone(&env), ctx)
+                };
+                pattern::destructure(&let_stmt.pattern, val.clone(), &env, mutable, ctx, literal)
+                    .map_err(|err| err.at(position))?;
+                Ok(val)
             } else {
-                let is_found = env.borrow().get(let_stmt.name.to_string());
-                if is_found.is_none() {
-                    return new_error!("identifier not found: {}", let_stmt.name.to_string());
-                }
+                let name = let_stmt.pattern.string();
+                let slot = match let_stmt.slot {
+                    Some(slot) if env.borrow().get(slot).is_some() => slot,
+                    Some(_) => return Err(ErrorKind::UsedBeforeDeclaration(name).at(position)),
+                    None => return Err(ErrorKind::UndefinedIdentifier(name).at(position)),
+                };
 
-                println!("is_found: {:?}", is_found);
-                let val = eval(let_stmt.value.to_node(), Rc::clone(&env));
-                if val.is_error() {
-                    return val;
-                }
-                env.borrow_mut().assign(let_stmt.name.to_string(), val)
+                let val = eval_expression(&let_stmt.value, Rc::clone(&env), ctx)?;
+                let assigned = env.borrow_mut().assign(slot, val);
+                assigned.map_err(|err| RuntimeError::from(err).at(position))
             }
         }
     }
 }
 
-fn eval_program(program: Program, env: Rc<RefCell<Environment>>) -> Object {
-    eval_statements(program.statements, env)
-}
+fn eval_statements<'a>(
+    stmts: impl IntoIterator<Item = &'a Box<StatementType>> + Clone,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    hoist_function_declarations(stmts.clone(), &env, ctx)?;
 
-fn eval_statements(stmts: Vec<Box<StatementType>>, env: Rc<RefCell<Environment>>) -> Object {
     let mut result = Object::Null(Null {});
     for statement in stmts {
-        result = eval(statement.to_node(), Rc::clone(&env));
+        result = eval_statement(statement, Rc::clone(&env), ctx)?;
 
         if result.is_return() {
-            return result;
+            return Ok(result);
         }
 
-        if result.is_error() {
+        if result.is_break() || result.is_continue() {
+            return Ok(result);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Binds every `fn name() {}` declaration of a scope before any of its statements run, so
+/// declarations can be called before they appear and can refer to each other.
+fn hoist_function_declarations<'a>(
+    stmts: impl IntoIterator<Item = &'a Box<StatementType>>,
+    env: &Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<(), RuntimeError> {
+    for stmt in stmts {
+        if let StatementType::Function(decl) = stmt.as_ref() {
+            let function = new_function(&decl.function, Rc::clone(env), ctx);
+            env.borrow_mut().init(decl.name(), function)?;
+        }
+    }
+    Ok(())
+}
+
+/// Evaluates a function or loop body in `env` itself rather than in a nested block scope,
+/// so the body shares one scope with the parameters or loop variable.
+fn eval_body(
+    body: &StatementType,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    match body {
+        StatementType::Block(BlockStatement { statements, .. }) => {
+            eval_statements(statements, env, ctx)
+        }
+        body => eval_statement(body, env, ctx),
+    }
+}
+
+/// Creates a closure over `env`. The closure shares the literal's parameters and body, so
+/// neither creating nor calling it copies any of the syntax tree.
+fn new_function(
+    func: &FunctionLiteral,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Object {
+    ctx.track_closure(&env);
+    Object::Function(Rc::new(Function {
+        name: func.name.clone(),
+        parameters: Rc::clone(&func.parameters),
+        body: Rc::clone(&func.body),
+        scope: Rc::clone(&func.scope.names),
+        env,
+    }))
+}
+
+fn eval_while_statement(
+    ws: &WhileStatement,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    loop {
+        let condition = eval_expression(&ws.condition, Rc::clone(&env), ctx)?;
+        if !is_truthy(condition) {
+            break;
+        }
+
+        let result = eval_statement(&ws.body, Rc::clone(&env), ctx)?;
+        match result {
+            Object::Break => break,
+            Object::Continue => continue,
+            Object::Return(_) => return Ok(result),
+            _ => {}
+        }
+    }
+
+    Ok(Object::Null(Null {}))
+}
+
+/// Runs the `catch` clause when the `try` block fails, then always runs `finally`. A
+/// `finally` that itself returns, breaks, continues or fails replaces the earlier outcome.
+/// Errors that are not catchable skip both clauses.
+fn eval_try_statement(
+    ts: &TryStatement,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let mut result = eval_statement(&ts.block, Rc::clone(&env), ctx);
+
+    if let Some(catch) = &ts.catch {
+        result = match result {
+            Err(err) if err.is_catchable() => {
+                let catch_env =
+                    Environment::new_enclosed_environment(Rc::clone(&env), &catch.scope.names);
+                if let Some(name) = &catch.name {
+                    catch_env
+                        .borrow_mut()
+                        .init(name.clone(), caught_value(err))?;
+                }
+                eval_body(&catch.body, catch_env, ctx)
+            }
+            result => result,
+        };
+    }
+    if let Err(err) = &result {
+        if !err.is_catchable() {
             return result;
         }
     }
 
+    if let Some(finally) = &ts.finally {
+        let completion = eval_statement(finally, env, ctx)?;
+        if let Object::Return(_) | Object::Break | Object::Continue = completion {
+            return Ok(completion);
+        }
+    }
+
     result
 }
 
-fn evel_boolean_expression(token: Token) -> Object {
+/// The value a `catch` clause binds, always a hash with a `message` the clause can read:
+///
+/// - a thrown hash, given its printed form as `message` when it has none;
+/// - any other thrown value as `{"message": printed form, "value": value}`;
+/// - an error raised by the interpreter itself as `{"message": description}`.
+pub(crate) fn caught_value(err: RuntimeError) -> Object {
+    let message_key = HashKey::String("message".into());
+    let (mut pairs, message) = match err.kind {
+        ErrorKind::Thrown(Object::Hash(hash)) if hash.pairs.contains_key(&message_key) => {
+            return Object::Hash(hash);
+        }
+        ErrorKind::Thrown(value) => {
+            let message = value.inspect();
+            let pairs = match value {
+                Object::Hash(hash) => hash.pairs,
+                value => {
+                    let mut pairs = BTreeMap::new();
+                    insert_field(&mut pairs, "value", value);
+                    pairs
+                }
+            };
+            (pairs, message)
+        }
+        kind => (BTreeMap::new(), kind.to_string()),
+    };
+
+    let message = Object::String(StringObj {
+        value: message.into(),
+    });
+    insert_field(&mut pairs, "message", message);
+    Object::Hash(Hash { pairs })
+}
+
+fn insert_field(pairs: &mut BTreeMap<HashKey, HashPair>, name: &str, value: Object) {
+    let key = Object::String(StringObj { value: name.into() });
+    pairs.insert(key.hash_key().unwrap(), HashPair { key, value });
+}
+
+/// Runs the statements from the first clause whose test `==` the value, or else from the
+/// `default` clause, falling through later clauses until a `break`.
+fn eval_switch_statement(
+    ss: &SwitchStatement,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let value = eval_expression(&ss.discriminant, Rc::clone(&env), ctx)?;
+
+    let mut start = None;
+    for (idx, case) in ss.cases.iter().enumerate() {
+        if let Some(test) = &case.test {
+            let test = eval_expression(test, Rc::clone(&env), ctx)?;
+            if values_equal(&value, &test) {
+                start = Some(idx);
+                break;
+            }
+        }
+    }
+    let start = match start.or_else(|| ss.cases.iter().position(|c| c.test.is_none())) {
+        Some(start) => start,
+        None => return Ok(Object::Null(Null {})),
+    };
+
+    let statements = ss.cases[start..].iter().flat_map(|case| &case.body);
+    match eval_statements(
+        statements,
+        Environment::new_enclosed_environment(env, &ss.scope.names),
+        ctx,
+    )? {
+        result @ (Object::Return(_) | Object::Continue) => Ok(result),
+        _ => Ok(Object::Null(Null {})),
+    }
+}
+
+fn eval_for_statement(
+    fs: &ForStatement,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let iterable = eval_expression(&fs.iterable, Rc::clone(&env), ctx)?;
+
+    let items = match iterable.iterate() {
+        Some(items) => items,
+        None => return Err(ErrorKind::NotIterable(iterable.object_type()).at(fs.position)),
+    };
+
+    for item in items {
+        // Stepped here as well, since an empty body evaluates nothing.
+        ctx.step().map_err(|err| err.at(fs.position))?;
+        let loop_env = Environment::new_enclosed_environment(Rc::clone(&env), &fs.scope.names);
+        loop_env.borrow_mut().init(fs.name.to_string(), item)?;
+
+        let result = eval_body(&fs.body, loop_env, ctx)?;
+        match result {
+            Object::Break => break,
+            Object::Continue => continue,
+            Object::Return(_) => return Ok(result),
+            _ => {}
+        }
+    }
+
+    Ok(Object::Null(Null {}))
+}
+
+/// Turns a `break`/`continue` signal that escaped every enclosing loop into an error.
+fn check_loop_signal(result: Object) -> Result<Object, RuntimeError> {
+    match result {
+        Object::Break => Err(ErrorKind::BreakOutsideLoop.into()),
+        Object::Continue => Err(ErrorKind::ContinueOutsideLoop.into()),
+        _ => Ok(result),
+    }
+}
+
+#[cfg(feature = "bigint")]
+pub(crate) fn eval_bigint_literal(token: &Token) -> Result<Object, RuntimeError> {
+    let digits = token
+        .as_bigint()
+        .expect("failed to convert token to bigint");
+    bigint::eval_bigint_literal(digits)
+}
+
+#[cfg(not(feature = "bigint"))]
+pub(crate) fn eval_bigint_literal(token: &Token) -> Result<Object, RuntimeError> {
+    Err(ErrorKind::BigIntDisabled(token.to_string()).into())
+}
+
+fn evel_boolean_expression(token: &Token) -> Object {
     match token {
         Token::TRUE => Object::Boolean(Boolean { value: true }),
         Token::FALSE => Object::Boolean(Boolean { value: false }),
@@ -151,11 +467,19 @@ fn evel_boolean_expression(token: Token) -> Object {
     }
 }
 
-fn eval_prefix_expression(operator: Token, right: Object) -> Object {
+pub(crate) fn eval_prefix_expression(
+    operator: Token,
+    right: Object,
+) -> Result<Object, RuntimeError> {
     match operator {
-        Token::BANG => eval_bang_operator_expression(right),
+        Token::BANG => Ok(eval_bang_operator_expression(right)),
         Token::MINUS => evel_minus_prefix_operator_expression(right),
-        _ => new_error!("unknown operator: {:?} {:?}", operator, right.object_type()),
+        _ => Err(ErrorKind::UnknownOperator {
+            left: None,
+            operator,
+            right: right.object_type(),
+        }
+        .into()),
     }
 }
 
@@ -167,113 +491,258 @@ fn eval_bang_operator_expression(right: Object) -> Object {
     }
 }
 
-fn evel_minus_prefix_operator_expression(right: Object) -> Object {
-    if right.object_type() != ObjectType::INTEGER {
-        return new_error!("unknown operator: -{:?}", right.object_type());
+fn evel_minus_prefix_operator_expression(right: Object) -> Result<Object, RuntimeError> {
+    let value = match right {
+        #[cfg(feature = "bigint")]
+        Object::BigInt(big) => return Ok(bigint::eval_bigint_minus_prefix_expression(big.value)),
+        Object::Integer(Integer { value }) => value,
+        right => {
+            return Err(ErrorKind::UnknownOperator {
+                left: None,
+                operator: Token::MINUS,
+                right: right.object_type(),
+            }
+            .into())
+        }
+    };
+    match value.checked_neg() {
+        Some(value) => Ok(Object::Integer(Integer { value })),
+        #[cfg(feature = "bigint")]
+        None => Ok(bigint::eval_bigint_minus_prefix_expression(value.into())),
+        #[cfg(not(feature = "bigint"))]
+        None => Err(ErrorKind::IntegerOverflow(format!("-({})", value)).into()),
     }
-    let value = right.as_integer().unwrap().value;
-    Object::Integer(Integer { value: -value })
 }
 
 fn eval_infix_expression(
     operator: Token,
-    left: Box<Node>,
-    right: Box<Node>,
+    left: &ExpressionType,
+    right: &ExpressionType,
+    position: Position,
     env: Rc<RefCell<Environment>>,
-) -> Object {
-    let left = eval(left, Rc::clone(&env));
-    let right = eval(right, Rc::clone(&env));
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let left = eval_expression(left, Rc::clone(&env), ctx)?;
+    let right = eval_expression(right, env, ctx)?;
+    check_infix_operands(&operator, &left, &right, ctx).map_err(|err| err.at(position))?;
+    let result = apply_infix_operator(operator, left, right).map_err(|err| err.at(position))?;
+    check_value_size(&result, ctx).map_err(|err| err.at(position))?;
+    Ok(result)
+}
+
+/// Fails when `left operator right` would build a number larger than the limits allow.
+#[cfg(feature = "bigint")]
+pub(crate) fn check_infix_operands(
+    operator: &Token,
+    left: &Object,
+    right: &Object,
+    ctx: &Context,
+) -> Result<(), RuntimeError> {
+    match bigint::result_bits(operator, left, right) {
+        Some(bits) => {
+            ctx.check_bigint_bits(bits)?;
+            ctx.check_deadline()
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(feature = "bigint"))]
+pub(crate) fn check_infix_operands(
+    _operator: &Token,
+    _left: &Object,
+    _right: &Object,
+    _ctx: &Context,
+) -> Result<(), RuntimeError> {
+    Ok(())
+}
+
+/// Fails when a string or number an operator built is larger than the limits allow.
+pub(crate) fn check_value_size(value: &Object, ctx: &Context) -> Result<(), RuntimeError> {
+    match value {
+        Object::String(StringObj { value }) => ctx.check_string_len(value.len()),
+        #[cfg(feature = "bigint")]
+        Object::BigInt(_) => ctx.check_bigint_bits(bigint::bits(value).unwrap_or_default()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether `left == right` holds, with the same semantics as the `==` operator.
+pub(crate) fn values_equal(left: &Object, right: &Object) -> bool {
+    matches!(
+        apply_infix_operator(Token::EQ, left.clone(), right.clone()),
+        Ok(Object::Boolean(Boolean { value: true }))
+    )
+}
+
+pub(crate) fn apply_infix_operator(
+    operator: Token,
+    left: Object,
+    right: Object,
+) -> Result<Object, RuntimeError> {
+    #[cfg(feature = "bigint")]
+    if left.is_big_int() || right.is_big_int() {
+        if let (Some(l), Some(r)) = (bigint::to_bigint(&left), bigint::to_bigint(&right)) {
+            return bigint::eval_bigint_infix_expression(operator, l, r);
+        }
+    }
 
     match operator {
-        Token::EQ => Object::Boolean(Boolean {
+        Token::EQ => Ok(Object::Boolean(Boolean {
             value: left == right,
-        }),
-        Token::NOT_EQ => Object::Boolean(Boolean {
+        })),
+        Token::NOT_EQ => Ok(Object::Boolean(Boolean {
             value: left != right,
-        }),
-        _ => match (left.object_type(), right.object_type()) {
-            (ObjectType::INTEGER, ObjectType::INTEGER) => {
-                eval_integer_infix_expression(operator, left, right)
+        })),
+        _ => match (left, right) {
+            (Object::Integer(left), Object::Integer(right)) => {
+                eval_integer_infix_expression(operator, left.value, right.value)
             }
-            (ObjectType::STRING, ObjectType::STRING) if operator == Token::PLUS => {
-                if let (Object::String(left), Object::String(right)) = (left, right) {
-                    Object::String(StringObj {
-                        value: format!("{}{}", left.value, right.value),
-                    })
-                } else {
-                    new_error!("failed to convert object to string")
-                }
+            (Object::String(left), Object::String(right)) if operator == Token::PLUS => {
+                Ok(Object::String(StringObj {
+                    value: format!("{}{}", left.value, right.value).into(),
+                }))
             }
-            (left_type, right_type) => {
-                if left_type != right_type {
-                    return new_error!(
-                        "type mismatch: {:?} {} {:?}",
-                        left_type,
-                        operator.to_string(),
-                        right_type
-                    );
+            (left, right) => {
+                let (left, right) = (left.object_type(), right.object_type());
+                if left != right {
+                    Err(ErrorKind::TypeMismatch {
+                        left,
+                        operator,
+                        right,
+                    }
+                    .into())
                 } else {
-                    return new_error!(
-                        "unknown operator: {:?} {} {:?}",
-                        left_type,
-                        operator.to_string(),
-                        right_type
-                    );
+                    Err(ErrorKind::UnknownOperator {
+                        left: Some(left),
+                        operator,
+                        right,
+                    }
+                    .into())
                 }
             }
         },
     }
 }
 
-fn eval_integer_infix_expression(operator: Token, left: Object, right: Object) -> Object {
-    match (left, right) {
-        (
-            Object::Integer(Integer { value: left_val }),
-            Object::Integer(Integer { value: right_val }),
-        ) => match operator {
-            Token::PLUS => Object::Integer(Integer {
-                value: left_val + right_val,
-            }),
-            Token::MINUS => Object::Integer(Integer {
-                value: left_val - right_val,
-            }),
-            Token::ASTERISK => Object::Integer(Integer {
-                value: left_val * right_val,
-            }),
-            Token::SLASH => Object::Integer(Integer {
-                value: left_val / right_val,
-            }),
-            Token::LT => Object::Boolean(Boolean {
-                value: left_val < right_val,
-            }),
-            Token::GT => Object::Boolean(Boolean {
-                value: left_val > right_val,
-            }),
-            Token::EQ => Object::Boolean(Boolean {
-                value: left_val == right_val,
-            }),
-            Token::NOT_EQ => Object::Boolean(Boolean {
-                value: left_val != right_val,
-            }),
-            _ => new_error!("unknown operator: {:?}", operator),
-        },
-        _ => new_error!("unknown operator: {:?}", operator),
+pub(crate) fn eval_integer_infix_expression(
+    operator: Token,
+    left_val: i64,
+    right_val: i64,
+) -> Result<Object, RuntimeError> {
+    match operator {
+        Token::PLUS => checked_integer_result(
+            left_val.checked_add(right_val),
+            operator,
+            left_val,
+            right_val,
+        ),
+        Token::MINUS => checked_integer_result(
+            left_val.checked_sub(right_val),
+            operator,
+            left_val,
+            right_val,
+        ),
+        Token::ASTERISK => checked_integer_result(
+            left_val.checked_mul(right_val),
+            operator,
+            left_val,
+            right_val,
+        ),
+        Token::SLASH => {
+            if right_val == 0 {
+                return Err(ErrorKind::DivisionByZero.into());
+            }
+            checked_integer_result(
+                left_val.checked_div(right_val),
+                operator,
+                left_val,
+                right_val,
+            )
+        }
+        Token::DOTDOT => Ok(Object::Range(Range {
+            start: left_val,
+            end: right_val,
+            inclusive: false,
+        })),
+        Token::DOTDOT_EQ => Ok(Object::Range(Range {
+            start: left_val,
+            end: right_val,
+            inclusive: true,
+        })),
+        Token::LT => Ok(Object::Boolean(Boolean {
+            value: left_val < right_val,
+        })),
+        Token::GT => Ok(Object::Boolean(Boolean {
+            value: left_val > right_val,
+        })),
+        Token::EQ => Ok(Object::Boolean(Boolean {
+            value: left_val == right_val,
+        })),
+        Token::NOT_EQ => Ok(Object::Boolean(Boolean {
+            value: left_val != right_val,
+        })),
+        _ => Err(ErrorKind::UnknownOperator {
+            left: Some(ObjectType::INTEGER),
+            operator,
+            right: ObjectType::INTEGER,
+        }
+        .into()),
+    }
+}
+
+/// Wraps a checked `i64` operation, promoting to a `BigInt` when it overflowed.
+fn checked_integer_result(
+    result: Option<i64>,
+    operator: Token,
+    left: i64,
+    right: i64,
+) -> Result<Object, RuntimeError> {
+    match result {
+        Some(value) => Ok(Object::Integer(Integer { value })),
+        #[cfg(feature = "bigint")]
+        None => bigint::eval_bigint_infix_expression(operator, left.into(), right.into()),
+        #[cfg(not(feature = "bigint"))]
+        None => {
+            Err(
+                ErrorKind::IntegerOverflow(format!("{} {} {}", left, operator.to_string(), right))
+                    .into(),
+            )
+        }
+    }
+}
+
+fn eval_if_expression(
+    ie: &IfExpression,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let condition = eval_expression(&ie.condition, Rc::clone(&env), ctx)?;
+
+    if is_truthy(condition) {
+        eval_statement(&ie.consequence, env, ctx)
+    } else if let Some(alternative) = &ie.alternative {
+        eval_statement(alternative, env, ctx)
+    } else {
+        Ok(Object::Null(Null {}))
     }
 }
 
-fn eval_if_expression(ie: IfExpression, env: Rc<RefCell<Environment>>) -> Object {
-    let condition = eval(ie.condition.to_node(), Rc::clone(&env));
+fn eval_conditional_expression(
+    ce: &ConditionalExpression,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let condition = eval_expression(&ce.condition, Rc::clone(&env), ctx)?;
 
     if is_truthy(condition) {
-        return eval(ie.consequence.to_node(), Rc::clone(&env));
-    } else if ie.alternative.is_some() {
-        return eval(ie.alternative.unwrap().to_node(), Rc::clone(&env));
+        eval_expression(&ce.consequence, env, ctx)
     } else {
-        return Object::Null(Null {});
+        eval_expression(&ce.alternative, env, ctx)
     }
 }
 
-fn is_truthy(obj: Object) -> bool {
+pub(crate) fn is_truthy(obj: Object) -> bool {
     match obj {
         Object::Null(_) => false,
         Object::Boolean(Boolean { value }) => value,
@@ -281,48 +750,283 @@ fn is_truthy(obj: Object) -> bool {
     }
 }
 
-fn eval_expressions(exps: Vec<Box<ExpressionType>>, env: Rc<RefCell<Environment>>) -> Vec<Object> {
+fn eval_expressions(
+    exps: &[Box<ExpressionType>],
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Vec<Object>, RuntimeError> {
     let mut result = vec![];
     for e in exps {
-        let evaluated = eval(e.to_node(), Rc::clone(&env));
-        if evaluated.is_error() {
-            return vec![evaluated];
+        if let ExpressionType::Spread(spread) = e.as_ref() {
+            let evaluated = eval_expression(&spread.value, Rc::clone(&env), ctx)?;
+            let items = match evaluated.iterate() {
+                Some(items) => items,
+                None => {
+                    return Err(ErrorKind::NotIterable(evaluated.object_type()).at(spread.position))
+                }
+            };
+            // Checked item by item, since spreading a range builds its elements lazily.
+            for item in items {
+                ctx.step().map_err(|err| err.at(spread.position))?;
+                result.push(item);
+                ctx.check_collection_len(result.len())
+                    .map_err(|err| err.at(spread.position))?;
+            }
+            continue;
         }
-        result.push(evaluated);
+
+        result.push(eval_expression(e, Rc::clone(&env), ctx)?);
     }
-    result
+    Ok(result)
 }
 
-fn apply_function(func_obj: Object, args: Vec<Object>) -> Object {
-    if let Object::Function(func_obj) = func_obj {
-        let extended_env = extend_function_env(&func_obj, args);
-        if extended_env.is_err() {
-            return new_error!("{}", extended_env.err().unwrap().to_string());
+/// Tries each arm in order. An arm runs in its own scope holding its pattern's bindings,
+/// and only when its guard, if any, is truthy.
+fn eval_match_expression(
+    me: &MatchExpression,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let subject = eval_expression(&me.subject, Rc::clone(&env), ctx)?;
+
+    for arm in &me.arms {
+        let arm_env = Environment::new_enclosed_environment(Rc::clone(&env), &arm.scope.names);
+        let mut bindings = Vec::new();
+        let literal = &mut |expr: &ExpressionType, ctx: &mut Context| {
+            eval_expression(expr, Rc::clone(&arm_env), ctx)
+        };
+        match pattern::match_pattern(&arm.pattern, subject.clone(), &mut bindings, ctx, literal) {
+            Ok(()) => {}
+            Err(pattern::Mismatch::Shape(_)) => continue,
+            Err(pattern::Mismatch::Error(err)) => return Err(err),
+        }
+        for (name, value) in bindings {
+            arm_env.borrow_mut().init(name, value)?;
         }
-        let evaluated = eval(func_obj.body.to_node(), extended_env.unwrap());
 
-        if let Object::Return(ReturnValue { value }) = evaluated {
-            *value
-        } else {
-            evaluated
+        if let Some(guard) = &arm.guard {
+            let guard = eval_expression(guard, Rc::clone(&arm_env), ctx)?;
+            if !is_truthy(guard) {
+                continue;
+            }
+        }
+
+        return eval_statement(&arm.body, arm_env, ctx);
+    }
+
+    Err(ErrorKind::NoMatchArm(subject).at(me.position))
+}
+
+fn eval_hash_literal(
+    hash: &HashLiteral,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let mut pairs = BTreeMap::new();
+    for (key, value) in &hash.pairs {
+        let key = eval_expression(key, Rc::clone(&env), ctx)?;
+        let hash_key = match key.hash_key() {
+            Some(hash_key) => hash_key,
+            None => return Err(ErrorKind::UnusableHashKey(key.object_type()).at(hash.position)),
+        };
+
+        let value = eval_expression(value, Rc::clone(&env), ctx)?;
+        pairs.insert(hash_key, HashPair { key, value });
+        ctx.check_collection_len(pairs.len())
+            .map_err(|err| err.at(hash.position))?;
+    }
+
+    Ok(Object::Hash(Hash { pairs }))
+}
+
+/// Indexes an array by position or a hash by key. Any other value only has a `message`,
+/// which is the value as text, so `e.message` works in a `catch` clause whatever was thrown.
+pub(crate) fn eval_index_expression(left: Object, index: Object) -> Result<Object, RuntimeError> {
+    match (left, index) {
+        (Object::Array(Array { elements }), Object::Integer(Integer { value })) => {
+            Ok(usize::try_from(value)
+                .ok()
+                .and_then(|i| elements.get(i).cloned())
+                .unwrap_or(Object::Null(Null {})))
+        }
+        (Object::Hash(Hash { pairs }), index) => match index.hash_key() {
+            Some(key) => Ok(pairs
+                .get(&key)
+                .map(|pair| pair.value.clone())
+                .unwrap_or(Object::Null(Null {}))),
+            None => Err(ErrorKind::UnusableHashKey(index.object_type()).into()),
+        },
+        (left, _) => Err(ErrorKind::IndexNotSupported(left.object_type()).into()),
+    }
+}
+
+/// Calls `func_obj` with a frame for it on the call stack. An error leaving the call records
+/// the stack as it was when the error was raised.
+///
+/// A tail call the function ends with is made here in a loop, replacing the finished call's
+/// frame instead of nesting inside it, so tail recursion runs in constant stack.
+fn apply_function(
+    mut func_obj: Object,
+    mut args: Vec<Object>,
+    mut named: Vec<(String, Object)>,
+    mut call_site: Position,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    loop {
+        let function = match func_obj {
+            Object::Function(function) => function,
+            func_obj => return Err(ErrorKind::NotAFunction(func_obj.object_type()).at(call_site)),
+        };
+        ctx.check_call_depth().map_err(|err| err.at(call_site))?;
+
+        ctx.push_frame(Frame {
+            function: function.name.clone(),
+            call_site,
+        });
+        let mut result = call_function(function, args, named, ctx);
+        if let Err(err) = &mut result {
+            err.backtrace
+                .get_or_insert_with(|| Box::new(ctx.backtrace()));
+        }
+        ctx.pop_frame();
+
+        match result.map_err(|err| err.at(call_site))? {
+            Object::TailCall(tail) => {
+                func_obj = tail.function;
+                args = tail.arguments;
+                named = tail.named;
+                call_site = tail.call_site;
+            }
+            result => return Ok(result),
         }
+    }
+}
+
+fn call_function(
+    func_obj: Rc<Function>,
+    args: Vec<Object>,
+    named: Vec<(String, Object)>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let extended_env = extend_function_env(&func_obj, args, named, ctx)?;
+    let evaluated = check_loop_signal(eval_body(&func_obj.body, extended_env, ctx)?)?;
+
+    if let Object::Return(ReturnValue { value }) = evaluated {
+        Ok(*value)
     } else {
-        new_error!("not a function: {:?}", func_obj.object_type())
+        Ok(evaluated)
     }
 }
 
-fn extend_function_env(func_obj: &Function, args: Vec<Object>) -> Result<Rc<RefCell<Environment>>> {
-    let env = Environment::new_enclosed_environment(Rc::clone(&func_obj.env));
-    if func_obj.parameters.len() != args.len() {
-        Err(anyhow!(format!(
-            "wrong number of arguments. got={}, want={}",
-            args.len(),
-            func_obj.parameters.len()
-        )))?;
+/// Binds call arguments to parameters. A missing argument falls back to the parameter's
+/// default, evaluated in the new environment, and a trailing `...rest` parameter collects
+/// the remaining arguments into an array.
+fn extend_function_env(
+    func_obj: &Function,
+    args: Vec<Object>,
+    named: Vec<(String, Object)>,
+    ctx: &mut Context,
+) -> Result<Rc<RefCell<Environment>>, RuntimeError> {
+    let env = Environment::new_enclosed_environment(Rc::clone(&func_obj.env), &func_obj.scope);
+
+    let mut bound = bind_arguments(&func_obj.name, &func_obj.parameters, args, named)?;
+    for (param, value) in func_obj.parameters.iter().zip(bound.values) {
+        let value = match (value, &param.default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => eval_expression(default, Rc::clone(&env), ctx)?,
+            (None, None) => Object::Null(Null {}),
+        };
+        env.borrow_mut().init(param.name.clone(), value)?;
     }
-    for (param_idx, param) in func_obj.parameters.iter().enumerate() {
+    if let (Some(param), Some(elements)) = (func_obj.parameters.last(), bound.rest.take()) {
         env.borrow_mut()
-            .init(param.as_ref().string(), args[param_idx].clone());
+            .init(param.name.clone(), Object::Array(Array { elements }))?;
     }
     Ok(env)
 }
+
+/// The arguments a call passes to each parameter.
+pub(crate) struct BoundArguments {
+    /// The value of each parameter before the rest parameter, or `None` where its default
+    /// applies.
+    pub values: Vec<Option<Object>>,
+    /// The arguments the rest parameter collects, when the function has one.
+    pub rest: Option<Vec<Object>>,
+}
+
+/// Matches the positional and named arguments of a call to `parameters`. Positional
+/// arguments fill the parameters in order, the rest parameter taking what is left over,
+/// and each named argument then fills the parameter of its name.
+pub(crate) fn bind_arguments(
+    function: &Option<String>,
+    parameters: &[Parameter],
+    args: Vec<Object>,
+    named: Vec<(String, Object)>,
+) -> Result<BoundArguments, RuntimeError> {
+    let (min, max) = function_arity(parameters);
+    let too_few = named.is_empty() && args.len() < min;
+    if too_few || max.is_some_and(|max| args.len() > max) {
+        return Err(ErrorKind::ArityMismatch {
+            function: function.clone(),
+            got: args.len(),
+            min,
+            max,
+        }
+        .into());
+    }
+
+    let positional = parameters.iter().take_while(|param| !param.rest).count();
+    let mut args = args.into_iter();
+    let mut values: Vec<Option<Object>> = args.by_ref().take(positional).map(Some).collect();
+    values.resize(positional, None);
+    let rest = match parameters.last() {
+        Some(param) if param.rest => Some(args.collect()),
+        _ => None,
+    };
+
+    for (name, value) in named {
+        let index = parameters[..positional]
+            .iter()
+            .position(|param| param.name == name);
+        let slot = match index {
+            Some(index) => &mut values[index],
+            None => {
+                let function = function.clone();
+                return Err(ErrorKind::UnknownArgument { function, name }.into());
+            }
+        };
+        if slot.is_some() {
+            let function = function.clone();
+            return Err(ErrorKind::DuplicateArgument { function, name }.into());
+        }
+        *slot = Some(value);
+    }
+
+    for (param, value) in parameters.iter().zip(&values) {
+        if value.is_none() && param.default.is_none() {
+            return Err(ErrorKind::MissingArgument {
+                function: function.clone(),
+                name: param.name.clone(),
+            }
+            .into());
+        }
+    }
+    Ok(BoundArguments { values, rest })
+}
+
+/// The accepted argument counts as `(min, max)`, where `max` is `None` with a rest parameter.
+pub(crate) fn function_arity(parameters: &[Parameter]) -> (usize, Option<usize>) {
+    let mut min = 0;
+    let mut max = Some(0);
+    for (idx, param) in parameters.iter().enumerate() {
+        if param.rest {
+            max = None;
+        } else {
+            if param.default.is_none() {
+                min = idx + 1;
+            }
+            max = max.map(|max| max + 1);
+        }
+    }
+    (min, max)
+}
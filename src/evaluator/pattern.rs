@@ -0,0 +1,210 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ast::{
+        expression::ExpressionType,
+        pattern::{
+            node::{ArrayPattern, HashPattern},
+            Pattern,
+        },
+        TNode,
+    },
+    object::{environment::Environment, Array, Hash, HashKey, Object},
+};
+
+use super::{values_equal, Context, ErrorKind, RuntimeError};
+
+/// Evaluates the literals a pattern compares against.
+pub type Literal<'a> =
+    dyn FnMut(&ExpressionType, &mut Context) -> Result<Object, RuntimeError> + 'a;
+
+/// Why a value could not be bound to a pattern.
+pub enum Mismatch {
+    /// The value does not have the pattern's shape. `let` reports this as an error while
+    /// `match` moves on to the next arm.
+    Shape(String),
+    /// Evaluating a literal in the pattern failed.
+    Error(RuntimeError),
+}
+
+/// Binds every name in `pattern` to the matching part of `value` in `env`, reporting a value
+/// whose shape does not fit the pattern as an error.
+pub fn destructure(
+    pattern: &Pattern,
+    value: Object,
+    env: &Rc<RefCell<Environment>>,
+    mutable: bool,
+    ctx: &mut Context,
+    literal: &mut Literal,
+) -> Result<(), RuntimeError> {
+    for (name, value) in bindings(pattern, value, ctx, literal)? {
+        match mutable {
+            true => env.borrow_mut().init(name, value)?,
+            false => env.borrow_mut().init_const(name, value)?,
+        };
+    }
+    Ok(())
+}
+
+/// The values the names of `pattern` bind to in `value`, failing when its shape does not
+/// fit.
+pub fn bindings(
+    pattern: &Pattern,
+    value: Object,
+    ctx: &mut Context,
+    literal: &mut Literal,
+) -> Result<Vec<(String, Object)>, RuntimeError> {
+    let mut bindings = Vec::new();
+    match match_pattern(pattern, value, &mut bindings, ctx, literal) {
+        Ok(()) => Ok(bindings),
+        Err(Mismatch::Shape(message)) => Err(ErrorKind::PatternMismatch(message).into()),
+        Err(Mismatch::Error(err)) => Err(err),
+    }
+}
+
+/// Checks `value` against `pattern`, collecting the values its names bind to. Array
+/// patterns accept anything iterable, taking one step per item. Literals are evaluated with
+/// `literal`.
+pub fn match_pattern(
+    pattern: &Pattern,
+    value: Object,
+    bindings: &mut Vec<(String, Object)>,
+    ctx: &mut Context,
+    literal: &mut Literal,
+) -> Result<(), Mismatch> {
+    match pattern {
+        Pattern::Identifier(name) => {
+            bindings.push((name.clone(), value));
+            Ok(())
+        }
+        Pattern::Wildcard => Ok(()),
+        Pattern::Literal(expr) => {
+            let expected = literal(expr, ctx).map_err(Mismatch::Error)?;
+            match values_equal(&expected, &value) {
+                true => Ok(()),
+                false => Err(Mismatch::Shape(format!(
+                    "value {} does not match {}",
+                    value.inspect(),
+                    pattern.string()
+                ))),
+            }
+        }
+        Pattern::Or(alternatives) => {
+            let mut mismatch = None;
+            for alternative in alternatives {
+                let mut alternative_bindings = Vec::new();
+                match match_pattern(
+                    alternative,
+                    value.clone(),
+                    &mut alternative_bindings,
+                    ctx,
+                    literal,
+                ) {
+                    Ok(()) => {
+                        bindings.append(&mut alternative_bindings);
+                        return Ok(());
+                    }
+                    Err(Mismatch::Error(err)) => return Err(Mismatch::Error(err)),
+                    Err(shape) => mismatch = Some(shape),
+                }
+            }
+            Err(mismatch.unwrap())
+        }
+        Pattern::Array(pattern) => match_array(pattern, value, bindings, ctx, literal),
+        Pattern::Hash(pattern) => match_hash(pattern, value, bindings, ctx, literal),
+    }
+}
+
+fn match_array(
+    pattern: &ArrayPattern,
+    value: Object,
+    bindings: &mut Vec<(String, Object)>,
+    ctx: &mut Context,
+    literal: &mut Literal,
+) -> Result<(), Mismatch> {
+    let iter = match value.iterate() {
+        Some(iter) => iter,
+        None => {
+            return Err(Mismatch::Shape(format!(
+                "cannot destructure {:?} with an array pattern",
+                value.object_type()
+            )))
+        }
+    };
+
+    // A range yields its items lazily, so only those a name binds are kept.
+    let want = pattern.elements.len();
+    let mut items = Vec::with_capacity(want);
+    let mut rest = Vec::new();
+    let mut len = 0;
+    for item in iter {
+        ctx.step().map_err(Mismatch::Error)?;
+        len += 1;
+        if items.len() < want {
+            items.push(item);
+        } else if pattern.rest.is_some() {
+            rest.push(item);
+            ctx.check_collection_len(rest.len())
+                .map_err(Mismatch::Error)?;
+        }
+    }
+
+    match &pattern.rest {
+        None if len != want => {
+            return Err(Mismatch::Shape(format!(
+                "array pattern expects {} elements, got {}",
+                want, len
+            )))
+        }
+        Some(_) if len < want => {
+            return Err(Mismatch::Shape(format!(
+                "array pattern expects at least {} elements, got {}",
+                want, len
+            )))
+        }
+        _ => {}
+    }
+
+    for (element, item) in pattern.elements.iter().zip(items) {
+        match_pattern(element, item, bindings, ctx, literal)?;
+    }
+    if let Some(name) = &pattern.rest {
+        let elements = rest;
+        bindings.push((name.clone(), Object::Array(Array { elements })));
+    }
+    Ok(())
+}
+
+fn match_hash(
+    pattern: &HashPattern,
+    value: Object,
+    bindings: &mut Vec<(String, Object)>,
+    ctx: &mut Context,
+    literal: &mut Literal,
+) -> Result<(), Mismatch> {
+    let mut pairs = match value {
+        Object::Hash(Hash { pairs }) => pairs,
+        value => {
+            return Err(Mismatch::Shape(format!(
+                "cannot destructure {:?} with a hash pattern",
+                value.object_type()
+            )))
+        }
+    };
+
+    for entry in &pattern.entries {
+        match pairs.remove(&HashKey::String(entry.key.as_str().into())) {
+            Some(pair) => match_pattern(&entry.value, pair.value, bindings, ctx, literal)?,
+            None => {
+                return Err(Mismatch::Shape(format!(
+                    "key not found in hash pattern: {}",
+                    entry.key
+                )))
+            }
+        }
+    }
+    if let Some(name) = &pattern.rest {
+        bindings.push((name.clone(), Object::Hash(Hash { pairs })));
+    }
+    Ok(())
+}
@@ -1,13 +1,21 @@
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
 
     use crate::{
         ast::TNode,
-        evaluator::eval,
-        lexer::Lexer,
-        object::{environment::Environment, Integer, Null, Object},
+        cli::Engine,
+        evaluator::{
+            Context, ErrorKind, Frame, Limit, Limits, RuntimeError, DEFAULT_MAX_CALL_DEPTH,
+        },
+        lexer::{Lexer, Position},
+        object::{environment::Environment, Function, HashKey, Integer, Null, Object, ObjectType},
         parser::Parser,
+        resolver::resolve,
+        token::Token,
     };
 
     #[test]
@@ -108,6 +116,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_else_if_expressions() {
+        let tests = vec![
+            ("if (1 > 2) { 10 } else if (2 > 1) { 20 } else { 30 }", 20),
+            ("if (1 > 2) { 10 } else if (2 > 3) { 20 } else { 30 }", 30),
+            ("if (1 < 2) { 10 } else if (2 > 1) { 20 } else { 30 }", 10),
+            (
+                "let x = 3; if (x == 1) { 1 } else if (x == 2) { 2 } else if (x == 3) { 3 } else { 4 }",
+                3,
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        test_null_object(test_eval("if (false) { 1 } else if (false) { 2 }"));
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        let tests = vec![
+            ("true ? 1 : 2", 1),
+            ("false ? 1 : 2", 2),
+            ("1 > 2 ? 1 : 2 + 3", 5),
+            ("let x = 2; x == 1 ? 10 : x == 2 ? 20 : 30", 20),
+            ("let x = 3; x == 1 ? 10 : x == 2 ? 20 : 30", 30),
+            (
+                "let f = fn(n) { n < 2 ? n : f(n - 1) + f(n - 2) }; f(10)",
+                55,
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let err = test_eval_error("foobar ? 1 : 2");
+        assert_eq!(err.kind.to_string(), "identifier not found: foobar");
+    }
+
     #[test]
     fn test_return_statements() {
         let tests = vec![
@@ -152,14 +201,361 @@ mod tests {
             (r#""Hello" - "World!""#, "unknown operator: STRING - STRING"),
         ];
         for tt in tests {
-            let evaluated = test_eval(tt.0);
-            let err_obj = evaluated.into_error().expect("not an error object");
+            let err = test_eval_error(tt.0);
             assert_eq!(
-                err_obj.message, tt.1,
+                err.kind.to_string(),
+                tt.1,
                 "expected={}, got={}",
-                tt.1, err_obj.message
+                tt.1,
+                err.kind
+            );
+        }
+    }
+
+    #[test]
+    fn test_backtraces() {
+        let input = "
+let inner = fn(x) { x + true };
+fn outer(x) {
+  inner(x) + 1
+}
+let values = [1, 2];
+outer(values[0]);";
+        let err = test_eval_error(input);
+        assert_eq!(err.to_string(), "2:23: type mismatch: INTEGER + BOOLEAN");
+        let backtrace = err.backtrace.as_ref().expect("no backtrace");
+        assert_eq!(
+            backtrace.frames,
+            vec![
+                Frame {
+                    function: Some("inner".into()),
+                    call_site: Position { line: 4, column: 8 },
+                },
+                Frame {
+                    function: Some("outer".into()),
+                    call_site: Position { line: 7, column: 6 },
+                },
+            ]
+        );
+        assert_eq!(
+            err.report(),
+            "2:23: type mismatch: INTEGER + BOOLEAN\n    \
+             at inner, called at 4:8\n    \
+             at outer, called at 7:6"
+        );
+
+        // An error outside of any call has no backtrace, and a caught error leaves the call
+        // stack balanced for later errors.
+        assert_eq!(test_eval_error("1 + true").backtrace, None);
+        let input = "
+let f = fn() { throw 1 };
+try { f() } catch {}
+fn() { f() + 1 }()";
+        let frames = test_eval_error(input).backtrace.unwrap().frames;
+        let names: Vec<_> = frames.iter().map(|f| f.function.clone()).collect();
+        assert_eq!(names, vec![Some("f".to_string()), None]);
+
+        let mut ctx = Context::new();
+        ctx.max_backtrace_depth = 2;
+        let input = "fn down(n) { if (n == 0) { 1 + true } else { down(n - 1) + 1 } } down(5)";
+        let backtrace = try_eval_with(input, &mut ctx)
+            .unwrap_err()
+            .backtrace
+            .unwrap();
+        assert_eq!(backtrace.frames.len(), 2);
+        assert_eq!(backtrace.omitted, 4);
+        assert!(backtrace.to_string().ends_with("... 4 more frames"));
+    }
+
+    #[test]
+    fn test_call_depth_and_tail_calls() {
+        let mut ctx = Context::new();
+        ctx.limits.max_call_depth = 10;
+
+        // Calls in tail position replace the caller's frame, so they never hit the limit.
+        let tests = vec![
+            (
+                "fn sum(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); } sum(1000, 0)",
+                500500,
+            ),
+            ("let count = fn(n) { n == 0 ? 0 : count(n - 1) }; count(1000)", 0),
+            ("let count = (n) => n == 0 ? 0 : count(n - 1); count(1000)", 0),
+            ("fn count(n) { match (n) { 0 => 0, _ => count(n - 1) } } count(1000)", 0),
+            (
+                "fn count(n) { if (n > 0) { let m = n - 1; return count(m); } n } count(1000)",
+                0,
+            ),
+            (
+                "fn count(n) { for (x in [1]) { if (n > 0) { return count(n - 1); } } n } count(1000)",
+                0,
+            ),
+            (
+                "let even = fn(n) { n == 0 ? 1 : odd(n - 1) }; let odd = fn(n) { n == 0 ? 0 : even(n - 1) }; even(1001)",
+                0,
+            ),
+        ];
+        for (input, expected) in tests {
+            let evaluated = try_eval_with(input, &mut ctx)
+                .unwrap_or_else(|e| panic!("eval returned an error for {}: {}", input, e));
+            test_integer_object(evaluated, expected);
+        }
+
+        let tests = vec![
+            "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(1000)",
+            "fn f(n) { if (n == 0) { return 0; } let r = f(n - 1); r } f(1000)",
+            "fn f(n) { if (n > 0) { f(n - 1); } 0 } f(1000)",
+            "fn f(n) { if (n == 0) { return 0; } try { return f(n - 1); } finally {} } f(1000)",
+        ];
+        for input in tests {
+            let err = try_eval_with(input, &mut ctx).expect_err(input);
+            assert_eq!(
+                err.kind,
+                ErrorKind::LimitExceeded(Limit::CallDepth(10)),
+                "{}",
+                input
             );
         }
+        assert_eq!(ctx.depth(), 0);
+
+        let input = "fn f(n) { 1 + f(n + 1) } try { f(0) } catch (e) { e.message }";
+        let evaluated = try_eval_with(input, &mut ctx).unwrap();
+        assert_eq!(
+            evaluated.inspect(),
+            "stack overflow: more than 10 nested calls"
+        );
+
+        // The default depth fits in the stack the CLI evaluates on.
+        let engine = ENGINE.with(Cell::get);
+        let overflowed = std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || {
+                ENGINE.with(|cell| cell.set(engine));
+                let input = "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }";
+                test_integer_object(test_eval(&format!("{} f(900)", input)), 900);
+                test_eval_error(&format!("{} f(5000)", input)).kind
+                    == ErrorKind::LimitExceeded(Limit::CallDepth(DEFAULT_MAX_CALL_DEPTH))
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(overflowed);
+
+        // A thread with little stack reports an overflow before running out of it.
+        let engine = ENGINE.with(Cell::get);
+        let overflowed = std::thread::Builder::new()
+            .stack_size(1024 * 1024)
+            .spawn(move || {
+                ENGINE.with(|cell| cell.set(engine));
+                let input = "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(5000)";
+                matches!(
+                    test_eval_error(input).kind,
+                    ErrorKind::LimitExceeded(Limit::CallDepth(_))
+                )
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(overflowed);
+    }
+
+    #[test]
+    fn test_execution_limits() {
+        let limits = Limits {
+            max_steps: Some(10_000),
+            max_string_len: Some(100),
+            max_collection_len: Some(50),
+            max_call_depth: 20,
+            max_bigint_bytes: 64,
+            ..Limits::default()
+        };
+
+        let tests = vec![
+            (
+                "let x = 0; while (true) { x = x + 1; }",
+                Limit::Steps(10_000),
+            ),
+            // Loops that evaluate no node per item still take steps.
+            ("for (x in 0..1000000000000) {}", Limit::Steps(10_000)),
+            ("let [a] = 0..1000000000000;", Limit::Steps(10_000)),
+            (
+                "let [a, ...b] = 0..1000000000000;",
+                Limit::CollectionLength(50),
+            ),
+            ("fn f() { 1 + f() } f()", Limit::CallDepth(20)),
+            (
+                "let s = \"ab\"; while (true) { s = s + s; }",
+                Limit::StringLength(100),
+            ),
+            ("[...0..1000000000000]", Limit::CollectionLength(50)),
+            (
+                "let a = []; while (true) { a = [...a, 1]; }",
+                Limit::CollectionLength(50),
+            ),
+            (
+                "let a = [...0..40]; [...a, ...a]",
+                Limit::CollectionLength(50),
+            ),
+            // Limits end the evaluation even inside `try`, without running `finally`.
+            (
+                "let x = 0; try { while (true) {} } catch (e) { x } finally { x = 1; }",
+                Limit::Steps(10_000),
+            ),
+        ];
+        for (input, expected) in tests {
+            let mut ctx = Context::with_limits(limits.clone());
+            let err = try_eval_with(input, &mut ctx).expect_err(input);
+            assert_eq!(err.kind, ErrorKind::LimitExceeded(expected), "{}", input);
+        }
+
+        let mut ctx = Context::with_limits(limits.clone());
+        test_integer_object(try_eval_with("let a = [...0..50]; 1", &mut ctx).unwrap(), 1);
+        assert!(ctx.steps() > 0);
+
+        let mut ctx = Context::with_limits(Limits {
+            max_steps: Some(10_000),
+            ..Limits::default()
+        });
+        let err = try_eval_with("[...0..1000000000000]", &mut ctx).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::LimitExceeded(Limit::Steps(10_000)));
+
+        // Big integers are checked before the operation that would outgrow the limit runs.
+        #[cfg(feature = "bigint")]
+        for input in [
+            "let x = 3n; while (true) { x = x * x; }",
+            "let x = 3n; while (true) { x = x + x; }",
+        ] {
+            let mut ctx = Context::with_limits(limits.clone());
+            let err = try_eval_with(input, &mut ctx).expect_err(input);
+            assert_eq!(err.kind, ErrorKind::LimitExceeded(Limit::BigIntSize(64)));
+        }
+
+        let mut ctx = Context::with_limits(Limits {
+            deadline: Some(std::time::Instant::now()),
+            ..Limits::default()
+        });
+        let err = try_eval_with("while (true) {}", &mut ctx).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::LimitExceeded(Limit::Deadline));
+
+        // An interrupt from another thread stops the evaluation once, after which the
+        // context can be used again.
+        let mut ctx = Context::new();
+        let handle = ctx.interrupt_handle();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            handle.interrupt();
+        });
+        let err = try_eval_with("try { while (true) {} } catch (e) { 1 }", &mut ctx).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Interrupted);
+        test_integer_object(try_eval_with("1 + 1", &mut ctx).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_cycle_collection() {
+        let mut ctx = Context::new();
+
+        // A recursive closure and the scope binding it reference each other.
+        let env = Environment::new();
+        let probe = Rc::downgrade(&env);
+        let input = "let f = fn(n) { n == 0 ? 0 : f(n - 1) }; f(10)";
+        test_integer_object(try_eval_in(input, env, &mut ctx).unwrap(), 0);
+        assert!(probe.upgrade().is_some());
+        assert!(ctx.collect_garbage() >= 1);
+        assert!(probe.upgrade().is_none());
+
+        // Environments still reachable from outside survive a collection.
+        let env = Environment::new();
+        let input = "let f = fn(n) { n == 0 ? 0 : f(n - 1) }; fn make() { let g = fn() { g }; g }";
+        try_eval_in(input, Rc::clone(&env), &mut ctx).unwrap();
+        let g = try_eval_in("let g = make(); g", Rc::clone(&env), &mut ctx).unwrap();
+        let g_probe = Rc::downgrade(&as_function(&g).env);
+        ctx.collect_garbage();
+        assert!(g_probe.upgrade().is_some());
+        test_integer_object(try_eval_in("f(5)", Rc::clone(&env), &mut ctx).unwrap(), 0);
+        let same = try_eval_in("g()", Rc::clone(&env), &mut ctx).unwrap();
+        assert_eq!(same, g);
+
+        // Closures defined over and over in a long session are reclaimed.
+        let mut probes = Vec::new();
+        for _ in 0..100 {
+            let made = as_function(&try_eval_in("make()", Rc::clone(&env), &mut ctx).unwrap());
+            probes.push(Rc::downgrade(&made.env));
+        }
+        assert!(ctx.collect_garbage() >= 100);
+        assert!(probes.iter().all(|probe| probe.upgrade().is_none()));
+        try_eval_in("g = 0", Rc::clone(&env), &mut ctx).unwrap();
+        drop((g, same));
+        assert!(ctx.collect_garbage() >= 1);
+        assert!(g_probe.upgrade().is_none());
+
+        // Collections also run on their own as closures are created.
+        let input =
+            "let total = 0; for (i in 0..5000) { let h = fn() { h }; total = total + 1; } total";
+        test_integer_object(try_eval_in(input, Rc::clone(&env), &mut ctx).unwrap(), 5000);
+        assert!(ctx.collect_garbage() < 1000);
+
+        // A closure held only while it is passed as an argument keeps its scope alive
+        // through the collections that other closures trigger meanwhile.
+        let input = "
+            let make = fn() { let g = fn(n) { if (n == 0) { \"ok\" } else { g(n - 1) } }; g };
+            let burn = fn() {
+                let i = 0;
+                while (i < 3000) { let k = fn() { 1 }; i = i + 1; }
+                0
+            };
+            let apply = fn(h, x) { h(3) };
+            apply(make(), burn())";
+        let evaluated = try_eval_in(input, Environment::new(), &mut ctx).unwrap();
+        assert_eq!(evaluated.inspect(), "ok");
+    }
+
+    #[test]
+    fn test_runtime_error_positions() {
+        // An error raised by an operand is reported as is rather than folded into the
+        // enclosing operator's error.
+        let tests = vec![
+            ("-(1 + true)", "1:5: type mismatch: INTEGER + BOOLEAN"),
+            ("(missing + 1) + true", "1:2: identifier not found: missing"),
+            ("if (missing) { 1 }", "1:5: identifier not found: missing"),
+            (
+                "let x = 1;\nx + true",
+                "2:3: type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "fn add(a, b) { a + b }\nadd(1)",
+                "2:4: wrong number of arguments to `add`. got=1, want=2",
+            ),
+            (
+                "let f = fn() {\n  throw 1;\n};\nf()",
+                "2:3: uncaught exception: 1",
+            ),
+            (
+                "let [a] = 1",
+                "1:1: cannot destructure INTEGER with an array pattern",
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval_error(input).to_string(), expected);
+        }
+
+        let err = test_eval_error("1 + true");
+        assert_eq!(
+            err.kind,
+            ErrorKind::TypeMismatch {
+                left: ObjectType::INTEGER,
+                operator: Token::PLUS,
+                right: ObjectType::BOOLEAN,
+            }
+        );
+        let err = test_eval_error("let f = fn(x) { x }; f()");
+        assert!(matches!(
+            err.kind,
+            ErrorKind::ArityMismatch {
+                got: 0,
+                min: 1,
+                max: Some(1),
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -178,22 +574,140 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_const_statements() {
+        let tests = vec![
+            ("const a = 5; a;", 5),
+            ("const a = 5; let b = a; b = b + 1; b;", 6),
+            ("const a = 5; let f = fn(a) { a = a + 1; a }; f(1);", 2),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        // `set` is parsed before `a` is declared, so this is only caught at runtime.
+        let err = test_eval_error("fn set() { a = 2; } const a = 1; set();");
+        assert_eq!(err.kind.to_string(), "assignment to constant: a");
+    }
+
+    #[test]
+    fn test_block_scoping() {
+        let tests = vec![
+            ("let a = 1; if (true) { let a = 2; } a;", 1),
+            ("let a = 1; if (true) { a = 2; } a;", 2),
+            ("let a = 1; if (false) { 0 } else { let a = 3; } a;", 1),
+            ("let a = 1; if (true) { let b = 2; a = b; } a;", 2),
+            (
+                "let a = 1; let i = 0; while (i < 2) { let a = 5; i = i + 1; } a;",
+                1,
+            ),
+            ("const a = 1; if (true) { const a = 2; a } else { 0 };", 2),
+            ("let a = 1; let a = 2; a;", 2),
+            ("let f = fn(x) { let x = x + 1; x }; f(1);", 2),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let err = test_eval_error("if (true) { let b = 2; } b;");
+        assert_eq!(err.kind.to_string(), "identifier not found: b");
+
+        // Redeclarations across separately parsed inputs, as in the REPL.
+        let tests = vec![
+            (
+                "const a = 1;",
+                "let a = 2;",
+                "identifier already declared: a",
+            ),
+            (
+                "const a = 1;",
+                "const a = 2;",
+                "identifier already declared: a",
+            ),
+            (
+                "let a = 1;",
+                "const a = 2;",
+                "identifier already declared: a",
+            ),
+            (
+                "const f = 1;",
+                "fn f() {}",
+                "identifier already declared: f",
+            ),
+        ];
+        for (first, second, expected) in tests {
+            let env = Environment::new();
+            let mut ctx = Context::new();
+            let mut evaluated = Ok(Object::Null(Null {}));
+            for input in [first, second] {
+                evaluated = try_eval_in(input, Rc::clone(&env), &mut ctx);
+            }
+            let err = evaluated.expect_err("expected a runtime error");
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_resolved_scopes() {
+        // A name refers to the innermost scope declaring it, even before the declaration
+        // runs, so an early read fails instead of seeing the outer variable.
+        let tests = vec![
+            (
+                "let a = 1; fn f() { let b = a; let a = 2; b } f()",
+                "`a` used before its declaration",
+            ),
+            (
+                "let a = 1; if (true) { a = 2; let a = 3; } a",
+                "`a` used before its declaration",
+            ),
+            ("x = 1; let x = 2;", "`x` used before its declaration"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval_error(input).kind.to_string(), expected);
+        }
+
+        let tests = vec![
+            ("fn f() { g() } fn g() { 1 } f()", 1),
+            ("let f = fn() { a }; let a = 2; f()", 2),
+            ("let a = 1; for (a in [5]) { let b = a; } a", 1),
+            (
+                "let n = 0; match ([1, 2]) { [a, b] if a < b => { let c = b; n = c } } n",
+                2,
+            ),
+            (
+                "let r = 0; try { throw 3; } catch (e) { let d = e.value; r = d; } r",
+                3,
+            ),
+            (
+                "let r = 0; switch (1) { case 1: let s = 4; case 2: r = s; } r",
+                4,
+            ),
+            ("fn f(a, b = a + 1) { let c = b; c } f(1)", 2),
+        ];
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+
+        // Later inputs see the globals of earlier ones, as in the REPL.
+        let env = Environment::new();
+        let mut ctx = Context::new();
+        for input in ["let a = 1; fn get() { a }", "let b = 2; a = a + b;"] {
+            try_eval_in(input, Rc::clone(&env), &mut ctx).unwrap();
+        }
+        let evaluated = try_eval_in("get() + b", env, &mut ctx).unwrap();
+        test_integer_object(evaluated, 5);
+    }
+
     #[test]
     fn test_function_object() {
         let input = "fn(x) { x + 2; };";
         let evaluated = test_eval(input);
-        let function = evaluated.into_function().expect("not a function object");
+        let function = as_function(&evaluated);
         assert_eq!(function.parameters.len(), 1);
-        assert_eq!(
-            function.parameters[0]
-                .clone()
-                .into_identifier()
-                .expect("not an identifier")
-                .token
-                .to_string(),
-            "x"
-        );
-        assert_eq!(function.body.into_block().expect("").string(), "{(x + 2)}");
+        assert_eq!(function.parameters[0].name, "x");
+        assert_eq!(function.body.as_block().expect("").string(), "{(x + 2)}");
     }
 
     #[test]
@@ -213,48 +727,901 @@ mod tests {
     }
 
     #[test]
-    fn test_string_literal() {
-        let input = r#""Hello World!""#;
-        let evaluated = test_eval(input);
-        let str = evaluated.into_string().expect("not a string object");
-        assert_eq!(str.value, "Hello World!");
+    fn test_arrow_functions() {
+        let tests = vec![
+            ("let add = (a, b) => a + b; add(2, 3)", 5),
+            ("let square = x => x * x; square(4)", 16),
+            ("let adder = x => y => x + y; adder(2)(3)", 5),
+            ("let f = (n) => { let m = n * 2; m + 1 }; f(3)", 7),
+            ("(() => 7)()", 7),
+            ("let apply = fn(f, x) { f(x) }; apply(x => x - 1, 10)", 9),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
     }
 
     #[test]
-    fn test_string_concatenation() {
-        let input = r#""Hello" + " " + "World!""#;
-        let evaluated = test_eval(input);
-        let str = evaluated.into_string().expect("not a string object");
-        assert_eq!(str.value, "Hello World!");
-    }
-
-    fn test_eval(input: &str) -> Object {
-        let l = Lexer::new(input.to_string());
-        let mut p = Parser::new(l);
-        let program = p.parse_program();
-        if program.is_err() {
-            panic!("parse_program() returned an error: {:?}", program.err());
+    fn test_function_declarations() {
+        let tests = vec![
+            (
+                "fn fact(n) { if (n < 2) { 1 } else { n * fact(n - 1) } } fact(5)",
+                120,
+            ),
+            ("let r = add(1, 2); fn add(a, b) { a + b } r", 3),
+            (
+                "fn isEven(n) { n == 0 ? 1 : isOdd(n - 1) } fn isOdd(n) { n == 0 ? 0 : isEven(n - 1) } isEven(10)",
+                1,
+            ),
+            ("fn outer() { return inner(); fn inner() { 42 } } outer()", 42),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
         }
-        let program = program.unwrap();
-        let env = Environment::new();
-        eval(Box::new(program.to_node()), Rc::clone(&env))
-    }
 
-    fn test_integer_object(obj: Object, expected: i64) {
-        let result = obj.as_integer().unwrap();
+        let evaluated = test_eval("fn add(a, b) { a + b } add");
+        assert_eq!(as_function(&evaluated).name.as_deref(), Some("add"));
+        assert_eq!(evaluated.inspect(), "fn add(a, b) {\n{(a + b)}\n}");
+
+        let evaluated = test_eval("let double = fn(x) { x * 2 }; double");
+        assert_eq!(as_function(&evaluated).name.as_deref(), Some("double"));
+
+        let err = test_eval_error("fn add(a, b) { a + b } add(1)");
         assert_eq!(
-            result.value, expected,
-            "expected={}, got={}",
-            expected, result.value
+            err.kind.to_string(),
+            "wrong number of arguments to `add`. got=1, want=2"
         );
     }
 
-    fn test_boolean_object(obj: Object, expected: bool) {
-        let result = obj.as_boolean().unwrap();
-        assert_eq!(result.value, expected);
+    #[test]
+    fn test_default_and_rest_parameters() {
+        let tests = vec![
+            ("fn f(x, y = 10) { x + y } f(1)", 11),
+            ("fn f(x, y = 10) { x + y } f(1, 2)", 3),
+            ("fn f(x, y = x * 2) { x + y } f(3)", 9),
+            ("let f = (x = 1, y = 2) => x * y; f()", 2),
+            ("fn f(first, ...others) { first } f(1, 2, 3)", 1),
+            ("fn add(a, b, c) { a + b + c } let xs = [1, 2, 3]; add(...xs)", 6),
+            ("fn add(a, b, c) { a + b + c } add(1, ...[2, 3])", 6),
+            ("fn add(a, b, c) { a + b + c } add(...1..=3)", 6),
+            (
+                "fn sum(...xs) { let total = 0; for (x in xs) { total = total + x; } total } sum(1, 2, 3, 4)",
+                10,
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let tests = vec![
+            ("fn f(first, ...others) { others } f(1, 2, 3)", "[2, 3]"),
+            ("fn f(first, ...others) { others } f(1)", "[]"),
+            ("let xs = [1, 2]; [0, ...xs, 3]", "[0, 1, 2, 3]"),
+            (r#"[..."ab", "c"]"#, "[a, b, c]"),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            assert_eq!(evaluated.inspect(), tt.1, "input={}", tt.0);
+        }
+
+        let tests = vec![
+            (
+                "fn f(x, y = 1) { x } f()",
+                "wrong number of arguments to `f`. got=0, want=1..2",
+            ),
+            (
+                "fn f(x, y = 1) { x } f(1, 2, 3)",
+                "wrong number of arguments to `f`. got=3, want=1..2",
+            ),
+            (
+                "fn f(x, ...rest) { x } f()",
+                "wrong number of arguments to `f`. got=0, want at least 1",
+            ),
+            (
+                "fn(x, y) { x }(1)",
+                "wrong number of arguments. got=1, want=2",
+            ),
+            ("fn f(x = foobar) { x } f()", "identifier not found: foobar"),
+            ("fn f(x) { x } f(...5)", "not iterable: INTEGER"),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
     }
 
-    fn test_null_object(obj: Object) {
-        assert_eq!(obj, Object::Null(Null));
+    #[test]
+    fn test_named_arguments() {
+        let tests = vec![
+            ("fn f(a, b) { a - b } f(b: 1, a: 10)", 9),
+            ("fn f(a, b) { a - b } f(10, b: 1)", 9),
+            (
+                "fn f(a = 1, b = 2, c = 3) { a * 100 + b * 10 + c } f(c: 9)",
+                129,
+            ),
+            ("fn f(a, b = a * 2) { b } f(a: 4)", 8),
+            ("fn f(a, b = 2, ...rest) { a + b } f(b: 5, a: 1)", 6),
+            ("let f = (x, y) => x * y; f(y: 3, x: 2)", 6),
+            ("fn f(a, b) { a - b } fn g(n) { f(b: n, a: 100) } g(1)", 99),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let tests = vec![
+            ("fn f(a) { a } f(b: 1)", "unknown argument `b` to `f`"),
+            (
+                "fn f(a, ...rest) { a } f(1, rest: [])",
+                "unknown argument `rest` to `f`",
+            ),
+            (
+                "fn f(a, b) { a } f(1, a: 2)",
+                "argument `a` given more than once to `f`",
+            ),
+            ("fn f(a, b) { a } f(b: 2)", "missing argument `a` to `f`"),
+            ("fn(a, b) { a }(a: 1)", "missing argument `b`"),
+            (
+                "fn f(a) { a } f(1, 2, a: 3)",
+                "wrong number of arguments to `f`. got=2, want=1",
+            ),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
+    }
+
+    #[test]
+    fn test_array_literals() {
+        let evaluated = test_eval("[1, 2 * 2, 3 + 3]");
+        let array = evaluated.into_array().expect("not an array object");
+        assert_eq!(array.elements.len(), 3);
+        test_integer_object(array.elements[0].clone(), 1);
+        test_integer_object(array.elements[1].clone(), 4);
+        test_integer_object(array.elements[2].clone(), 6);
+    }
+
+    #[test]
+    fn test_array_index_expressions() {
+        let tests = vec![
+            ("[1, 2, 3][0]", Object::Integer(Integer { value: 1 })),
+            ("[1, 2, 3][2]", Object::Integer(Integer { value: 3 })),
+            ("let i = 0; [1][i]", Object::Integer(Integer { value: 1 })),
+            ("[1, 2, 3][1 + 1]", Object::Integer(Integer { value: 3 })),
+            (
+                "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2]",
+                Object::Integer(Integer { value: 6 }),
+            ),
+            ("[1, 2, 3][3]", Object::Null(Null)),
+            ("[1, 2, 3][-1]", Object::Null(Null)),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            if let Object::Integer(integer) = tt.1 {
+                test_integer_object(evaluated, integer.value);
+            } else {
+                test_null_object(evaluated);
+            }
+        }
+
+        let evaluated = test_eval("let sum = 0; for (x in [1, 2, 3]) { sum = sum + x; } sum");
+        test_integer_object(evaluated, 6);
+
+        let err = test_eval_error("1[0]");
+        assert_eq!(
+            err.kind.to_string(),
+            "index operator not supported: INTEGER"
+        );
+    }
+
+    #[test]
+    fn test_hash_literals() {
+        let input = r#"let two = "two";
+        {"one": 10 - 9, two: 1 + 1, "thr" + "ee": 6 / 2, 4: 4, true: 5, false: 6}"#;
+        let evaluated = test_eval(input);
+        let hash = evaluated.into_hash().expect("not a hash object");
+        let expected = vec![
+            (HashKey::String("one".into()), 1),
+            (HashKey::String("two".into()), 2),
+            (HashKey::String("three".into()), 3),
+            (HashKey::Integer(4), 4),
+            (HashKey::Boolean(true), 5),
+            (HashKey::Boolean(false), 6),
+        ];
+        assert_eq!(hash.pairs.len(), expected.len());
+        for (key, value) in expected {
+            let pair = hash.pairs.get(&key).expect("no pair for key");
+            test_integer_object(pair.value.clone(), value);
+        }
+    }
+
+    #[test]
+    fn test_hash_index_expressions() {
+        let tests = vec![
+            (r#"{"foo": 5}["foo"]"#, Some(5)),
+            (r#"{"foo": 5}["bar"]"#, None),
+            (r#"let key = "foo"; {"foo": 5}[key]"#, Some(5)),
+            (r#"{}["foo"]"#, None),
+            ("{5: 5}[5]", Some(5)),
+            ("{true: 5}[true]", Some(5)),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            match expected {
+                Some(expected) => test_integer_object(evaluated, expected),
+                None => test_null_object(evaluated),
+            }
+        }
+
+        let tests = vec![
+            (r#"{"name": "x"}[[1]]"#, "unusable as hash key: ARRAY"),
+            ("{[1]: 2}", "unusable as hash key: ARRAY"),
+        ];
+        for (input, expected) in tests {
+            let err = test_eval_error(input);
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_destructuring_let_statements() {
+        let tests = vec![
+            ("let [a, b] = [1, 2]; a * 10 + b;", 12),
+            ("let [a, ...rest] = [1, 2, 3]; rest[1];", 3),
+            (
+                "let [a, ...rest] = [1]; let n = 0; for (x in rest) { n = n + 1 } n;",
+                0,
+            ),
+            ("let [x, y] = 3..5; x + y;", 7),
+            (
+                r#"let {name, age: years} = {"name": 1, "age": 40}; name + years;"#,
+                41,
+            ),
+            (
+                r#"let {a, ...others} = {"a": 1, "b": 2, "c": 3}; others["c"];"#,
+                3,
+            ),
+            (r#"let {pos: [x, y]} = {"pos": [3, 4]}; x * y;"#, 12),
+            ("let [[a], {b}] = [[1], {\"b\": 2}]; a + b;", 3),
+            ("const [a, b] = [1, 2]; let f = fn() { a + b }; f();", 3),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_integer_object(evaluated, expected);
+        }
+
+        let tests = vec![
+            (
+                "let [a, b] = [1];",
+                "array pattern expects 2 elements, got 1",
+            ),
+            (
+                "let [a] = [1, 2];",
+                "array pattern expects 1 elements, got 2",
+            ),
+            (
+                "let [a, b, ...c] = [1];",
+                "array pattern expects at least 2 elements, got 1",
+            ),
+            (
+                "let [a] = 5;",
+                "cannot destructure INTEGER with an array pattern",
+            ),
+            (
+                "let {a} = [1];",
+                "cannot destructure ARRAY with a hash pattern",
+            ),
+            (r#"let {a} = {"b": 1};"#, "key not found in hash pattern: a"),
+            (
+                r#"let {a: [b]} = {"a": true};"#,
+                "cannot destructure BOOLEAN with an array pattern",
+            ),
+            (
+                "fn set() { a = 2; } const [a] = [1]; set();",
+                "assignment to constant: a",
+            ),
+        ];
+        for (input, expected) in tests {
+            let err = test_eval_error(input);
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_match_expressions() {
+        let classify = r#"
+        let classify = fn(v) {
+            match (v) {
+                0 => 0,
+                1 | 2 => 12,
+                -3 => 3,
+                "four" => 4,
+                true => 5,
+                [x, y] if x > y => x - y,
+                [x, y] => x + y,
+                [first, ...rest] => first * 100,
+                {kind: "a", size} => size,
+                {kind} => -1,
+                _ => 99,
+            }
+        };
+        "#;
+        let tests = vec![
+            ("classify(0)", 0),
+            ("classify(2)", 12),
+            ("classify(-3)", 3),
+            (r#"classify("four")"#, 4),
+            ("classify(true)", 5),
+            ("classify([5, 2])", 3),
+            ("classify([2, 5])", 7),
+            ("classify([7, 8, 9])", 700),
+            (r#"classify({"kind": "a", "size": 6})"#, 6),
+            (r#"classify({"kind": "b", "size": 6})"#, -1),
+            (r#"classify({"size": 6})"#, 99),
+            ("classify(false)", 99),
+            ("classify([])", 99),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(&format!("{}{}", classify, input));
+            test_integer_object(evaluated, expected);
+        }
+
+        let tests = vec![
+            // Arm bindings and block bodies live in their own scope.
+            ("let x = 1; match (5) { x => { let y = x; y } }; x;", 1),
+            ("let x = 1; match (5) { n => { x = n } }; x;", 5),
+            // `return` inside an arm returns from the enclosing function.
+            (
+                "let f = fn() { match (1) { 1 => { return 10; } }; 20 }; f();",
+                10,
+            ),
+            ("match (0..3) { [a, b, c] => a + b + c }", 3),
+            ("match (1) { 2 => 2, n if n > 5 => 5, n => n * 3 }", 3),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_integer_object(evaluated, expected);
+        }
+
+        let tests = vec![
+            ("match (3) { 1 | 2 => 0 }", "no match arm for value: 3"),
+            ("match ([1]) { [a, b] => 0 }", "no match arm for value: [1]"),
+            (
+                "match (1) { n if n + true => 0 }",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "match (1) { n => n + true }",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+        ];
+        for (input, expected) in tests {
+            let err = test_eval_error(input);
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_try_statements() {
+        let tests = vec![
+            ("try { throw 5; } catch (e) { e.value * 2 }", 10),
+            // A thrown hash is caught as is.
+            (r#"try { throw {"code": 7}; } catch (e) { e.code }"#, 7),
+            ("let f = fn() { throw 3; 4 }; try { f() } catch (e) { e.value + 1 }", 4),
+            ("try { 1 } catch (e) { 2 }", 1),
+            ("try { throw 1; } catch { 2 }", 2),
+            // Rethrowing from a catch reaches the next handler out.
+            (
+                "try { try { throw 1; } catch (e) { throw e.value + 1; } } catch (e) { e.value * 10 }",
+                20,
+            ),
+            // `finally` runs on normal completion, on errors and on `return`.
+            ("let n = 0; try { n = 1; } finally { n = n + 1; } n;", 2),
+            (
+                "let n = 0; try { try { throw 1; } finally { n = 5; } } catch (e) { n + e.value }",
+                6,
+            ),
+            (
+                "let n = 0; let f = fn() { try { return 1; } finally { n = 9; } }; f() + n;",
+                10,
+            ),
+            // An abrupt `finally` replaces the pending outcome.
+            ("let f = fn() { try { return 1; } finally { return 2; } }; f();", 2),
+            (
+                "let n = 0; for (i in 0..3) { try { throw i; } finally { n = n + 1; continue; } } n;",
+                3,
+            ),
+            // The catch binding shadows outer names in its own scope.
+            ("let e = 1; try { throw 2; } catch (e) { e } e;", 1),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_integer_object(evaluated, expected);
+        }
+
+        let tests = vec![
+            (
+                "try { 1 + true; } catch (e) { e.message }",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "try { early; let early = 1; } catch (e) { e.message }",
+                "`early` used before its declaration",
+            ),
+            (r#"try { throw "boom"; } catch (e) { e.value }"#, "boom"),
+            // Every caught value has a message, whatever was thrown.
+            (r#"try { throw "boom"; } catch (e) { e.message }"#, "boom"),
+            ("try { throw 5; } catch (e) { e.message }", "5"),
+            (
+                "try { throw [1, true]; } catch (e) { e.message }",
+                "[1, true]",
+            ),
+            (
+                r#"try { throw {"message": "custom"}; } catch (e) { e.message }"#,
+                "custom",
+            ),
+            (
+                r#"try { throw {"code": 7}; } catch (e) { e.message }"#,
+                "{code: 7}",
+            ),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            let string = evaluated.into_string().expect("not a string object");
+            assert_eq!(&*string.value, expected);
+        }
+
+        let tests = vec![
+            ("throw 5;", "uncaught exception: 5"),
+            (r#"throw {"a": 1}; 2"#, "uncaught exception: {a: 1}"),
+            (
+                "try { throw 1; } catch (e) { e.value + true }",
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            ("try { throw 1; } finally { 2 }", "uncaught exception: 1"),
+            ("try { 1 } finally { throw 2; }", "uncaught exception: 2"),
+            (
+                r#"try { throw "boom"; } catch (e) { e.value.code }"#,
+                "index operator not supported: STRING",
+            ),
+        ];
+        for (input, expected) in tests {
+            let err = test_eval_error(input);
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_string_literal() {
+        let input = r#""Hello World!""#;
+        let evaluated = test_eval(input);
+        let str = evaluated.into_string().expect("not a string object");
+        assert_eq!(&*str.value, "Hello World!");
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        let input = r#""Hello" + " " + "World!""#;
+        let evaluated = test_eval(input);
+        let str = evaluated.into_string().expect("not a string object");
+        assert_eq!(&*str.value, "Hello World!");
+    }
+
+    #[test]
+    fn test_function_equality() {
+        let tests = vec![
+            ("let f = fn(x) { x }; f == f", true),
+            ("let f = fn(x) { x }; let g = f; f == g", true),
+            ("let f = fn(x) { x }; f != f", false),
+            ("fn(x) { x } == fn(x) { x }", false),
+            ("let make = fn() { fn() { 1 } }; make() == make()", false),
+            ("let make = fn() { fn() { 1 } }; make() != make()", true),
+            ("fn f() { 1 } fn g() { 1 } f == g", false),
+            // Closures that capture themselves compare without walking their environments.
+            ("let f = fn() { f }; f() == f", true),
+            ("let f = fn() { f }; let g = fn() { g }; f == g", false),
+            ("let f = fn() { f }; [f, 1] == [f, 1]", true),
+            ("let f = fn() { f }; {\"a\": f} == {\"a\": f}", true),
+            ("let f = fn() { f }; [f] == [fn() { f }]", false),
+            ("let f = fn() { 1 }; f == 1", false),
+            (
+                "let f = fn() { 1 }; let g = fn() { 1 }; let r = 0; switch (g) { case f: r = 1; break; case g: r = 2; } r == 2",
+                true,
+            ),
+        ];
+        for (input, expected) in tests {
+            test_boolean_object(test_eval(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_while_statements() {
+        let tests = vec![
+            ("let i = 0; while (i < 10) { i = i + 1; } i", 10),
+            (
+                "let i = 0; let sum = 0; while (true) { i = i + 1; if (i > 5) { break; } sum = sum + i; } sum",
+                15,
+            ),
+            (
+                "let i = 0; let sum = 0; while (i < 10) { i = i + 1; if (i == 5) { continue; } sum = sum + i; } sum",
+                50,
+            ),
+            (
+                "let i = 0; let count = 0; while (i < 3) { i = i + 1; let j = 0; while (true) { j = j + 1; if (j > 2) { break; } count = count + 1; } } count",
+                6,
+            ),
+            (
+                "let f = fn() { let i = 0; while (true) { i = i + 1; if (i == 7) { return i; } } }; f()",
+                7,
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        test_null_object(test_eval("while (false) { 1 }"));
+
+        let tests = vec![
+            ("break;", "break outside of a loop"),
+            ("fn() { continue; }()", "continue outside of a loop"),
+            ("while (true) { foobar; }", "identifier not found: foobar"),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
+    }
+
+    #[test]
+    fn test_switch_statements() {
+        let describe = r#"
+        let describe = fn(x) {
+            let out = "";
+            switch (x) {
+                case 1:
+                    out = out + "one ";
+                case 2:
+                    out = out + "two ";
+                    break;
+                default:
+                    out = out + "default ";
+                case 3:
+                    out = out + "three ";
+                case "four":
+                    out = out + "four";
+            }
+            out
+        };
+        "#;
+        let tests = vec![
+            ("describe(1)", "one two "),
+            ("describe(2)", "two "),
+            ("describe(3)", "three four"),
+            (r#"describe("four")"#, "four"),
+            ("describe(9)", "default three four"),
+            ("describe(true)", "default three four"),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(&format!("{}{}", describe, input));
+            let string = evaluated.into_string().expect("not a string object");
+            assert_eq!(&*string.value, expected);
+        }
+
+        let tests = vec![
+            // `break` leaves the switch, `continue` reaches the enclosing loop.
+            (
+                "let n = 0; for (i in 0..5) { switch (i) { case 1: case 3: continue; } n = n + i; } n;",
+                6,
+            ),
+            (
+                "let n = 0; while (n < 10) { switch (n) { case 3: break; } n = n + 1; } n;",
+                10,
+            ),
+            (
+                "let f = fn(x) { switch (x) { case 1: return 10; } 20 }; f(1) + f(2);",
+                30,
+            ),
+            // Case tests are only evaluated until one matches.
+            (
+                "let n = 0; let bump = fn() { n = n + 1; 2 }; switch (1) { case 1: case bump(): } n;",
+                0,
+            ),
+            // The clauses share one scope nested in the enclosing one.
+            (
+                "let x = 1; switch (1) { case 1: let x = 2; case 2: x = x + 1; } x;",
+                1,
+            ),
+        ];
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_integer_object(evaluated, expected);
+        }
+
+        test_null_object(test_eval("switch (5) { case 1: 1; }"));
+
+        let tests = vec![
+            ("switch (1 + true) { }", "type mismatch: INTEGER + BOOLEAN"),
+            (
+                "switch (1) { case 1: continue; }",
+                "continue outside of a loop",
+            ),
+        ];
+        for (input, expected) in tests {
+            let err = test_eval_error(input);
+            assert_eq!(err.kind.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_for_statements() {
+        let tests = vec![
+            ("let sum = 0; for (x in 1..5) { sum = sum + x; } sum", 10),
+            ("let sum = 0; for (x in 1..=5) { sum = sum + x; } sum", 15),
+            ("let n = 0; for (x in 5..1) { n = n + 1; } n", 0),
+            (
+                "let sum = 0; for (x in 0..100) { if (x == 4) { break; } sum = sum + x; } sum",
+                6,
+            ),
+            (
+                "let sum = 0; for (x in 0..=6) { if (x == 3) { continue; } sum = sum + x; } sum",
+                18,
+            ),
+            (
+                "let f = fn(n) { for (x in 0..n) { if (x * x > n) { return x; } } }; f(50)",
+                8,
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let input = r#"let s = ""; for (c in "abc") { s = c + s; } s"#;
+        let evaluated = test_eval(input);
+        let str = evaluated.into_string().expect("not a string object");
+        assert_eq!(&*str.value, "cba");
+
+        assert_eq!(test_eval("1..=3").inspect(), "1..=3");
+
+        let tests = vec![
+            ("for (x in 5) { x }", "not iterable: INTEGER"),
+            ("for (x in 0..3) { } x", "identifier not found: x"),
+            ("0..true", "type mismatch: INTEGER .. BOOLEAN"),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        let tests = vec![
+            ("9223372036854775807 - 1 + 1", 9223372036854775807),
+            ("-9223372036854775807 - 1", i64::MIN),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let err = test_eval_error("5 / 0");
+        assert_eq!(err.kind.to_string(), "division by zero");
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_integer_overflow_without_bigint() {
+        let tests = vec![
+            (
+                "9223372036854775807 + 1",
+                "integer overflow: 9223372036854775807 + 1",
+            ),
+            ("12n", "bigint literals require the `bigint` feature: 12n"),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint_expressions() {
+        let tests = vec![
+            ("99999999999999999999n", "99999999999999999999"),
+            ("99999999999999999999", "99999999999999999999"),
+            ("9223372036854775807 + 1", "9223372036854775808"),
+            ("-9223372036854775807 - 2", "-9223372036854775809"),
+            ("-(-9223372036854775807 - 1)", "9223372036854775808"),
+            ("4294967296 * 4294967296", "18446744073709551616"),
+            ("18446744073709551616n * 2", "36893488147419103232"),
+            (
+                "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(25)",
+                "15511210043330985984000000",
+            ),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            let big = evaluated.as_big_int().expect("not a bigint object");
+            assert_eq!(big.value.to_string(), tt.1, "input={}", tt.0);
+        }
+
+        let tests = vec![
+            ("18446744073709551616 / 4294967296", 4294967296),
+            ("9223372036854775808n - 1", 9223372036854775807),
+            ("2n + 3", 5),
+            // Literals that fit in an i64 are integers, so they work wherever integers do.
+            ("123n", 123),
+            (r#"{1: "one"}[1n] == "one" ? 1 : 0"#, 1),
+            ("let n = 0; for (i in 0n..3) { n = n + i; } n", 3),
+            ("match (2) { 2n => 1, _ => 0 }", 1),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_integer_object(evaluated, tt.1);
+        }
+
+        let tests = vec![
+            ("5n == 5", true),
+            ("5 != 5n", false),
+            ("99999999999999999999 > 1", true),
+            ("1 < 99999999999999999999", true),
+            ("-99999999999999999999 < -9223372036854775807", true),
+        ];
+        for tt in tests {
+            let evaluated = test_eval(tt.0);
+            test_boolean_object(evaluated, tt.1);
+        }
+
+        let tests = vec![
+            ("1n / 0", "division by zero"),
+            (
+                "99999999999999999999n + true",
+                "type mismatch: BIGINT + BOOLEAN",
+            ),
+            ("5n + true", "type mismatch: INTEGER + BOOLEAN"),
+        ];
+        for tt in tests {
+            let err = test_eval_error(tt.0);
+            assert_eq!(err.kind.to_string(), tt.1);
+        }
+    }
+
+    fn try_eval(input: &str) -> Result<Object, RuntimeError> {
+        try_eval_with(input, &mut Context::new())
+    }
+
+    fn try_eval_with(input: &str, ctx: &mut Context) -> Result<Object, RuntimeError> {
+        try_eval_in(input, Environment::new(), ctx)
+    }
+
+    fn try_eval_in(
+        input: &str,
+        env: Rc<RefCell<Environment>>,
+        ctx: &mut Context,
+    ) -> Result<Object, RuntimeError> {
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = resolve(program.unwrap(), &env).map_err(|mut errors| errors.remove(0))?;
+        ENGINE.with(Cell::get).run(program, env, ctx)
+    }
+
+    fn test_eval(input: &str) -> Object {
+        try_eval(input).unwrap_or_else(|e| panic!("eval returned an error: {}", e))
+    }
+
+    fn test_eval_error(input: &str) -> RuntimeError {
+        match try_eval(input) {
+            Ok(obj) => panic!("expected a runtime error, got {:?}", obj),
+            Err(err) => err,
+        }
+    }
+
+    fn test_integer_object(obj: Object, expected: i64) {
+        let result = obj.as_integer().unwrap();
+        assert_eq!(
+            result.value, expected,
+            "expected={}, got={}",
+            expected, result.value
+        );
+    }
+
+    fn test_boolean_object(obj: Object, expected: bool) {
+        let result = obj.as_boolean().unwrap();
+        assert_eq!(result.value, expected);
+    }
+
+    fn test_null_object(obj: Object) {
+        assert_eq!(obj, Object::Null(Null));
+    }
+
+    /// A function from either engine as the evaluator represents it.
+    fn as_function(obj: &Object) -> Function {
+        match obj {
+            Object::Function(f) => Function::clone(f),
+            Object::Closure(c) => Function {
+                name: c.function.name.clone(),
+                parameters: Rc::clone(&c.function.parameters),
+                body: Rc::clone(&c.function.body),
+                scope: c.function.scope.clone().unwrap_or_default(),
+                env: Rc::clone(&c.env),
+            },
+            obj => panic!("object is not a function. got={:?}", obj),
+        }
+    }
+
+    thread_local! {
+        /// The engine the helpers above run programs on.
+        static ENGINE: Cell<Engine> = const { Cell::new(Engine::Evaluator) };
+    }
+
+    /// Every test above again on the virtual machine, which must behave the same.
+    mod vm {
+        macro_rules! on_vm {
+            ($($(#[$attr:meta])* $test:ident,)*) => {
+                $(
+                    $(#[$attr])*
+                    #[test]
+                    fn $test() {
+                        super::ENGINE.with(|engine| engine.set(crate::cli::Engine::Vm));
+                        super::$test();
+                    }
+                )*
+            };
+        }
+
+        on_vm! {
+            test_eval_integer_expression,
+            test_eval_boolean_expression,
+            test_bang_operator,
+            test_if_else_expressions,
+            test_else_if_expressions,
+            test_conditional_expressions,
+            test_return_statements,
+            test_error_handling,
+            test_backtraces,
+            test_call_depth_and_tail_calls,
+            test_execution_limits,
+            test_cycle_collection,
+            test_runtime_error_positions,
+            test_let_statements,
+            test_const_statements,
+            test_block_scoping,
+            test_resolved_scopes,
+            test_function_object,
+            test_function_application,
+            test_arrow_functions,
+            test_function_declarations,
+            test_default_and_rest_parameters,
+            test_named_arguments,
+            test_array_literals,
+            test_array_index_expressions,
+            test_hash_literals,
+            test_hash_index_expressions,
+            test_destructuring_let_statements,
+            test_match_expressions,
+            test_try_statements,
+            test_string_literal,
+            test_string_concatenation,
+            test_function_equality,
+            test_while_statements,
+            test_switch_statements,
+            test_for_statements,
+            test_integer_overflow,
+            #[cfg(not(feature = "bigint"))]
+            test_integer_overflow_without_bigint,
+            #[cfg(feature = "bigint")]
+            test_bigint_expressions,
+        }
     }
 }
@@ -1,29 +1,57 @@
 pub mod test;
 
+use std::rc::Rc;
+
 use crate::token;
 use token::{Token, KEYWORDS};
 
-#[derive(Debug)]
+/// A 1-based line and column in the source, used to point diagnostics at a token.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Cloning a lexer is cheap, which lets the parser scan ahead and rewind.
+#[derive(Debug, Clone)]
 pub struct Lexer {
-    input: Vec<char>,
+    input: Rc<[char]>,
     position: usize,
     read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    token_position: Position,
 }
 
 impl Lexer {
     pub fn new(input: String) -> Lexer {
         let mut l = Lexer {
-            input: input.chars().collect(),
+            input: input.chars().collect::<Vec<char>>().into(),
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 0,
+            token_position: Position::default(),
         };
         l.read_char();
         l
     }
 
     fn read_char(&mut self) {
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         if self.read_position >= self.input.len() {
             self.ch = '\0';
         } else {
@@ -39,7 +67,7 @@ impl Lexer {
             self.read_char();
         }
 
-        self.input[position..self.position].into_iter().collect()
+        self.input[position..self.position].iter().collect()
     }
 
     fn read_string(&mut self) -> String {
@@ -49,7 +77,7 @@ impl Lexer {
             self.read_char();
         }
 
-        self.input[position..self.position].into_iter().collect()
+        self.input[position..self.position].iter().collect()
     }
 
     pub fn peek_char(&self) -> char {
@@ -62,10 +90,10 @@ impl Lexer {
 
     fn read_number(&mut self) -> String {
         let position = self.position;
-        while self.ch.is_digit(10) {
+        while self.ch.is_ascii_digit() {
             self.read_char();
         }
-        self.input[position..self.position].into_iter().collect()
+        self.input[position..self.position].iter().collect()
     }
 
     fn skip_whitespace(&mut self) {
@@ -74,18 +102,46 @@ impl Lexer {
         }
     }
 
+    /// The position of the first character of the token last returned by `next_token`.
+    pub fn position(&self) -> Position {
+        self.token_position
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.token_position = Position {
+            line: self.line,
+            column: self.column,
+        };
         let token = match self.ch {
             '\0' => Token::EOF,
             '=' if self.peek_char() == '=' => {
                 self.read_char();
                 Token::EQ
             }
+            '=' if self.peek_char() == '>' => {
+                self.read_char();
+                Token::ARROW
+            }
             '!' if self.peek_char() == '=' => {
                 self.read_char();
                 Token::NOT_EQ
             }
+            '.' if self.peek_char() == '.' => {
+                self.read_char();
+                match self.peek_char() {
+                    '.' => {
+                        self.read_char();
+                        Token::ELLIPSIS
+                    }
+                    '=' => {
+                        self.read_char();
+                        Token::DOTDOT_EQ
+                    }
+                    _ => Token::DOTDOT,
+                }
+            }
+            '.' => Token::DOT,
             '=' => Token::ASSIGN,
             '+' => Token::PLUS,
             '-' => Token::MINUS,
@@ -96,10 +152,15 @@ impl Lexer {
             '<' => Token::LT,
             ',' => Token::COMMA,
             ';' => Token::SEMICOLON,
+            '?' => Token::QUESTION,
+            '|' => Token::PIPE,
+            ':' => Token::COLON,
             '(' => Token::LPAREN,
             ')' => Token::RPAREN,
             '{' => Token::LBRACE,
             '}' => Token::RBRACE,
+            '[' => Token::LBRACKET,
+            ']' => Token::RBRACKET,
             '"' => Token::STRING(self.read_string()),
             c if is_letter(c) => {
                 let str = self.read_identifier();
@@ -108,7 +169,26 @@ impl Lexer {
                     false => Token::IDENT(str),
                 };
             }
-            c if c.is_digit(10) => return Token::INT(self.read_number().parse().unwrap()),
+            c if c.is_ascii_digit() => {
+                let digits = self.read_number();
+                // `n` only makes a bigint when it ends the literal, so `123name` stays a
+                // number followed by an identifier.
+                if self.ch == 'n' && !is_letter(self.peek_char()) {
+                    self.read_char();
+                    // More digits after the suffix, as in `12n5`, make the run one malformed
+                    // literal for the parser to report, rather than two numbers in a row.
+                    if self.ch.is_ascii_digit() {
+                        let rest = self.read_number();
+                        return Token::BIGINT(format!("{}n{}", digits, rest));
+                    }
+                    return Token::BIGINT(digits);
+                }
+                // Literals that do not fit in an i64 are promoted like overflowing arithmetic.
+                return match digits.parse() {
+                    Ok(value) => Token::INT(value),
+                    Err(_) => Token::BIGINT(digits),
+                };
+            }
             c => Token::ILLEGAL(c),
         };
 
@@ -24,6 +24,20 @@ mod tests {
           14 != 5;
           "foobar"
           "foo bar"
+          12n;
+          12n5;
+          99999999999999999999;
+          123name;
+          while (true) { break; continue; }
+          for (x in 0..2) {}
+          1..=n;
+          a ? b : c;
+          (x) => x;
+          [1, ...xs];
+          const k = 1;
+          match (k) { 1 | 2 => k }
+          switch (k) { case 1: break; default: }
+          try { throw e.message; } catch (e) {} finally {}
         "#;
 
         let tokens = vec![
@@ -102,6 +116,104 @@ mod tests {
             Token::SEMICOLON,
             Token::STRING("foobar".into()),
             Token::STRING("foo bar".into()),
+            Token::BIGINT("12".into()),
+            Token::SEMICOLON,
+            Token::BIGINT("12n5".into()),
+            Token::SEMICOLON,
+            Token::BIGINT("99999999999999999999".into()),
+            Token::SEMICOLON,
+            Token::INT(123),
+            Token::IDENT("name".to_string()),
+            Token::SEMICOLON,
+            Token::WHILE,
+            Token::LPAREN,
+            Token::TRUE,
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::BREAK,
+            Token::SEMICOLON,
+            Token::CONTINUE,
+            Token::SEMICOLON,
+            Token::RBRACE,
+            Token::FOR,
+            Token::LPAREN,
+            Token::IDENT("x".to_string()),
+            Token::IN,
+            Token::INT(0),
+            Token::DOTDOT,
+            Token::INT(2),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::RBRACE,
+            Token::INT(1),
+            Token::DOTDOT_EQ,
+            Token::IDENT("n".to_string()),
+            Token::SEMICOLON,
+            Token::IDENT("a".to_string()),
+            Token::QUESTION,
+            Token::IDENT("b".to_string()),
+            Token::COLON,
+            Token::IDENT("c".to_string()),
+            Token::SEMICOLON,
+            Token::LPAREN,
+            Token::IDENT("x".to_string()),
+            Token::RPAREN,
+            Token::ARROW,
+            Token::IDENT("x".to_string()),
+            Token::SEMICOLON,
+            Token::LBRACKET,
+            Token::INT(1),
+            Token::COMMA,
+            Token::ELLIPSIS,
+            Token::IDENT("xs".to_string()),
+            Token::RBRACKET,
+            Token::SEMICOLON,
+            Token::CONST,
+            Token::IDENT("k".to_string()),
+            Token::ASSIGN,
+            Token::INT(1),
+            Token::SEMICOLON,
+            Token::MATCH,
+            Token::LPAREN,
+            Token::IDENT("k".to_string()),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::INT(1),
+            Token::PIPE,
+            Token::INT(2),
+            Token::ARROW,
+            Token::IDENT("k".to_string()),
+            Token::RBRACE,
+            Token::SWITCH,
+            Token::LPAREN,
+            Token::IDENT("k".to_string()),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::CASE,
+            Token::INT(1),
+            Token::COLON,
+            Token::BREAK,
+            Token::SEMICOLON,
+            Token::DEFAULT,
+            Token::COLON,
+            Token::RBRACE,
+            Token::TRY,
+            Token::LBRACE,
+            Token::THROW,
+            Token::IDENT("e".to_string()),
+            Token::DOT,
+            Token::IDENT("message".to_string()),
+            Token::SEMICOLON,
+            Token::RBRACE,
+            Token::CATCH,
+            Token::LPAREN,
+            Token::IDENT("e".to_string()),
+            Token::RPAREN,
+            Token::LBRACE,
+            Token::RBRACE,
+            Token::FINALLY,
+            Token::LBRACE,
+            Token::RBRACE,
             Token::EOF,
         ];
 
@@ -1,15 +1,29 @@
-use repl::run_repl;
+use std::process::ExitCode;
 
 pub mod ast;
+pub mod binary;
+pub mod cli;
+pub mod compiler;
 pub mod evaluator;
 pub mod lexer;
 pub mod object;
 pub mod parser;
 pub mod repl;
+pub mod resolver;
 pub mod token;
-
-fn main() {
-    run_repl();
+pub mod vm;
+
+/// The evaluator recurses natively for interpreted calls, so programs run on a thread with
+/// room for `DEFAULT_MAX_CALL_DEPTH` nested calls even in unoptimized builds.
+const EVAL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    std::thread::Builder::new()
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(|| cli::run(std::env::args().skip(1)))
+        .expect("failed to spawn the evaluation thread")
+        .join()
+        .unwrap_or(ExitCode::FAILURE)
 }
 
 // use std::rc::Rc;
@@ -1,54 +1,175 @@
-use rinonguci_script::new_error;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-use super::Object;
+use super::{gc::Node, Object};
+use crate::ast::{ScopeNames, Slot};
 
+/// Why a variable could not be bound or assigned. The evaluator reports it as a runtime
+/// error at the statement that caused it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BindingError {
+    AlreadyDeclared(String),
+    AssignmentToConstant(String),
+    /// The variable's declaration has not run yet.
+    Undeclared(String),
+}
+
+/// A value stored in an environment together with whether it may be reassigned.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Binding {
+    pub value: Object,
+    pub mutable: bool,
+}
+
+/// The variables of one scope, stored in the slots the resolver assigned them. A slot stays
+/// empty until its declaration runs.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Environment {
-    store: HashMap<String, Object>,
+    names: ScopeNames,
+    slots: Vec<Option<Binding>>,
     outer: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
+    /// An outermost environment. It starts without names and grows as the resolver declares
+    /// the top-level names of each program evaluated in it.
     pub fn new() -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
-            store: HashMap::new(),
+            names: Rc::from([]),
+            slots: Vec::new(),
             outer: None,
         }))
     }
 
-    pub fn new_enclosed_environment(outer: Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
+    pub fn new_enclosed_environment(
+        outer: Rc<RefCell<Self>>,
+        names: &ScopeNames,
+    ) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
-            store: HashMap::new(),
+            names: Rc::clone(names),
+            slots: vec![None; names.len()],
             outer: Some(outer),
         }))
     }
 
-    pub fn get(&self, name: String) -> Option<Object> {
-        match self.store.get(&name) {
-            Some(val) => Some(val.clone()),
-            None => match &self.outer {
-                Some(outer) => outer.borrow().get(name),
-                None => None,
-            },
+    pub fn names(&self) -> &ScopeNames {
+        &self.names
+    }
+
+    pub fn outer(&self) -> Option<&Rc<RefCell<Environment>>> {
+        self.outer.as_ref()
+    }
+
+    /// The slot of `name` in this scope, adding a slot at the end when it has none yet.
+    pub fn slot_of(&mut self, name: &str) -> usize {
+        if let Some(index) = self.names.iter().position(|n| n == name) {
+            return index;
+        }
+        self.names = self
+            .names
+            .iter()
+            .cloned()
+            .chain([name.to_string()])
+            .collect();
+        self.slots.push(None);
+        self.names.len() - 1
+    }
+
+    /// The value in `slot`, or `None` while its declaration has not run.
+    pub fn get(&self, slot: Slot) -> Option<Object> {
+        if slot.depth > 0 {
+            let outer = self.outer.as_ref()?;
+            return outer.borrow().get(Slot {
+                depth: slot.depth - 1,
+                ..slot
+            });
+        }
+        let binding = self.slots.get(slot.index)?.as_ref()?;
+        Some(binding.value.clone())
+    }
+
+    pub fn init(&mut self, name: String, val: Object) -> Result<Object, BindingError> {
+        self.declare(name, val, true)
+    }
+
+    pub fn init_const(&mut self, name: String, val: Object) -> Result<Object, BindingError> {
+        self.declare(name, val, false)
+    }
+
+    /// Binds `name` in this scope. A `let` may redeclare an earlier `let` in the same
+    /// scope, but a constant can neither be redeclared nor replace an existing binding.
+    /// Bindings in enclosing scopes are shadowed, never touched.
+    fn declare(
+        &mut self,
+        name: String,
+        val: Object,
+        mutable: bool,
+    ) -> Result<Object, BindingError> {
+        let index = self.slot_of(&name);
+        if let Some(existing) = &self.slots[index] {
+            if !existing.mutable || !mutable {
+                return Err(BindingError::AlreadyDeclared(name));
+            }
         }
+        self.slots[index] = Some(Binding {
+            value: val.clone(),
+            mutable,
+        });
+        Ok(val)
     }
 
-    pub fn init(&mut self, name: String, val: Object) -> Object {
-        self.store.insert(name, val.clone());
-        val
+    /// The name of the variable in `slot`.
+    pub fn name(&self, slot: Slot) -> Option<String> {
+        if slot.depth > 0 {
+            let outer = self.outer.as_ref()?;
+            return outer.borrow().name(Slot {
+                depth: slot.depth - 1,
+                ..slot
+            });
+        }
+        self.names.get(slot.index).cloned()
     }
 
-    pub fn assign(&mut self, name: String, val: Object) -> Object {
-        match self.store.get(&name) {
-            Some(_) => {
-                self.store.insert(name, val.clone());
-                val
+    /// Stores `val` in the variable at `slot`, which must have been resolved against this
+    /// environment.
+    pub fn assign(&mut self, slot: Slot, val: Object) -> Result<Object, BindingError> {
+        if slot.depth > 0 {
+            let outer = self
+                .outer
+                .as_ref()
+                .expect("slots are resolved against the enclosing scopes");
+            return outer.borrow_mut().assign(
+                Slot {
+                    depth: slot.depth - 1,
+                    ..slot
+                },
+                val,
+            );
+        }
+        let name = || self.names[slot.index].clone();
+        match self.slots.get_mut(slot.index) {
+            Some(Some(binding)) if !binding.mutable => {
+                Err(BindingError::AssignmentToConstant(name()))
+            }
+            Some(Some(binding)) => {
+                binding.value = val.clone();
+                Ok(val)
             }
-            None => match &self.outer {
-                Some(outer) => outer.borrow_mut().assign(name, val),
-                None => new_error!("unknown operator"),
-            },
+            _ => Err(BindingError::Undeclared(name())),
         }
     }
+
+    /// Pushes what this environment references for the cycle collector: its enclosing
+    /// scope and the closures it binds.
+    pub(super) fn references(&self, out: &mut Vec<Node>) {
+        out.extend(self.outer.iter().cloned().map(Node::Environment));
+        for binding in self.slots.iter().flatten() {
+            binding.value.references(out);
+        }
+    }
+
+    /// Empties the environment, handing back what it held so the caller chooses when it
+    /// is dropped.
+    pub(super) fn release(&mut self) -> (Vec<Option<Binding>>, Option<Rc<RefCell<Environment>>>) {
+        (std::mem::take(&mut self.slots), self.outer.take())
+    }
 }
@@ -0,0 +1,215 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use super::{environment::Environment, Closure, Function, Object};
+
+/// The fewest tracked environments that trigger an automatic collection.
+const MIN_COLLECTION_THRESHOLD: usize = 1_000;
+
+/// Frees environments kept alive only by reference cycles.
+///
+/// A closure holds its defining environment, and that environment often holds the closure,
+/// as in `let f = fn() { f() }`, so neither is ever dropped by reference counting alone.
+/// Every such cycle passes through a closure, so the collector tracks the environments
+/// closures capture. A collection counts, for each environment and closure reachable from
+/// them, the references coming from other nodes in that graph. A node with more references
+/// than that is held from outside, by the evaluator, the virtual machine or the embedder,
+/// and keeps everything it reaches alive. The rest are garbage: the environments' bindings
+/// are cleared, which breaks the cycles and lets reference counting free them.
+///
+/// Closures are nodes of their own rather than edges between environments, since one held
+/// only outside the graph, say as an argument being passed, adds nothing to the count of
+/// the environment it captured.
+#[derive(Debug)]
+pub struct CycleCollector {
+    tracked: Vec<Weak<RefCell<Environment>>>,
+    threshold: usize,
+}
+
+impl Default for CycleCollector {
+    fn default() -> Self {
+        CycleCollector {
+            tracked: Vec::new(),
+            threshold: MIN_COLLECTION_THRESHOLD,
+        }
+    }
+}
+
+impl CycleCollector {
+    /// Records an environment captured by a closure.
+    pub fn track(&mut self, env: &Rc<RefCell<Environment>>) {
+        self.tracked.push(Rc::downgrade(env));
+    }
+
+    /// Whether enough environments were tracked since the last collection to run another.
+    pub fn should_collect(&self) -> bool {
+        self.tracked.len() >= self.threshold
+    }
+
+    /// Frees the environments only reachable through cycles and returns how many there were.
+    pub fn collect(&mut self) -> usize {
+        let mut graph = Graph::default();
+        let tracked: Vec<usize> = self
+            .tracked
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|env| graph.insert(Node::Environment(env)))
+            .collect();
+        graph.scan();
+        let live = graph.mark_live();
+
+        let mut released = Vec::new();
+        for (node, env) in graph.nodes.iter().enumerate() {
+            if let (false, Node::Environment(env)) = (live[node], env) {
+                let mut env = env.borrow_mut();
+                released.push(env.release());
+            }
+        }
+
+        let mut seen = vec![false; graph.nodes.len()];
+        self.tracked = tracked
+            .into_iter()
+            .filter(|&node| live[node] && !std::mem::replace(&mut seen[node], true))
+            .filter_map(|node| match &graph.nodes[node] {
+                Node::Environment(env) => Some(Rc::downgrade(env)),
+                _ => None,
+            })
+            .collect();
+        self.threshold = MIN_COLLECTION_THRESHOLD.max(self.tracked.len() * 2);
+
+        released.len()
+    }
+}
+
+/// What the collector follows: environments, and the closures holding them.
+pub(super) enum Node {
+    Environment(Rc<RefCell<Environment>>),
+    Function(Rc<Function>),
+    Closure(Rc<Closure>),
+}
+
+impl Node {
+    fn address(&self) -> *const () {
+        match self {
+            Node::Environment(env) => Rc::as_ptr(env) as *const (),
+            Node::Function(function) => Rc::as_ptr(function) as *const (),
+            Node::Closure(closure) => Rc::as_ptr(closure) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Node::Environment(env) => Rc::strong_count(env),
+            Node::Function(function) => Rc::strong_count(function),
+            Node::Closure(closure) => Rc::strong_count(closure),
+        }
+    }
+}
+
+/// The nodes reachable from the tracked environments and the references between them.
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    index: HashMap<*const (), usize>,
+    edges: Vec<Vec<usize>>,
+    /// Environments in use by the evaluator, which cannot be scanned and are kept.
+    borrowed: Vec<bool>,
+}
+
+impl Graph {
+    fn insert(&mut self, node: Node) -> usize {
+        let next = self.nodes.len();
+        *self.index.entry(node.address()).or_insert_with(|| {
+            self.nodes.push(node);
+            next
+        })
+    }
+
+    /// Discovers every node reachable from the inserted ones, recording its edges.
+    fn scan(&mut self) {
+        let mut node = 0;
+        while node < self.nodes.len() {
+            let mut targets = Vec::new();
+            let borrowed = match &self.nodes[node] {
+                Node::Environment(env) => match env.try_borrow() {
+                    Ok(env) => {
+                        env.references(&mut targets);
+                        false
+                    }
+                    Err(_) => true,
+                },
+                Node::Function(function) => {
+                    targets.push(Node::Environment(Rc::clone(&function.env)));
+                    false
+                }
+                Node::Closure(closure) => {
+                    targets.push(Node::Environment(Rc::clone(&closure.env)));
+                    false
+                }
+            };
+            let edges = targets
+                .into_iter()
+                .map(|target| self.insert(target))
+                .collect();
+            self.edges.push(edges);
+            self.borrowed.push(borrowed);
+            node += 1;
+        }
+    }
+
+    /// Marks the nodes held from outside the graph and everything they reach.
+    fn mark_live(&self) -> Vec<bool> {
+        let mut internal = vec![0; self.nodes.len()];
+        for edges in &self.edges {
+            for &target in edges {
+                internal[target] += 1;
+            }
+        }
+
+        let mut live = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = (0..self.nodes.len())
+            .filter(|&node| {
+                // One reference is the graph's own.
+                self.borrowed[node] || self.nodes[node].strong_count() - 1 > internal[node]
+            })
+            .collect();
+        while let Some(node) = stack.pop() {
+            if !std::mem::replace(&mut live[node], true) {
+                stack.extend(&self.edges[node]);
+            }
+        }
+        live
+    }
+}
+
+impl Object {
+    /// Pushes every closure this object holds, directly or nested.
+    pub(super) fn references(&self, out: &mut Vec<Node>) {
+        match self {
+            Object::Function(function) => out.push(Node::Function(Rc::clone(function))),
+            Object::Closure(closure) => out.push(Node::Closure(Rc::clone(closure))),
+            Object::Array(array) => array
+                .elements
+                .iter()
+                .for_each(|element| element.references(out)),
+            Object::Hash(hash) => hash.pairs.values().for_each(|pair| {
+                pair.key.references(out);
+                pair.value.references(out);
+            }),
+            Object::Return(ret) => ret.value.references(out),
+            Object::TailCall(tail) => {
+                tail.function.references(out);
+                tail.arguments
+                    .iter()
+                    .for_each(|argument| argument.references(out));
+                tail.named
+                    .iter()
+                    .for_each(|(_, argument)| argument.references(out));
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,46 +1,114 @@
 use core::fmt::Debug;
 use enum_as_inner::EnumAsInner;
 use environment::Environment;
-use std::{cell::RefCell, fmt::Write, rc::Rc};
+use std::{cell::RefCell, collections::BTreeMap, fmt::Write, rc::Rc};
 
-use crate::ast::{expression::ExpressionType, statement::StatementType, TNode};
+use crate::{
+    ast::{expression::node::Parameter, statement::StatementType, ScopeNames, TNode},
+    compiler::CompiledFunction,
+    lexer::Position,
+};
 
 pub mod environment;
+pub mod gc;
 
 trait TObject {
     fn inspect(&self) -> String;
     fn object_type(&self) -> ObjectType;
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ObjectType {
     STRING,
     INTEGER,
+    #[cfg(feature = "bigint")]
+    BIGINT,
     BOOLEAN,
     NULL,
+    RANGE,
+    ARRAY,
+    HASH,
 }
 
-#[derive(EnumAsInner, PartialEq, Clone)]
+#[derive(EnumAsInner, Clone)]
 pub enum Object {
     Integer(Integer),
+    #[cfg(feature = "bigint")]
+    BigInt(BigInteger),
     String(StringObj),
     Boolean(Boolean),
     Null(Null),
+    Range(Range),
+    Array(Array),
+    Hash(Hash),
     Return(ReturnValue),
-    Function(Function),
-    Error(Error),
+    Break,
+    Continue,
+    TailCall(Box<TailCall>),
+    /// Shared rather than copied, so every copy of a closure is the same function.
+    Function(Rc<Function>),
+    /// A function compiled for the virtual machine, shared like `Function`.
+    Closure(Rc<Closure>),
+}
+
+/// The equality behind `==` and `!=`.
+///
+/// Integers, big integers, strings, booleans, `null` and ranges are equal when they hold the
+/// same value. Arrays and hashes cannot be changed once built, so they are values too and
+/// are equal when their elements, or their keys and values, are equal. Functions, whether
+/// evaluated or compiled, are equal only to themselves: copies of one closure are equal,
+/// but two closures are not, even when they come from the same literal. Comparing functions
+/// never looks at their bodies or the environments they captured, which can refer back to
+/// the function itself. Values of different variants are never equal.
+///
+/// Returns, breaks, continues and tail calls only signal control flow inside the evaluator
+/// and never reach a script; they compare by their contents.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::Integer(left), Object::Integer(right)) => left == right,
+            #[cfg(feature = "bigint")]
+            (Object::BigInt(left), Object::BigInt(right)) => left == right,
+            (Object::String(left), Object::String(right)) => left == right,
+            (Object::Boolean(left), Object::Boolean(right)) => left == right,
+            (Object::Null(_), Object::Null(_)) => true,
+            (Object::Range(left), Object::Range(right)) => left == right,
+            (Object::Array(left), Object::Array(right)) => left == right,
+            (Object::Hash(left), Object::Hash(right)) => left == right,
+            (Object::Return(left), Object::Return(right)) => left == right,
+            (Object::Break, Object::Break) | (Object::Continue, Object::Continue) => true,
+            (Object::TailCall(left), Object::TailCall(right)) => left == right,
+            (Object::Function(left), Object::Function(right)) => Rc::ptr_eq(left, right),
+            (Object::Closure(left), Object::Closure(right)) => Rc::ptr_eq(left, right),
+            _ => false,
+        }
+    }
 }
 
 impl Debug for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Object::Integer(i) => write!(f, "{:?}", i),
+            #[cfg(feature = "bigint")]
+            Object::BigInt(i) => write!(f, "{:?}", i),
             Object::String(s) => write!(f, "{:?}", s),
             Object::Boolean(b) => write!(f, "{:?}", b),
             Object::Null(n) => write!(f, "{:?}", n),
+            Object::Range(r) => write!(f, "{:?}", r),
+            Object::Array(a) => write!(f, "{:?}", a),
+            Object::Hash(h) => write!(f, "{:?}", h),
             Object::Return(r) => write!(f, "{:?}", r),
-            Object::Error(e) => write!(f, "{:?}", e),
-            _ => write!(f, "Function"),
+            Object::Break => write!(f, "Break"),
+            Object::Continue => write!(f, "Continue"),
+            Object::TailCall(_) => write!(f, "TailCall"),
+            Object::Function(function) => match &function.name {
+                Some(name) => write!(f, "Function({})", name),
+                None => write!(f, "Function"),
+            },
+            Object::Closure(closure) => match &closure.function.name {
+                Some(name) => write!(f, "Function({})", name),
+                None => write!(f, "Function"),
+            },
         }
     }
 }
@@ -49,24 +117,77 @@ impl Object {
     pub fn inspect(&self) -> String {
         match self {
             Object::Integer(i) => i.inspect(),
+            #[cfg(feature = "bigint")]
+            Object::BigInt(i) => i.inspect(),
             Object::String(s) => s.inspect(),
             Object::Boolean(b) => b.inspect(),
             Object::Null(n) => n.inspect(),
+            Object::Range(r) => r.inspect(),
+            Object::Array(a) => a.inspect(),
+            Object::Hash(h) => h.inspect(),
             Object::Return(r) => r.inspect(),
+            Object::Break => "break".into(),
+            Object::Continue => "continue".into(),
+            Object::TailCall(t) => t.function.inspect(),
             Object::Function(f) => f.inspect(),
-            Object::Error(e) => e.inspect(),
+            Object::Closure(c) => c.inspect(),
         }
     }
 
     pub fn object_type(&self) -> ObjectType {
         match self {
             Object::Integer(i) => i.object_type(),
+            #[cfg(feature = "bigint")]
+            Object::BigInt(i) => i.object_type(),
             Object::String(s) => s.object_type(),
             Object::Boolean(b) => b.object_type(),
             Object::Null(n) => n.object_type(),
+            Object::Range(r) => r.object_type(),
+            Object::Array(a) => a.object_type(),
+            Object::Hash(h) => h.object_type(),
             Object::Return(r) => r.object_type(),
+            Object::Break | Object::Continue | Object::TailCall(_) => ObjectType::NULL,
             Object::Function(f) => f.object_type(),
-            Object::Error(e) => e.object_type(),
+            Object::Closure(c) => c.object_type(),
+        }
+    }
+
+    /// Returns the values a `for (x in ...)` loop visits, or `None` when the object is not
+    /// iterable. Every iterable type goes through here so loops need no per-type handling.
+    pub fn iterate(&self) -> Option<Box<dyn Iterator<Item = Object>>> {
+        match self {
+            Object::Range(Range {
+                start,
+                end,
+                inclusive,
+            }) => {
+                let to_integer = |value| Object::Integer(Integer { value });
+                if *inclusive {
+                    Some(Box::new((*start..=*end).map(to_integer)))
+                } else {
+                    Some(Box::new((*start..*end).map(to_integer)))
+                }
+            }
+            Object::String(StringObj { value }) => {
+                let chars: Vec<char> = value.chars().collect();
+                Some(Box::new(chars.into_iter().map(|c| {
+                    Object::String(StringObj {
+                        value: c.to_string().into(),
+                    })
+                })))
+            }
+            Object::Array(Array { elements }) => Some(Box::new(elements.clone().into_iter())),
+            _ => None,
+        }
+    }
+
+    /// The key this object is stored under in a hash, or `None` when it cannot be a key.
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Object::Integer(Integer { value }) => Some(HashKey::Integer(*value)),
+            Object::Boolean(Boolean { value }) => Some(HashKey::Boolean(*value)),
+            Object::String(StringObj { value }) => Some(HashKey::String(Rc::clone(value))),
+            _ => None,
         }
     }
 }
@@ -86,14 +207,32 @@ impl TObject for Integer {
     }
 }
 
+#[cfg(feature = "bigint")]
+#[derive(Debug, PartialEq, Clone)]
+pub struct BigInteger {
+    pub value: num_bigint::BigInt,
+}
+
+#[cfg(feature = "bigint")]
+impl TObject for BigInteger {
+    fn inspect(&self) -> String {
+        self.value.to_string()
+    }
+
+    fn object_type(&self) -> ObjectType {
+        ObjectType::BIGINT
+    }
+}
+
+/// Strings cannot be changed in place, so copies of one share its text.
 #[derive(Debug, PartialEq, Clone)]
 pub struct StringObj {
-    pub value: String,
+    pub value: Rc<str>,
 }
 
 impl TObject for StringObj {
     fn inspect(&self) -> String {
-        format!("{}", self.value)
+        self.value.to_string()
     }
 
     fn object_type(&self) -> ObjectType {
@@ -130,30 +269,83 @@ impl TObject for Null {
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct ReturnValue {
-    pub value: Box<Object>,
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
 }
 
-impl TObject for ReturnValue {
+impl TObject for Range {
     fn inspect(&self) -> String {
-        match self {
-            ReturnValue { value } => value.inspect(),
+        match self.inclusive {
+            true => format!("{}..={}", self.start, self.end),
+            false => format!("{}..{}", self.start, self.end),
         }
     }
 
     fn object_type(&self) -> ObjectType {
-        ObjectType::NULL
+        ObjectType::RANGE
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Array {
+    pub elements: Vec<Object>,
+}
+
+impl TObject for Array {
+    fn inspect(&self) -> String {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.inspect()).collect();
+        format!("[{}]", elements.join(", "))
+    }
+
+    fn object_type(&self) -> ObjectType {
+        ObjectType::ARRAY
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(Rc<str>),
+}
+
+/// Keeps the original key object next to its value so the hash can be inspected.
+#[derive(Debug, PartialEq, Clone)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Hash {
+    pub pairs: BTreeMap<HashKey, HashPair>,
+}
+
+impl TObject for Hash {
+    fn inspect(&self) -> String {
+        let pairs: Vec<String> = self
+            .pairs
+            .values()
+            .map(|pair| format!("{}: {}", pair.key.inspect(), pair.value.inspect()))
+            .collect();
+        format!("{{{}}}", pairs.join(", "))
+    }
+
+    fn object_type(&self) -> ObjectType {
+        ObjectType::HASH
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct Error {
-    pub message: String,
+pub struct ReturnValue {
+    pub value: Box<Object>,
 }
 
-impl TObject for Error {
+impl TObject for ReturnValue {
     fn inspect(&self) -> String {
-        format!("ERROR: {}", self.message)
+        self.value.inspect()
     }
 
     fn object_type(&self) -> ObjectType {
@@ -161,24 +353,51 @@ impl TObject for Error {
     }
 }
 
+/// A call in tail position, evaluated but not yet made. It is handed back to the caller,
+/// which makes it in place of the call that produced it.
 #[derive(Debug, PartialEq, Clone)]
+pub struct TailCall {
+    pub function: Object,
+    pub arguments: Vec<Object>,
+    pub named: Vec<(String, Object)>,
+    pub call_site: Position,
+}
+
+#[derive(Debug, Clone)]
 pub struct Function {
-    pub parameters: Vec<Box<ExpressionType>>,
-    pub body: Box<StatementType>,
+    pub name: Option<String>,
+    pub parameters: Rc<[Parameter]>,
+    pub body: Rc<StatementType>,
+    /// The names of the scope each call runs in.
+    pub scope: ScopeNames,
     pub env: Rc<RefCell<Environment>>,
 }
 
 impl TObject for Function {
     fn inspect(&self) -> String {
-        let mut out = String::new();
-        let params: Vec<String> = self.parameters.iter().map(|p| p.string()).collect();
-        out.push_str("fn");
-        out.push_str("(");
-        out.push_str(&params.join(", "));
-        out.push_str(") {\n");
-        out.push_str(&self.body.string());
-        out.push_str("\n}");
-        out
+        inspect_function(self.name.as_deref(), &self.parameters, &self.body)
+    }
+
+    fn object_type(&self) -> ObjectType {
+        ObjectType::NULL
+    }
+}
+
+/// A function the compiler produced, closed over the environment it was created in.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+impl TObject for Closure {
+    fn inspect(&self) -> String {
+        let function = &self.function;
+        inspect_function(
+            function.name.as_deref(),
+            &function.parameters,
+            &function.body,
+        )
     }
 
     fn object_type(&self) -> ObjectType {
@@ -186,6 +405,23 @@ impl TObject for Function {
     }
 }
 
+/// Prints a function as its source, the same for evaluated and compiled functions.
+fn inspect_function(name: Option<&str>, parameters: &[Parameter], body: &StatementType) -> String {
+    let mut out = String::new();
+    let params: Vec<String> = parameters.iter().map(|p| p.string()).collect();
+    out.push_str("fn");
+    if let Some(name) = name {
+        out.push(' ');
+        out.push_str(name);
+    }
+    out.push('(');
+    out.push_str(&params.join(", "));
+    out.push_str(") {\n");
+    out.push_str(&body.string());
+    out.push_str("\n}");
+    out
+}
+
 impl Write for Function {
     fn write_str(&mut self, _s: &str) -> std::fmt::Result {
         todo!()
@@ -1,17 +1,26 @@
+pub(crate) mod tail_call;
 pub mod test;
 
+use std::rc::Rc;
+
 use crate::ast::expression::node::{
-    Boolean, CallExpression, FunctionLiteral, Identifier, IfExpression, InfixExpression,
-    IntegerLiteral, PrefixExpression, StringLiteral,
+    ArrayLiteral, BigIntLiteral, Boolean, CallExpression, ConditionalExpression, FunctionLiteral,
+    HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression, IntegerLiteral,
+    MatchArm, MatchExpression, NamedArgument, Parameter, PrefixExpression, SpreadExpression,
+    StringLiteral,
 };
 use crate::ast::expression::ExpressionType;
+use crate::ast::pattern::node::{ArrayPattern, HashPattern, HashPatternEntry};
+use crate::ast::pattern::Pattern;
 use crate::ast::statement::node::{
-    BlockStatement, ExpressionStatement, LetStatement, ReturnStatement,
+    BlockStatement, BreakStatement, CatchClause, ContinueStatement, ExpressionStatement,
+    ForStatement, FunctionDeclaration, LetStatement, ReturnStatement, SwitchCase, SwitchStatement,
+    ThrowStatement, TryStatement, WhileStatement,
 };
 use crate::ast::statement::StatementType;
-use crate::ast::Program;
+use crate::ast::{Program, TNode};
 use crate::{
-    lexer::Lexer,
+    lexer::{Lexer, Position},
     token::{Precedence, Token},
 };
 use anyhow::{anyhow, Result};
@@ -22,6 +31,10 @@ use tracing::auto_log;
 type PrefixParseFn = fn(&mut Parser) -> Box<ExpressionType>;
 type InfixParseFn = fn(&mut Parser, Box<ExpressionType>) -> Box<ExpressionType>;
 
+/// The bindings declared in one block, mapped to whether they are `const`. Mirrors the
+/// evaluator's environments so constant misuse is reported before the program runs.
+type Scope = HashMap<String, bool>;
+
 #[derive(Debug)]
 pub struct Parser {
     l: Lexer,
@@ -29,6 +42,11 @@ pub struct Parser {
 
     cur_token: Token,
     peek_token: Token,
+    cur_position: Position,
+    peek_position: Position,
+
+    scopes: Vec<Scope>,
+    arrows_allowed: bool,
 
     prefix_parse_fns: HashMap<Discriminant<Token>, PrefixParseFn>,
     infix_parse_fns: HashMap<Discriminant<Token>, InfixParseFn>,
@@ -41,12 +59,17 @@ impl Parser {
             errors: Vec::new(),
             cur_token: Token::EOF,
             peek_token: Token::EOF,
+            cur_position: Position::default(),
+            peek_position: Position::default(),
+            scopes: vec![Scope::default()],
+            arrows_allowed: true,
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
         };
 
         parser.register_prefix(Token::IDENT(String::new()), Parser::parse_identifier);
         parser.register_prefix(Token::INT(0), Parser::parse_integer_literal);
+        parser.register_prefix(Token::BIGINT(String::new()), Parser::parse_bigint_literal);
         parser.register_prefix(Token::STRING(String::new()), Parser::parse_string_literal);
         parser.register_prefix(Token::BANG, Parser::parse_prefix_expression);
         parser.register_prefix(Token::MINUS, Parser::parse_prefix_expression);
@@ -55,6 +78,10 @@ impl Parser {
         parser.register_prefix(Token::LPAREN, Parser::parse_grouped_expression);
         parser.register_prefix(Token::IF, Parser::parse_if_expression);
         parser.register_prefix(Token::FUNCTION, Parser::parse_function_literal);
+        parser.register_prefix(Token::LBRACKET, Parser::parse_array_literal);
+        parser.register_prefix(Token::LBRACE, Parser::parse_hash_literal);
+        parser.register_prefix(Token::MATCH, Parser::parse_match_expression);
+        parser.register_prefix(Token::ELLIPSIS, Parser::parse_misplaced_spread);
 
         parser.register_infix(Token::PLUS, Parser::parse_infix_expression);
         parser.register_infix(Token::MINUS, Parser::parse_infix_expression);
@@ -64,7 +91,12 @@ impl Parser {
         parser.register_infix(Token::NOT_EQ, Parser::parse_infix_expression);
         parser.register_infix(Token::LT, Parser::parse_infix_expression);
         parser.register_infix(Token::GT, Parser::parse_infix_expression);
+        parser.register_infix(Token::DOTDOT, Parser::parse_infix_expression);
+        parser.register_infix(Token::DOTDOT_EQ, Parser::parse_infix_expression);
         parser.register_infix(Token::LPAREN, Parser::parse_call_expression);
+        parser.register_infix(Token::LBRACKET, Parser::parse_index_expression);
+        parser.register_infix(Token::DOT, Parser::parse_member_expression);
+        parser.register_infix(Token::QUESTION, Parser::parse_conditional_expression);
 
         parser.next_token();
         parser.next_token();
@@ -74,8 +106,20 @@ impl Parser {
 
     #[auto_log]
     fn parse_identifier(&mut self) -> Box<ExpressionType> {
+        if self.arrows_allowed && self.peek_token_is(Token::ARROW) {
+            let parameter = Parameter {
+                name: self.cur_token.to_string(),
+                default: None,
+                rest: false,
+            };
+            self.next_token();
+            return self.parse_arrow_function_body(vec![parameter]);
+        }
+
         Box::new(ExpressionType::Identifier(Identifier {
             token: self.cur_token.clone(),
+            position: self.cur_position,
+            slot: None,
         }))
     }
 
@@ -86,6 +130,21 @@ impl Parser {
         }))
     }
 
+    #[auto_log]
+    fn parse_bigint_literal(&mut self) -> Box<ExpressionType> {
+        if let Token::BIGINT(digits) = &self.cur_token {
+            if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+                self.errors.push(format!(
+                    "{}: malformed number literal {:?}",
+                    self.cur_position, digits
+                ));
+            }
+        }
+        Box::new(ExpressionType::BigIntLiteral(BigIntLiteral {
+            token: self.cur_token.clone(),
+        }))
+    }
+
     #[auto_log]
     fn parse_string_literal(&mut self) -> Box<ExpressionType> {
         Box::new(ExpressionType::StringLiteral(StringLiteral {
@@ -101,10 +160,28 @@ impl Parser {
     }
 
     fn parse_grouped_expression(&mut self) -> Box<ExpressionType> {
+        if self.arrows_allowed && self.peek_arrow_parameters() {
+            let parameters = self.parse_function_parameters();
+            if !self.expect_peek(&Token::ARROW) {
+                return Box::new(ExpressionType::Identifier(Identifier {
+                    token: Token::EOF,
+                    position: self.cur_position,
+                    slot: None,
+                }));
+            }
+            return self.parse_arrow_function_body(parameters);
+        }
+
         self.next_token();
+        let arrows_allowed = std::mem::replace(&mut self.arrows_allowed, true);
         let exp = self.parse_expression(Precedence::LOWEST);
+        self.arrows_allowed = arrows_allowed;
         if !self.expect_peek(&Token::RPAREN) {
-            return Box::new(ExpressionType::Identifier(Identifier { token: Token::EOF }));
+            return Box::new(ExpressionType::Identifier(Identifier {
+                token: Token::EOF,
+                position: self.cur_position,
+                slot: None,
+            }));
         }
         exp
     }
@@ -121,11 +198,18 @@ impl Parser {
             self.next_token();
         }
 
-        Box::new(StatementType::Block(BlockStatement { statements }))
+        Box::new(StatementType::Block(BlockStatement {
+            statements,
+            scope: crate::ast::Scope::default(),
+        }))
     }
 
     fn parse_if_expression(&mut self) -> Box<ExpressionType> {
-        let default = Box::new(ExpressionType::Identifier(Identifier { token: Token::EOF }));
+        let default = Box::new(ExpressionType::Identifier(Identifier {
+            token: Token::EOF,
+            position: self.cur_position,
+            slot: None,
+        }));
 
         if !self.expect_peek(&Token::LPAREN) {
             return default;
@@ -142,16 +226,25 @@ impl Parser {
             return default;
         }
 
-        let consequence = self.parse_block_statement();
+        let consequence = self.parse_scoped_block();
 
         let mut alternative: Option<Box<StatementType>> = None;
 
         if self.peek_token_is(Token::ELSE) {
             self.next_token();
-            if !self.expect_peek(&Token::LBRACE) {
-                return default;
+            if self.peek_token_is(Token::IF) {
+                // `else if` chains nest the next `if` as the whole alternative.
+                self.next_token();
+                let expression = self.parse_if_expression();
+                alternative = Some(Box::new(StatementType::Expression(ExpressionStatement {
+                    expression,
+                })));
+            } else {
+                if !self.expect_peek(&Token::LBRACE) {
+                    return default;
+                }
+                alternative = Some(self.parse_scoped_block());
             }
-            alternative = Some(self.parse_block_statement());
         }
 
         Box::new(ExpressionType::If(IfExpression {
@@ -161,31 +254,185 @@ impl Parser {
         }))
     }
 
-    fn parse_function_parameters(&mut self) -> Vec<Box<ExpressionType>> {
-        let mut identifiers = Vec::new();
+    /// Parses an `if`, `else` or `while` body, which runs in its own block scope.
+    fn parse_scoped_block(&mut self) -> Box<StatementType> {
+        self.enter_scope(Vec::new());
+        let block = self.parse_block_statement();
+        self.exit_scope();
+        block
+    }
+
+    fn enter_scope(&mut self, names: Vec<String>) {
+        self.scopes
+            .push(names.into_iter().map(|name| (name, false)).collect());
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Records a declaration in the current scope, rejecting the same redeclarations as
+    /// `Environment::init`: a `const` can neither be redeclared nor replace a binding.
+    fn declare(&mut self, name: String, constant: bool, position: Position) {
+        let scope = self.scopes.last_mut().unwrap();
+        if scope
+            .get(&name)
+            .is_some_and(|&existing| existing || constant)
+        {
+            self.errors.push(format!(
+                "{}: identifier already declared: {}",
+                position, name
+            ));
+            return;
+        }
+        scope.insert(name, constant);
+    }
+
+    fn is_constant(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn parse_conditional_expression(
+        &mut self,
+        condition: Box<ExpressionType>,
+    ) -> Box<ExpressionType> {
+        let default = Box::new(ExpressionType::Identifier(Identifier {
+            token: Token::EOF,
+            position: self.cur_position,
+            slot: None,
+        }));
+
+        self.next_token();
+        let consequence = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::COLON) {
+            return default;
+        }
+
+        // Parsing the alternative at the lowest precedence makes chains right-associative.
+        self.next_token();
+        let alternative = self.parse_expression(Precedence::LOWEST);
+
+        Box::new(ExpressionType::Conditional(ConditionalExpression {
+            condition,
+            consequence,
+            alternative,
+        }))
+    }
+
+    fn parse_function_parameters(&mut self) -> Vec<Parameter> {
+        let mut parameters: Vec<Parameter> = Vec::new();
         if self.peek_token_is(Token::RPAREN) {
             self.next_token();
-            return identifiers;
+            return parameters;
         }
-        self.next_token();
-        identifiers.push(Box::new(ExpressionType::Identifier(Identifier {
-            token: self.cur_token.clone(),
-        })));
-        while self.peek_token_is(Token::COMMA) {
+        loop {
             self.next_token();
+            let position = self.cur_position;
+            let parameter = match self.parse_function_parameter() {
+                Some(parameter) => parameter,
+                None => {
+                    self.skip_parameter_list();
+                    return Vec::new();
+                }
+            };
+            if parameters.iter().any(|p| p.name == parameter.name) {
+                self.errors.push(format!(
+                    "{}: duplicate parameter name {:?}",
+                    position, parameter.name
+                ));
+            }
+            if parameters.last().is_some_and(|p| p.rest) {
+                self.errors.push(format!(
+                    "{}: parameter {:?} follows a rest parameter; the rest parameter must be last",
+                    position, parameter.name
+                ));
+            }
+            parameters.push(parameter);
+
+            if !self.peek_token_is(Token::COMMA) {
+                break;
+            }
             self.next_token();
-            identifiers.push(Box::new(ExpressionType::Identifier(Identifier {
-                token: self.cur_token.clone(),
-            })));
         }
         if !self.expect_peek(&Token::RPAREN) {
             return Vec::new();
         }
-        identifiers
+        parameters
+    }
+
+    /// Parses `name`, `name = default` or `...name` starting at the current token.
+    fn parse_function_parameter(&mut self) -> Option<Parameter> {
+        let rest = self.cur_token_is(Token::ELLIPSIS);
+        if rest {
+            self.next_token();
+        }
+
+        let Token::IDENT(name) = self.cur_token.clone() else {
+            self.errors.push(format!(
+                "{}: expected parameter name, got {:?}",
+                self.cur_position,
+                self.cur_token.to_string()
+            ));
+            return None;
+        };
+
+        let mut default = None;
+        if self.peek_token_is(Token::ASSIGN) {
+            if rest {
+                self.errors.push(format!(
+                    "{}: rest parameter {:?} cannot have a default value",
+                    self.peek_position, name
+                ));
+            }
+            self.next_token();
+            self.next_token();
+            default = Some(self.parse_expression(Precedence::LOWEST));
+        }
+
+        Some(Parameter {
+            name,
+            default,
+            rest,
+        })
+    }
+
+    /// Skips past the closing `)` of a malformed parameter list so one bad parameter is
+    /// reported once instead of cascading into errors for the rest of the function.
+    fn skip_parameter_list(&mut self) {
+        let mut depth = 1;
+        while !self.cur_token_is(Token::EOF) {
+            match self.cur_token {
+                Token::LPAREN => depth += 1,
+                Token::RPAREN => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.next_token();
+        }
     }
 
     fn parse_function_literal(&mut self) -> Box<ExpressionType> {
-        let default = Box::new(ExpressionType::Identifier(Identifier { token: Token::EOF }));
+        let default = Box::new(ExpressionType::Identifier(Identifier {
+            token: Token::EOF,
+            position: self.cur_position,
+            slot: None,
+        }));
+
+        let mut name = None;
+        if self.peek_token_is(Token::IDENT(String::new())) {
+            self.next_token();
+            name = Some(self.cur_token.to_string());
+        }
 
         if !self.expect_peek(&Token::LPAREN) {
             return default;
@@ -197,39 +444,348 @@ impl Parser {
             return default;
         }
 
-        let body = self.parse_block_statement();
+        self.enter_scope(parameters.iter().map(|p| p.name.clone()).collect());
+        let mut body = self.parse_block_statement();
+        self.exit_scope();
+        tail_call::mark_tail_calls(&mut body);
 
-        Box::new(ExpressionType::Fn(FunctionLiteral { parameters, body }))
+        Box::new(ExpressionType::Fn(FunctionLiteral {
+            name,
+            parameters: parameters.into(),
+            body: Rc::from(body),
+            scope: crate::ast::Scope::default(),
+        }))
+    }
+
+    /// Scans ahead from the current `(` to its matching `)` and reports whether `=>` follows,
+    /// telling an arrow function's parameter list apart from a grouped expression.
+    fn peek_arrow_parameters(&self) -> bool {
+        let mut lexer = self.l.clone();
+        let mut token = self.peek_token.clone();
+        let mut depth = 1;
+        loop {
+            match token {
+                Token::LPAREN => depth += 1,
+                Token::RPAREN => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return lexer.next_token() == Token::ARROW;
+                    }
+                }
+                Token::EOF => return false,
+                _ => {}
+            }
+            token = lexer.next_token();
+        }
+    }
+
+    /// Parses what follows `=>`. An expression body is returned implicitly.
+    fn parse_arrow_function_body(&mut self, parameters: Vec<Parameter>) -> Box<ExpressionType> {
+        self.enter_scope(parameters.iter().map(|p| p.name.clone()).collect());
+        let mut body = if self.peek_token_is(Token::LBRACE) {
+            self.next_token();
+            self.parse_block_statement()
+        } else {
+            self.next_token();
+            let value = self.parse_expression(Precedence::LOWEST);
+            Box::new(StatementType::Block(BlockStatement {
+                statements: vec![Box::new(StatementType::Return(ReturnStatement { value }))],
+                scope: crate::ast::Scope::default(),
+            }))
+        };
+        self.exit_scope();
+        tail_call::mark_tail_calls(&mut body);
+
+        Box::new(ExpressionType::Fn(FunctionLiteral {
+            name: None,
+            parameters: parameters.into(),
+            body: Rc::from(body),
+            scope: crate::ast::Scope::default(),
+        }))
     }
 
     fn parse_call_expression(&mut self, function: Box<ExpressionType>) -> Box<ExpressionType> {
         let token = self.cur_token.clone();
-        let arguments = self.parse_call_arguments();
+        let position = self.cur_position;
+        let (arguments, named) = self.parse_call_arguments();
 
         Box::new(ExpressionType::Call(CallExpression {
             token,
             function,
             arguments,
+            named,
+            position,
+            tail: false,
         }))
     }
 
-    fn parse_call_arguments(&mut self) -> Vec<Box<ExpressionType>> {
+    /// Parses the arguments of a call up to its `)`: positional ones, then `name: value`
+    /// ones, which bind the parameter called `name`.
+    #[allow(clippy::vec_box)]
+    fn parse_call_arguments(&mut self) -> (Vec<Box<ExpressionType>>, Vec<NamedArgument>) {
         let mut arguments = Vec::new();
+        let mut named: Vec<NamedArgument> = Vec::new();
         if self.peek_token_is(Token::RPAREN) {
             self.next_token();
-            return arguments;
+            return (arguments, named);
+        }
+        let arrows_allowed = std::mem::replace(&mut self.arrows_allowed, true);
+        loop {
+            self.next_token();
+            let position = self.cur_position;
+            match (&self.cur_token, self.peek_token_is(Token::COLON)) {
+                (Token::IDENT(name), true) => {
+                    let name = name.clone();
+                    if named.iter().any(|argument| argument.name == name) {
+                        self.errors
+                            .push(format!("{}: duplicate named argument {:?}", position, name));
+                    }
+                    self.next_token();
+                    self.next_token();
+                    named.push(NamedArgument {
+                        name,
+                        value: self.parse_expression(Precedence::LOWEST),
+                        position,
+                    });
+                }
+                _ => {
+                    if let Some(last) = named.last() {
+                        self.errors.push(format!(
+                            "{}: positional argument follows named argument {:?}",
+                            position, last.name
+                        ));
+                    }
+                    arguments.push(self.parse_element());
+                }
+            }
+
+            if !self.peek_token_is(Token::COMMA) {
+                break;
+            }
+            self.next_token();
+        }
+        self.arrows_allowed = arrows_allowed;
+        if !self.expect_peek(&Token::RPAREN) {
+            return (Vec::new(), Vec::new());
+        }
+        (arguments, named)
+    }
+
+    // The AST holds its expressions boxed.
+    #[allow(clippy::vec_box)]
+    fn parse_expression_list(&mut self, end: Token) -> Vec<Box<ExpressionType>> {
+        let mut list = Vec::new();
+        if self.peek_token_is(end.clone()) {
+            self.next_token();
+            return list;
         }
+        let arrows_allowed = std::mem::replace(&mut self.arrows_allowed, true);
         self.next_token();
-        arguments.push(self.parse_expression(Precedence::LOWEST));
+        list.push(self.parse_element());
         while self.peek_token_is(Token::COMMA) {
             self.next_token();
             self.next_token();
-            arguments.push(self.parse_expression(Precedence::LOWEST));
+            list.push(self.parse_element());
         }
-        if !self.expect_peek(&Token::RPAREN) {
+        self.arrows_allowed = arrows_allowed;
+        if !self.expect_peek(&end) {
             return Vec::new();
         }
-        arguments
+        list
+    }
+
+    fn parse_match_expression(&mut self) -> Box<ExpressionType> {
+        let position = self.cur_position;
+        let default = Box::new(ExpressionType::Identifier(Identifier {
+            token: Token::EOF,
+            position: self.cur_position,
+            slot: None,
+        }));
+
+        if !self.expect_peek(&Token::LPAREN) {
+            return default;
+        }
+
+        self.next_token();
+        let subject = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::RPAREN) || !self.expect_peek(&Token::LBRACE) {
+            return default;
+        }
+
+        let mut arms = Vec::new();
+        while !self.peek_token_is(Token::RBRACE) {
+            self.next_token();
+            let arm = match self.parse_match_arm() {
+                Some(arm) => arm,
+                None => return default,
+            };
+            let block_body = arm.body.is_block();
+            arms.push(arm);
+
+            // Arms are separated by commas, which may be left out after a block body.
+            if self.peek_token_is(Token::COMMA) {
+                self.next_token();
+            } else if !block_body && !self.peek_token_is(Token::RBRACE) {
+                self.peek_error(&Token::COMMA);
+                return default;
+            }
+        }
+        self.next_token();
+
+        Box::new(ExpressionType::Match(MatchExpression {
+            subject,
+            arms,
+            position,
+        }))
+    }
+
+    /// Parses `pattern [if guard] => body`, where the body is a block or an expression. The
+    /// pattern's bindings are in scope for both the guard and the body.
+    fn parse_match_arm(&mut self) -> Option<MatchArm> {
+        let mut bindings = Vec::new();
+        let pattern = self.parse_pattern(&mut bindings)?;
+        self.enter_scope(bindings.into_iter().map(|(name, _)| name).collect());
+
+        let mut guard = None;
+        if self.peek_token_is(Token::IF) {
+            self.next_token();
+            self.next_token();
+            // In `n if n > 0 => ...` the `=>` ends the guard rather than starting an arrow
+            // function, unless it is nested inside parentheses or brackets.
+            let arrows_allowed = std::mem::replace(&mut self.arrows_allowed, false);
+            guard = Some(self.parse_expression(Precedence::LOWEST));
+            self.arrows_allowed = arrows_allowed;
+        }
+
+        if !self.expect_peek(&Token::ARROW) {
+            self.exit_scope();
+            return None;
+        }
+
+        let body = if self.peek_token_is(Token::LBRACE) {
+            self.next_token();
+            self.parse_scoped_block()
+        } else {
+            self.next_token();
+            let expression = self.parse_expression(Precedence::LOWEST);
+            Box::new(StatementType::Expression(ExpressionStatement {
+                expression,
+            }))
+        };
+        self.exit_scope();
+
+        Some(MatchArm {
+            pattern,
+            guard,
+            scope: crate::ast::Scope::default(),
+            body,
+        })
+    }
+
+    fn parse_hash_literal(&mut self) -> Box<ExpressionType> {
+        let position = self.cur_position;
+        let default = Box::new(ExpressionType::Identifier(Identifier {
+            token: Token::EOF,
+            position: self.cur_position,
+            slot: None,
+        }));
+
+        let mut pairs = Vec::new();
+        while !self.peek_token_is(Token::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::LOWEST);
+
+            if !self.expect_peek(&Token::COLON) {
+                return default;
+            }
+
+            self.next_token();
+            let value = self.parse_expression(Precedence::LOWEST);
+            pairs.push((key, value));
+
+            if !self.peek_token_is(Token::RBRACE) && !self.expect_peek(&Token::COMMA) {
+                return default;
+            }
+        }
+
+        if !self.expect_peek(&Token::RBRACE) {
+            return default;
+        }
+
+        Box::new(ExpressionType::Hash(HashLiteral { pairs, position }))
+    }
+
+    fn parse_array_literal(&mut self) -> Box<ExpressionType> {
+        let elements = self.parse_expression_list(Token::RBRACKET);
+        Box::new(ExpressionType::Array(ArrayLiteral { elements }))
+    }
+
+    /// `left.name` is sugar for `left["name"]`.
+    fn parse_member_expression(&mut self, left: Box<ExpressionType>) -> Box<ExpressionType> {
+        let position = self.cur_position;
+        if !self.expect_peek(&Token::IDENT(String::new())) {
+            return Box::new(ExpressionType::Identifier(Identifier {
+                token: Token::EOF,
+                position: self.cur_position,
+                slot: None,
+            }));
+        }
+
+        let index = Box::new(ExpressionType::StringLiteral(StringLiteral {
+            token: Token::STRING(self.cur_token.to_string()),
+        }));
+        Box::new(ExpressionType::Index(IndexExpression {
+            left,
+            index,
+            position,
+        }))
+    }
+
+    fn parse_index_expression(&mut self, left: Box<ExpressionType>) -> Box<ExpressionType> {
+        let position = self.cur_position;
+        self.next_token();
+        let index = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::RBRACKET) {
+            return Box::new(ExpressionType::Identifier(Identifier {
+                token: Token::EOF,
+                position: self.cur_position,
+                slot: None,
+            }));
+        }
+
+        Box::new(ExpressionType::Index(IndexExpression {
+            left,
+            index,
+            position,
+        }))
+    }
+
+    /// Parses an array element or a positional argument, the only places `...value` may
+    /// appear.
+    fn parse_element(&mut self) -> Box<ExpressionType> {
+        match self.cur_token_is(Token::ELLIPSIS) {
+            true => self.parse_spread_expression(),
+            false => self.parse_expression(Precedence::LOWEST),
+        }
+    }
+
+    fn parse_spread_expression(&mut self) -> Box<ExpressionType> {
+        let position = self.cur_position;
+        self.next_token();
+        let value = self.parse_expression(Precedence::LOWEST);
+        Box::new(ExpressionType::Spread(SpreadExpression { value, position }))
+    }
+
+    /// Reports a `...` anywhere else, parsing the spread anyway so its operand is not
+    /// reported too.
+    fn parse_misplaced_spread(&mut self) -> Box<ExpressionType> {
+        self.errors.push(format!(
+            "{}: spread syntax is only allowed in calls and array literals",
+            self.cur_position
+        ));
+        self.parse_spread_expression()
     }
 
     fn peek_precedence(&mut self) -> Precedence {
@@ -242,7 +798,9 @@ impl Parser {
 
     fn next_token(&mut self) {
         self.cur_token = self.peek_token.clone();
+        self.cur_position = self.peek_position;
         self.peek_token = self.l.next_token();
+        self.peek_position = self.l.position();
     }
 
     fn cur_token_is(&self, t: Token) -> bool {
@@ -266,8 +824,18 @@ impl Parser {
     #[auto_log]
     fn parse_statement(&mut self) -> Result<Box<StatementType>> {
         match self.cur_token {
-            Token::LET => self.parse_let_statement(),
+            Token::LET | Token::CONST => self.parse_let_statement(),
             Token::RETURN => self.parse_return_statement(),
+            Token::FUNCTION if self.peek_token_is(Token::IDENT(String::new())) => {
+                self.parse_function_declaration()
+            }
+            Token::WHILE => self.parse_while_statement(),
+            Token::FOR => self.parse_for_statement(),
+            Token::SWITCH => self.parse_switch_statement(),
+            Token::THROW => self.parse_throw_statement(),
+            Token::TRY => self.parse_try_statement(),
+            Token::BREAK => self.parse_break_statement(),
+            Token::CONTINUE => self.parse_continue_statement(),
             Token::IDENT(_) if self.peek_token_is(Token::ASSIGN) => self.parse_reassign_statement(),
             _ => self.parse_expression_statement(),
         }
@@ -276,32 +844,221 @@ impl Parser {
     #[auto_log]
     fn parse_let_statement(&mut self) -> Result<Box<StatementType>> {
         let token = self.cur_token.clone();
+        let statement_position = self.cur_position;
 
-        if !self.expect_peek(&Token::IDENT(String::new())) {
-            return Err(anyhow!("failed to parse let statement"));
+        self.next_token();
+        let position = self.cur_position;
+        let mut bindings = Vec::new();
+        let pattern = match self.parse_pattern(&mut bindings) {
+            Some(pattern) => pattern,
+            None => return Err(anyhow!("failed to parse let statement")),
+        };
+        if pattern.is_refutable() {
+            self.errors.push(format!(
+                "{}: refutable pattern in let binding: {}",
+                position,
+                pattern.string()
+            ));
         }
 
-        let name = self.cur_token.to_string();
-
         if !self.expect_peek(&Token::ASSIGN) {
             return Err(anyhow!("failed to parse let statement"));
         }
         self.next_token();
 
-        let value = self.parse_expression(Precedence::LOWEST);
+        let mut value = self.parse_expression(Precedence::LOWEST);
         if self.peek_token_is(Token::SEMICOLON) {
             self.next_token();
         }
 
+        // `let f = fn() {}` names the function after its binding.
+        if let (ExpressionType::Fn(function), Pattern::Identifier(name)) =
+            (value.as_mut(), &pattern)
+        {
+            if function.name.is_none() {
+                function.name = Some(name.clone());
+            }
+        }
+        for (name, position) in bindings {
+            self.declare(name, token == Token::CONST, position);
+        }
+
         Ok(Box::new(StatementType::Let(LetStatement {
             token: Some(token),
-            name,
+            pattern,
             value,
+            position: statement_position,
+            slot: None,
+        })))
+    }
+
+    /// Parses a binding target starting at the current token: a name, `_`, a literal,
+    /// `[a, b, ...rest]`, `{key, key: pattern, ...rest}`, or alternatives joined by `|`.
+    /// Every bound name is appended to `bindings` with its position, and a name bound twice
+    /// in one pattern is reported.
+    fn parse_pattern(&mut self, bindings: &mut Vec<(String, Position)>) -> Option<Pattern> {
+        let start = bindings.len();
+        let first = self.parse_primary_pattern(bindings)?;
+        if !self.peek_token_is(Token::PIPE) {
+            return Some(first);
+        }
+
+        let sorted_names = |bindings: &[(String, Position)]| {
+            let mut names: Vec<String> = bindings.iter().map(|(name, _)| name.clone()).collect();
+            names.sort();
+            names
+        };
+        let names = sorted_names(&bindings[start..]);
+        let mut alternatives = vec![first];
+        while self.peek_token_is(Token::PIPE) {
+            self.next_token();
+            self.next_token();
+            let position = self.cur_position;
+            let mut alternative_bindings = Vec::new();
+            alternatives.push(self.parse_primary_pattern(&mut alternative_bindings)?);
+            if sorted_names(&alternative_bindings) != names {
+                self.errors.push(format!(
+                    "{}: every alternative of a pattern must bind the same names",
+                    position
+                ));
+            }
+        }
+        Some(Pattern::Or(alternatives))
+    }
+
+    fn parse_primary_pattern(&mut self, bindings: &mut Vec<(String, Position)>) -> Option<Pattern> {
+        match self.cur_token.clone() {
+            Token::IDENT(name) if name == "_" => Some(Pattern::Wildcard),
+            Token::INT(_) | Token::BIGINT(_) | Token::STRING(_) | Token::TRUE | Token::FALSE => {
+                let prefix = self.prefix_parse_fns[&self.cur_token.to_original_type()];
+                Some(Pattern::Literal(prefix(self)))
+            }
+            Token::MINUS
+                if self.peek_token_is(Token::INT(0))
+                    || self.peek_token_is(Token::BIGINT(String::new())) =>
+            {
+                Some(Pattern::Literal(self.parse_prefix_expression()))
+            }
+            Token::IDENT(name) => {
+                self.bind_pattern_name(name.clone(), bindings);
+                Some(Pattern::Identifier(name))
+            }
+            Token::LBRACKET => {
+                let mut elements = Vec::new();
+                let rest = self.parse_pattern_list(Token::RBRACKET, bindings, |p, bindings| {
+                    elements.push(p.parse_pattern(bindings)?);
+                    Some(())
+                })?;
+                Some(Pattern::Array(ArrayPattern { elements, rest }))
+            }
+            Token::LBRACE => {
+                let mut entries = Vec::new();
+                let rest = self.parse_pattern_list(Token::RBRACE, bindings, |p, bindings| {
+                    let Token::IDENT(key) = p.cur_token.clone() else {
+                        p.errors.push(format!(
+                            "{}: expected hash pattern key, got {:?}",
+                            p.cur_position,
+                            p.cur_token.to_string()
+                        ));
+                        return None;
+                    };
+                    let value = if p.peek_token_is(Token::COLON) {
+                        p.next_token();
+                        p.next_token();
+                        p.parse_pattern(bindings)?
+                    } else {
+                        p.bind_pattern_name(key.clone(), bindings);
+                        Pattern::Identifier(key.clone())
+                    };
+                    entries.push(HashPatternEntry { key, value });
+                    Some(())
+                })?;
+                Some(Pattern::Hash(HashPattern { entries, rest }))
+            }
+            token => {
+                self.errors.push(format!(
+                    "{}: expected binding pattern, got {:?}",
+                    self.cur_position,
+                    token.to_string()
+                ));
+                None
+            }
+        }
+    }
+
+    /// Parses the comma-separated entries of an array or hash pattern up to `end`, handing
+    /// each entry to `parse_entry` and returning the name of a trailing `...rest`, if any.
+    fn parse_pattern_list(
+        &mut self,
+        end: Token,
+        bindings: &mut Vec<(String, Position)>,
+        mut parse_entry: impl FnMut(&mut Parser, &mut Vec<(String, Position)>) -> Option<()>,
+    ) -> Option<Option<String>> {
+        let mut rest = None;
+        while !self.peek_token_is(end.clone()) {
+            self.next_token();
+            if let Some(rest) = &rest {
+                self.errors.push(format!(
+                    "{}: pattern entry follows rest element {:?}; the rest element must be last",
+                    self.cur_position, rest
+                ));
+                return None;
+            }
+            if self.cur_token_is(Token::ELLIPSIS) {
+                if !self.expect_peek(&Token::IDENT(String::new())) {
+                    return None;
+                }
+                let name = self.cur_token.to_string();
+                self.bind_pattern_name(name.clone(), bindings);
+                rest = Some(name);
+            } else {
+                parse_entry(self, bindings)?;
+            }
+            if !self.peek_token_is(end.clone()) && !self.expect_peek(&Token::COMMA) {
+                return None;
+            }
+        }
+        self.next_token();
+        Some(rest)
+    }
+
+    fn bind_pattern_name(&mut self, name: String, bindings: &mut Vec<(String, Position)>) {
+        if bindings.iter().any(|(bound, _)| *bound == name) {
+            self.errors.push(format!(
+                "{}: duplicate binding name {:?}",
+                self.cur_position, name
+            ));
+        }
+        bindings.push((name, self.cur_position));
+    }
+
+    #[auto_log]
+    fn parse_function_declaration(&mut self) -> Result<Box<StatementType>> {
+        let position = self.peek_position;
+        let function = match *self.parse_function_literal() {
+            ExpressionType::Fn(function) => function,
+            _ => return Err(anyhow!("failed to parse function declaration")),
+        };
+
+        if self.peek_token_is(Token::SEMICOLON) {
+            self.next_token();
+        }
+        if let Some(name) = &function.name {
+            self.declare(name.clone(), false, position);
+        }
+
+        Ok(Box::new(StatementType::Function(FunctionDeclaration {
+            function,
         })))
     }
 
     fn parse_reassign_statement(&mut self) -> Result<Box<StatementType>> {
         let name = self.cur_token.to_string();
+        let position = self.cur_position;
+        if self.is_constant(&name) {
+            self.errors
+                .push(format!("{}: assignment to constant: {}", position, name));
+        }
 
         if !self.expect_peek(&Token::ASSIGN) {
             return Err(anyhow!("failed to parse let statement"));
@@ -315,8 +1072,10 @@ impl Parser {
 
         Ok(Box::new(StatementType::Let(LetStatement {
             token: None,
-            name,
+            pattern: Pattern::Identifier(name),
             value,
+            position,
+            slot: None,
         })))
     }
 
@@ -333,6 +1092,237 @@ impl Parser {
         Ok(Box::new(StatementType::Return(ReturnStatement { value })))
     }
 
+    #[auto_log]
+    fn parse_throw_statement(&mut self) -> Result<Box<StatementType>> {
+        let position = self.cur_position;
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::LOWEST);
+
+        if self.peek_token_is(Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Ok(Box::new(StatementType::Throw(ThrowStatement {
+            value,
+            position,
+        })))
+    }
+
+    /// Parses `try { } catch (e) { } finally { }`, where at least one of `catch` and
+    /// `finally` must be present. The catch binding shares a scope with the catch body.
+    #[auto_log]
+    fn parse_try_statement(&mut self) -> Result<Box<StatementType>> {
+        let position = self.cur_position;
+        if !self.expect_peek(&Token::LBRACE) {
+            return Err(anyhow!("failed to parse try statement"));
+        }
+        let block = self.parse_scoped_block();
+
+        let mut catch = None;
+        if self.peek_token_is(Token::CATCH) {
+            self.next_token();
+            let mut name = None;
+            if self.peek_token_is(Token::LPAREN) {
+                self.next_token();
+                if !self.expect_peek(&Token::IDENT(String::new())) {
+                    return Err(anyhow!("failed to parse catch clause"));
+                }
+                name = Some(self.cur_token.to_string());
+                if !self.expect_peek(&Token::RPAREN) {
+                    return Err(anyhow!("failed to parse catch clause"));
+                }
+            }
+            if !self.expect_peek(&Token::LBRACE) {
+                return Err(anyhow!("failed to parse catch clause"));
+            }
+            self.enter_scope(name.iter().cloned().collect());
+            let body = self.parse_block_statement();
+            self.exit_scope();
+            catch = Some(CatchClause {
+                name,
+                body,
+                scope: crate::ast::Scope::default(),
+            });
+        }
+
+        let mut finally = None;
+        if self.peek_token_is(Token::FINALLY) {
+            self.next_token();
+            if !self.expect_peek(&Token::LBRACE) {
+                return Err(anyhow!("failed to parse finally clause"));
+            }
+            finally = Some(self.parse_scoped_block());
+        }
+
+        if catch.is_none() && finally.is_none() {
+            self.errors.push(format!(
+                "{}: try statement needs a catch or finally clause",
+                position
+            ));
+            return Err(anyhow!("failed to parse try statement"));
+        }
+
+        Ok(Box::new(StatementType::Try(TryStatement {
+            block,
+            catch,
+            finally,
+        })))
+    }
+
+    /// Parses `switch (value) { case test: ... default: ... }`. All clauses share one
+    /// block scope, since execution falls through from one clause into the next.
+    #[auto_log]
+    fn parse_switch_statement(&mut self) -> Result<Box<StatementType>> {
+        if !self.expect_peek(&Token::LPAREN) {
+            return Err(anyhow!("failed to parse switch statement"));
+        }
+
+        self.next_token();
+        let discriminant = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::RPAREN) || !self.expect_peek(&Token::LBRACE) {
+            return Err(anyhow!("failed to parse switch statement"));
+        }
+        self.next_token();
+
+        self.enter_scope(Vec::new());
+        let mut cases: Vec<SwitchCase> = Vec::new();
+        while !self.cur_token_is(Token::RBRACE) && !self.cur_token_is(Token::EOF) {
+            let test = match self.cur_token {
+                Token::CASE => {
+                    self.next_token();
+                    Some(self.parse_expression(Precedence::LOWEST))
+                }
+                Token::DEFAULT => {
+                    if cases.iter().any(|c| c.test.is_none()) {
+                        self.errors.push(format!(
+                            "{}: multiple default clauses in switch",
+                            self.cur_position
+                        ));
+                    }
+                    None
+                }
+                _ => {
+                    self.errors.push(format!(
+                        "{}: expected \"case\" or \"default\", got {:?}",
+                        self.cur_position,
+                        self.cur_token.to_string()
+                    ));
+                    self.exit_scope();
+                    return Err(anyhow!("failed to parse switch statement"));
+                }
+            };
+            if !self.expect_peek(&Token::COLON) {
+                self.exit_scope();
+                return Err(anyhow!("failed to parse switch statement"));
+            }
+            self.next_token();
+
+            let mut body = Vec::new();
+            while !self.cur_token_is(Token::CASE)
+                && !self.cur_token_is(Token::DEFAULT)
+                && !self.cur_token_is(Token::RBRACE)
+                && !self.cur_token_is(Token::EOF)
+            {
+                if let Ok(stmt) = self.parse_statement() {
+                    body.push(stmt);
+                }
+                self.next_token();
+            }
+            cases.push(SwitchCase { test, body });
+        }
+        self.exit_scope();
+
+        Ok(Box::new(StatementType::Switch(SwitchStatement {
+            discriminant,
+            cases,
+            scope: crate::ast::Scope::default(),
+        })))
+    }
+
+    #[auto_log]
+    fn parse_while_statement(&mut self) -> Result<Box<StatementType>> {
+        if !self.expect_peek(&Token::LPAREN) {
+            return Err(anyhow!("failed to parse while statement"));
+        }
+
+        self.next_token();
+        let condition = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::RPAREN) {
+            return Err(anyhow!("failed to parse while statement"));
+        }
+
+        if !self.expect_peek(&Token::LBRACE) {
+            return Err(anyhow!("failed to parse while statement"));
+        }
+
+        let body = self.parse_scoped_block();
+
+        Ok(Box::new(StatementType::While(WhileStatement {
+            condition,
+            body,
+        })))
+    }
+
+    #[auto_log]
+    fn parse_for_statement(&mut self) -> Result<Box<StatementType>> {
+        let position = self.cur_position;
+        if !self.expect_peek(&Token::LPAREN) {
+            return Err(anyhow!("failed to parse for statement"));
+        }
+
+        if !self.expect_peek(&Token::IDENT(String::new())) {
+            return Err(anyhow!("failed to parse for statement"));
+        }
+
+        let name = self.cur_token.to_string();
+
+        if !self.expect_peek(&Token::IN) {
+            return Err(anyhow!("failed to parse for statement"));
+        }
+
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::LOWEST);
+
+        if !self.expect_peek(&Token::RPAREN) {
+            return Err(anyhow!("failed to parse for statement"));
+        }
+
+        if !self.expect_peek(&Token::LBRACE) {
+            return Err(anyhow!("failed to parse for statement"));
+        }
+
+        self.enter_scope(vec![name.clone()]);
+        let body = self.parse_block_statement();
+        self.exit_scope();
+
+        Ok(Box::new(StatementType::For(ForStatement {
+            name,
+            iterable,
+            body,
+            position,
+            scope: crate::ast::Scope::default(),
+        })))
+    }
+
+    fn parse_break_statement(&mut self) -> Result<Box<StatementType>> {
+        if self.peek_token_is(Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Ok(Box::new(StatementType::Break(BreakStatement)))
+    }
+
+    fn parse_continue_statement(&mut self) -> Result<Box<StatementType>> {
+        if self.peek_token_is(Token::SEMICOLON) {
+            self.next_token();
+        }
+
+        Ok(Box::new(StatementType::Continue(ContinueStatement)))
+    }
+
     #[auto_log]
     fn parse_expression_statement(&mut self) -> Result<Box<StatementType>> {
         let expression = self.parse_expression(Precedence::LOWEST);
@@ -346,25 +1336,32 @@ impl Parser {
     }
 
     fn no_prefix_parse_fn_error(&mut self, t: Token) {
-        let msg = format!("no prefix parse function for {:?} found", t.to_string());
+        let msg = format!(
+            "{}: no prefix parse function for {:?} found",
+            self.cur_position,
+            t.to_string()
+        );
         self.errors.push(msg);
     }
 
     #[auto_log]
     fn parse_prefix_expression(&mut self) -> Box<ExpressionType> {
         let token = self.cur_token.clone();
+        let position = self.cur_position;
         self.next_token();
         let right = self.parse_expression(Precedence::PREFIX);
 
         Box::new(ExpressionType::Prefix(PrefixExpression {
             operator: token,
             right,
+            position,
         }))
     }
 
     #[auto_log]
     fn parse_infix_expression(&mut self, left: Box<ExpressionType>) -> Box<ExpressionType> {
         let token = self.cur_token.clone();
+        let position = self.cur_position;
         let precedence = self.cur_precedence();
         self.next_token();
         let right = self.parse_expression(precedence);
@@ -373,6 +1370,7 @@ impl Parser {
             operator: token,
             left,
             right,
+            position,
         }))
     }
 
@@ -384,7 +1382,11 @@ impl Parser {
 
         if prefix.is_none() {
             self.no_prefix_parse_fn_error(self.cur_token.clone());
-            return Box::new(ExpressionType::Identifier(Identifier { token: Token::EOF }));
+            return Box::new(ExpressionType::Identifier(Identifier {
+                token: Token::EOF,
+                position: self.cur_position,
+                slot: None,
+            }));
         }
 
         let mut left_exp = prefix.unwrap()(self);
@@ -448,7 +1450,8 @@ impl Parser {
 
     fn peek_error(&mut self, expected: &Token) {
         let msg = format!(
-            "expected next token to be {:?}, got {:?} instead",
+            "{}: expected next token to be {:?}, got {:?} instead",
+            self.peek_position,
             expected.to_string(),
             self.peek_token.to_string()
         );
@@ -0,0 +1,68 @@
+use crate::ast::{expression::ExpressionType, statement::StatementType};
+
+/// Marks the calls in a function body whose value the function returns as is, so the
+/// evaluator can run them without growing the stack: the operand of a `return`, and the
+/// last expression of the body, looking through `if`, `match` and `?:` branches.
+///
+/// `try` statements are skipped, since a call inside one must return before its `catch`
+/// and `finally` clauses can run.
+///
+/// Returns how many calls were marked.
+pub fn mark_tail_calls(body: &mut StatementType) -> usize {
+    let mut marked = 0;
+    mark_statement(body, true, &mut marked);
+    marked
+}
+
+/// `is_result` tells whether the statement's value becomes the function's return value.
+fn mark_statement(stmt: &mut StatementType, is_result: bool, marked: &mut usize) {
+    match stmt {
+        StatementType::Return(ret) => mark_expression(&mut ret.value, true, marked),
+        StatementType::Expression(stmt) => mark_expression(&mut stmt.expression, is_result, marked),
+        StatementType::Block(block) => {
+            let last = block.statements.len().saturating_sub(1);
+            for (idx, stmt) in block.statements.iter_mut().enumerate() {
+                mark_statement(stmt, is_result && idx == last, marked);
+            }
+        }
+        StatementType::While(ws) => mark_statement(&mut ws.body, false, marked),
+        StatementType::For(fs) => mark_statement(&mut fs.body, false, marked),
+        StatementType::Switch(ss) => {
+            for stmt in ss.cases.iter_mut().flat_map(|case| case.body.iter_mut()) {
+                mark_statement(stmt, false, marked);
+            }
+        }
+        StatementType::Try(_)
+        | StatementType::Let(_)
+        | StatementType::Throw(_)
+        | StatementType::Function(_)
+        | StatementType::Break(_)
+        | StatementType::Continue(_) => {}
+    }
+}
+
+/// Looks for `return` statements in branches even when `is_tail` is false.
+fn mark_expression(expr: &mut ExpressionType, is_tail: bool, marked: &mut usize) {
+    match expr {
+        ExpressionType::Call(call) => {
+            call.tail = is_tail;
+            *marked += usize::from(is_tail);
+        }
+        ExpressionType::If(ie) => {
+            mark_statement(&mut ie.consequence, is_tail, marked);
+            if let Some(alternative) = &mut ie.alternative {
+                mark_statement(alternative, is_tail, marked);
+            }
+        }
+        ExpressionType::Conditional(ce) => {
+            mark_expression(&mut ce.consequence, is_tail, marked);
+            mark_expression(&mut ce.alternative, is_tail, marked);
+        }
+        ExpressionType::Match(me) => {
+            for arm in &mut me.arms {
+                mark_statement(&mut arm.body, is_tail, marked);
+            }
+        }
+        _ => {}
+    }
+}
@@ -3,10 +3,11 @@ mod tests {
     use crate::{
         ast::{
             expression::{node::Identifier, ExpressionType},
+            pattern::Pattern,
             statement::{node::LetStatement, StatementType},
             Program, TNode,
         },
-        lexer::Lexer,
+        lexer::{Lexer, Position},
         parser::Parser,
         token::Token,
     };
@@ -31,16 +32,198 @@ mod tests {
             program.statements.len()
         );
 
-        let tests = vec![("x", Some(1)), ("y", Some(10)), ("foobar", Some(838383))];
+        let tests = [("x", Some(1)), ("y", Some(10)), ("foobar", Some(838383))];
 
         for (i, tt) in tests.iter().enumerate() {
             let stmt = &program.statements[i];
             let stmt = stmt.as_let().unwrap();
 
             assert_eq!(
-                stmt.name, tt.0,
+                stmt.pattern,
+                Pattern::Identifier(tt.0.to_string()),
                 "stmt.Name.Value not '{}'. got={}",
-                tt.0, stmt.name,
+                tt.0,
+                stmt.pattern.string(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_const_statements() {
+        let input = "const x = 1; let y = x; y = 2;";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program().unwrap_or_else(|e| {
+            panic!("parse_program() returned an error: {:?}", e);
+        });
+
+        assert_eq!(program.string(), "const x = 1;let y = x;y = 2;");
+        let stmt = program.statements[0].as_let().unwrap();
+        assert_eq!(stmt.token, Some(Token::CONST));
+    }
+
+    #[test]
+    fn test_const_assignment_errors() {
+        let tests = vec![
+            (
+                "const x = 1; x = 2;",
+                Some("1:14: assignment to constant: x"),
+            ),
+            (
+                "const x = 1; let f = fn() { x = 2; };",
+                Some("1:29: assignment to constant: x"),
+            ),
+            (
+                "const x = 1;\nwhile (true) { x = 2; }",
+                Some("2:16: assignment to constant: x"),
+            ),
+            ("const x = 1; let f = fn(x) { x = 2; };", None),
+            ("const x = 1; for (x in 0..2) { x = 2; }", None),
+            (
+                "const x = 1; let x = 2;",
+                Some("1:18: identifier already declared: x"),
+            ),
+            (
+                "let x = 1; const x = 2;",
+                Some("1:18: identifier already declared: x"),
+            ),
+            ("let x = 1; if (true) { const x = 2; } x = 3;", None),
+            ("const x = 1; if (true) { let x = 2; x = 3; }", None),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            match (p.parse_program(), expected) {
+                (Ok(_), None) => {}
+                (Ok(_), Some(expected)) => panic!("expected {:?} for {:?}", expected, input),
+                (Err(err), None) => panic!("unexpected error for {:?}: {:?}", input, err),
+                (Err(err), Some(expected)) => assert!(
+                    err.to_string().contains(expected),
+                    "wrong error for {:?}. want {:?} in {:?}",
+                    input,
+                    expected,
+                    err
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_destructuring_let_statements() {
+        let tests = vec![
+            ("let [a, b] = xs;", "let [a, b] = xs;"),
+            ("let [a, ...rest] = xs;", "let [a, ...rest] = xs;"),
+            ("let [] = xs;", "let [] = xs;"),
+            ("let {name, age: years} = p;", "let {name, age: years} = p;"),
+            (
+                "const {pos: [x, y], ...others} = p;",
+                "const {pos: [x, y], ...others} = p;",
+            ),
+            ("let [{a}, [b, c],] = xs;", "let [{a}, [b, c]] = xs;"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program().unwrap_or_else(|e| {
+                panic!("parse_program() returned an error: {:?}", e);
+            });
+            assert_eq!(program.statements.len(), 1);
+            assert_eq!(program.string(), expected);
+        }
+
+        let l = Lexer::new("let [a, {b: c}] = xs;".to_string());
+        let program = Parser::new(l).parse_program().unwrap();
+        let stmt = program.statements[0].as_let().unwrap();
+        assert_eq!(stmt.pattern.names(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_destructuring_pattern_errors() {
+        let tests =
+            vec![
+            ("let [a, a] = xs;", "1:9: duplicate binding name \"a\""),
+            ("let {a, b: a} = h;", "1:12: duplicate binding name \"a\""),
+            (
+                "let [...rest, a] = xs;",
+                "1:15: pattern entry follows rest element \"rest\"; the rest element must be last",
+            ),
+            ("let {\"a\"} = h;", "1:6: expected hash pattern key, got \"a\""),
+            ("let [(a)] = xs;", "1:6: expected binding pattern, got \"(\""),
+            ("const [a] = xs; a = 1;", "1:17: assignment to constant: a"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let err = match p.parse_program() {
+                Ok(_) => panic!("expected a parser error for {:?}", input),
+                Err(err) => err.to_string(),
+            };
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_number_literals() {
+        for (input, expected) in [
+            ("12n5;", "1:1: malformed number literal \"12n5\""),
+            (
+                "let x = [1, 2n34];",
+                "1:13: malformed number literal \"2n34\"",
+            ),
+        ] {
+            let err = Parser::new(Lexer::new(input.to_string()))
+                .parse_program()
+                .expect_err(input)
+                .to_string();
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_misplaced_spread() {
+        for (input, expected) in [
+            (
+                "let a = ...xs;",
+                "1:9: spread syntax is only allowed in calls and array literals",
+            ),
+            (
+                "...xs + 1",
+                "1:1: spread syntax is only allowed in calls and array literals",
+            ),
+            (
+                "h[...xs]",
+                "1:3: spread syntax is only allowed in calls and array literals",
+            ),
+            (
+                "f(x: ...xs)",
+                "1:6: spread syntax is only allowed in calls and array literals",
+            ),
+        ] {
+            let err = Parser::new(Lexer::new(input.to_string()))
+                .parse_program()
+                .expect_err(input)
+                .to_string();
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
             );
         }
     }
@@ -50,10 +233,14 @@ mod tests {
         let program = Program {
             statements: vec![Box::new(StatementType::Let(LetStatement {
                 token: Some(Token::LET),
-                name: "myVar".to_string(),
+                pattern: Pattern::Identifier("myVar".to_string()),
                 value: Box::new(ExpressionType::Identifier(Identifier {
                     token: Token::IDENT("anotherVar".to_string()),
+                    position: Position::default(),
+                    slot: None,
                 })),
+                position: Position::default(),
+                slot: None,
             }))],
         };
 
@@ -252,6 +439,7 @@ mod tests {
                 "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
                 "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
             ),
+            ("f(a, b: c ? 1 : 2)", "f(a, b: (c ? 1 : 2))"),
             (
                 "add(a + b + c * d / f + g)",
                 "add((((a + b) + ((c * d) / f)) + g))",
@@ -280,6 +468,24 @@ mod tests {
                 "3 + 4 * 5 == 3 * 1 + 4 * 5",
                 "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
             ),
+            ("0..n + 1", "(0 .. (n + 1))"),
+            ("a ? b : c", "(a ? b : c)"),
+            ("a == 1 ? b + 1 : c * 2", "((a == 1) ? (b + 1) : (c * 2))"),
+            ("a ? b : c ? d : e", "(a ? b : (c ? d : e))"),
+            ("a ? b ? c : d : e", "(a ? (b ? c : d) : e)"),
+            ("f(a ? b : c, d)", "f((a ? b : c), d)"),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
+            ("f(...xs, [...ys, 1])", "f(...xs, [...ys, 1])"),
+            ("a..=b * 2 == c", "((a ..= (b * 2)) == c)"),
+            ("-a.b.c * 2", "((-((a[b])[c])) * 2)"),
+            ("a.b(1)[0]", "((a[b])(1)[0])"),
         ];
 
         for (input, expected) in tests {
@@ -343,6 +549,232 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_if_else_if_expression() {
+        let input = "if (x < y) { x } else if (x > y) { y } else { z }";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program.Body does not contain 1 statement"
+        );
+
+        let stmt = program.statements[0].as_expression().unwrap();
+        let exp = stmt.expression.as_ref().as_if().unwrap();
+
+        let alternative = exp
+            .alternative
+            .as_ref()
+            .expect("alternative is missing")
+            .as_expression()
+            .expect("alternative is not an expression statement");
+        let else_if = alternative.expression.as_ref().as_if().unwrap();
+
+        assert_eq!(
+            else_if.condition.string(),
+            "(x > y)",
+            "else_if.Condition is not 'x > y'. got={}",
+            else_if.condition.string()
+        );
+        assert!(
+            else_if.alternative.as_ref().unwrap().is_block(),
+            "final alternative is not a block"
+        );
+
+        assert_eq!(
+            program.string(),
+            "if (x < y) { {x} } else if (x > y) { {y} } else { {z} }"
+        );
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let input = "while (x < y) { x; break; continue; }";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program.Body does not contain 1 statement"
+        );
+
+        let stmt = program.statements[0].as_while().unwrap();
+
+        assert_eq!(
+            stmt.condition.string(),
+            "(x < y)",
+            "stmt.Condition is not 'x < y'. got={}",
+            stmt.condition.string()
+        );
+
+        let body = &stmt.body.as_block().unwrap().statements;
+        assert_eq!(
+            body.len(),
+            3,
+            "body is not 3 statements. got={}",
+            body.len()
+        );
+        assert!(body[1].is_break(), "body[1] is not a break statement");
+        assert!(body[2].is_continue(), "body[2] is not a continue statement");
+    }
+
+    #[test]
+    fn test_switch_statement() {
+        let input = "switch (x) { case 1: case 2: y; break; default: z; case a ? 3 : 4: }";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program().unwrap_or_else(|e| {
+            panic!("parse_program() returned an error: {:?}", e);
+        });
+        assert_eq!(program.statements.len(), 1);
+
+        let stmt = program.statements[0].as_switch().unwrap();
+        assert_eq!(stmt.discriminant.string(), "x");
+        assert_eq!(stmt.cases.len(), 4);
+        assert_eq!(stmt.cases[0].body.len(), 0);
+        assert_eq!(stmt.cases[1].body.len(), 2);
+        assert!(stmt.cases[2].test.is_none());
+        assert_eq!(
+            stmt.string(),
+            "switch (x) { case 1: case 2: y break; default: z case (a ? 3 : 4): }"
+        );
+
+        let tests = vec![
+            (
+                "switch (x) { default: 1; default: 2; }",
+                "1:26: multiple default clauses in switch",
+            ),
+            (
+                "switch (x) { 1: }",
+                "1:14: expected \"case\" or \"default\", got \"1\"",
+            ),
+            (
+                "switch (x) { case 1 2 }",
+                "1:21: expected next token to be \":\", got \"2\" instead",
+            ),
+            (
+                "const c = 1; switch (x) { case 1: c = 2; }",
+                "1:35: assignment to constant: c",
+            ),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let err = match p.parse_program() {
+                Ok(_) => panic!("expected a parser error for {:?}", input),
+                Err(err) => err.to_string(),
+            };
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_statement() {
+        let tests = vec![
+            (
+                "try { throw 1; } catch (e) { e } finally { 2 }",
+                "try {throw 1;} catch (e) {e} finally {2}",
+            ),
+            ("try { f() } catch { 0 }", "try {f()} catch {0}"),
+            ("try { f() } finally { g() }", "try {f()} finally {g()}"),
+            ("throw {\"message\": m};", "throw {message: m};"),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program().unwrap_or_else(|e| {
+                panic!("parse_program() returned an error: {:?}", e);
+            });
+            assert_eq!(program.statements.len(), 1);
+            assert_eq!(program.string(), expected);
+        }
+
+        let tests = vec![
+            (
+                "try { 1 }",
+                "1:1: try statement needs a catch or finally clause",
+            ),
+            (
+                "try { 1 } catch (1) { }",
+                "1:18: expected next token to be \"\", got \"1\" instead",
+            ),
+        ];
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let err = match p.parse_program() {
+                Ok(_) => panic!("expected a parser error for {:?}", input),
+                Err(err) => err.to_string(),
+            };
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+        }
+
+        // The catch binding shadows the outer constant.
+        let l = Lexer::new("const e = 1; try { } catch (e) { e = 2; }".to_string());
+        Parser::new(l).parse_program().unwrap_or_else(|e| {
+            panic!("parse_program() returned an error: {:?}", e);
+        });
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let input = "for (x in 0..10) { x; }";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program.Body does not contain 1 statement"
+        );
+
+        let stmt = program.statements[0].as_for().unwrap();
+
+        assert_eq!(stmt.name, "x", "stmt.Name is not 'x'. got={}", stmt.name);
+        assert_eq!(
+            stmt.iterable.string(),
+            "(0 .. 10)",
+            "stmt.Iterable is not '0..10'. got={}",
+            stmt.iterable.string()
+        );
+        assert_eq!(
+            stmt.body.as_block().unwrap().statements.len(),
+            1,
+            "body is not 1 statements. got={}",
+            stmt.body.as_block().unwrap().statements.len()
+        );
+    }
+
     #[test]
     fn test_fn_literal_expression() {
         let input = "fn(x, y) { x + y; }";
@@ -371,17 +803,17 @@ mod tests {
         );
 
         assert_eq!(
-            exp.parameters[0].as_ref().token_literal(),
+            exp.parameters[0].token_literal(),
             "x",
             "parameter is not 'x'. got={}",
-            exp.parameters[0].as_ref().token_literal()
+            exp.parameters[0].token_literal()
         );
 
         assert_eq!(
-            exp.parameters[1].as_ref().token_literal(),
+            exp.parameters[1].token_literal(),
             "y",
             "parameter is not 'y'. got={}",
-            exp.parameters[1].as_ref().token_literal()
+            exp.parameters[1].token_literal()
         );
 
         assert_eq!(
@@ -403,12 +835,111 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_function_declaration() {
+        let input = "fn add(x, y) { x + y; } let sub = fn(x, y) { x - y; };";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        assert_eq!(
+            program.statements.len(),
+            2,
+            "program.Statements does not contain 2 statements. got={}",
+            program.statements.len()
+        );
+
+        let decl = program.statements[0].as_function().unwrap();
+        assert_eq!(
+            decl.name(),
+            "add",
+            "decl.Name is not 'add'. got={}",
+            decl.name()
+        );
+        assert_eq!(decl.function.parameters.len(), 2);
+        assert_eq!(decl.string(), "fn add(x,y){(x + y)}");
+
+        let let_stmt = program.statements[1].as_let().unwrap();
+        let function = let_stmt.value.as_fn().unwrap();
+        assert_eq!(function.name.as_deref(), Some("sub"));
+    }
+
+    #[test]
+    fn test_arrow_function_parsing() {
+        let tests = vec![
+            (
+                "(a, b) => a + b",
+                vec!["a", "b"],
+                "fn(a,b){return (a + b);}",
+            ),
+            ("x => { x * 2; }", vec!["x"], "fn(x){(x * 2)}"),
+            ("() => 1", vec![], "fn(){return 1;}"),
+            (
+                "(x) => (x + 1) * 2",
+                vec!["x"],
+                "fn(x){return ((x + 1) * 2);}",
+            ),
+            (
+                "x => y => x + y",
+                vec!["x"],
+                "fn(x){return fn(y){return (x + y);};}",
+            ),
+        ];
+
+        for (input, expected_params, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program();
+            if program.is_err() {
+                panic!("parse_program() returned an error: {:?}", program.err());
+            }
+            let program = program.unwrap();
+
+            let stmt = program.statements[0].as_expression().unwrap();
+            let function = stmt.expression.as_ref().as_fn().unwrap();
+
+            let params: Vec<String> = function.parameters.iter().map(|p| p.string()).collect();
+            assert_eq!(params, expected_params, "wrong parameters for {}", input);
+            assert_eq!(
+                program.string(),
+                expected,
+                "expected={}, got={}",
+                expected,
+                program.string()
+            );
+        }
+
+        let tests = vec![
+            ("(a + b) * c", "((a + b) * c)"),
+            ("((a)) + b", "(a + b)"),
+            ("apply((x) => x * 2, 5)", "apply(fn(x){return (x * 2);}, 5)"),
+            ("(() => 7)()", "fn(){return 7;}()"),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program();
+            if program.is_err() {
+                panic!("parse_program() returned an error: {:?}", program.err());
+            }
+            let actual = program.unwrap().string();
+            assert_eq!(actual, expected, "expected={}, got={}", expected, actual);
+        }
+    }
+
     #[test]
     fn test_function_parameter_parsing() {
         let tests = vec![
             ("fn() {};", vec![]),
             ("fn(x) {};", vec!["x"]),
             ("fn(x, y, z) {};", vec!["x", "y", "z"]),
+            ("fn(x, y = 10) {};", vec!["x", "y = 10"]),
+            ("fn(x, ...rest) {};", vec!["x", "...rest"]),
         ];
 
         for (input, expected_params) in tests {
@@ -432,18 +963,200 @@ mod tests {
             );
 
             for (i, ident) in expected_params.iter().enumerate() {
-                let param = function.parameters[i].as_ref();
+                let param = &function.parameters[i];
                 assert_eq!(
-                    param.token_literal(),
+                    param.string(),
                     ident.to_string(),
                     "parameter is not '{}'. got={}",
                     ident,
-                    param.token_literal()
+                    param.string()
                 );
             }
         }
     }
 
+    #[test]
+    fn test_function_parameter_errors() {
+        let tests = vec![
+            (
+                "fn(1, \"a\") {};",
+                "1:4: expected parameter name, got \"1\"",
+            ),
+            ("fn(x, x) {};", "1:7: duplicate parameter name \"x\""),
+            ("(a, b, a) => a;", "1:8: duplicate parameter name \"a\""),
+            (
+                "fn(...rest, x) {};",
+                "1:13: parameter \"x\" follows a rest parameter; the rest parameter must be last",
+            ),
+            (
+                "fn(...rest = 1) {};",
+                "1:12: rest parameter \"rest\" cannot have a default value",
+            ),
+            (
+                "let f = fn(x,\n  2) {};",
+                "2:3: expected parameter name, got \"2\"",
+            ),
+            ("f(a: 1, a: 2);", "1:9: duplicate named argument \"a\""),
+            (
+                "f(a: 1, 2);",
+                "1:9: positional argument follows named argument \"a\"",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let err = match p.parse_program() {
+                Ok(_) => panic!("expected a parser error for {:?}", input),
+                Err(err) => err.to_string(),
+            };
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+            assert!(
+                err.contains("Parser has 1 errors"),
+                "expected exactly one error for {:?}. got={:?}",
+                input,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_array_literal_parsing() {
+        let input = "[1, 2 * 2, 3 + 3]";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        let stmt = program.statements[0].as_expression().unwrap();
+        let array = stmt.expression.as_ref().as_array().unwrap();
+
+        assert_eq!(
+            array.elements.len(),
+            3,
+            "len(array.Elements) not 3. got={}",
+            array.elements.len()
+        );
+        assert_eq!(array.elements[0].string(), "1");
+        assert_eq!(array.elements[1].string(), "(2 * 2)");
+        assert_eq!(array.elements[2].string(), "(3 + 3)");
+    }
+
+    #[test]
+    fn test_hash_literal_parsing() {
+        let tests = vec![
+            ("{}", "{}"),
+            (r#"{"one": 1, "two": 2}"#, "{one: 1, two: 2}"),
+            (
+                r#"{"one": 0 + 1, true: 2, 3: "x",}"#,
+                "{one: (0 + 1), true: 2, 3: x}",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program().unwrap_or_else(|e| {
+                panic!("parse_program() returned an error: {:?}", e);
+            });
+            let stmt = program.statements[0].as_expression().unwrap();
+            let hash = stmt.expression.as_ref().as_hash().unwrap();
+            assert_eq!(hash.string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_match_expression_parsing() {
+        let tests = vec![
+            (
+                "match (x) { 1 | 2 => a, _ => b }",
+                "match (x) { 1 | 2 => a, _ => b }",
+            ),
+            (
+                r#"match (x) { [a, ...r] if a > 1 => { a } {kind: "a", n} => n, -1 => 0, }"#,
+                r#"match (x) { [a, ...r] if (a > 1) => {a}, {kind: "a", n} => n, (-1) => 0 }"#,
+            ),
+            ("match (x) {}", "match (x) {  }"),
+            (
+                "match (x) { n if (n) => f(y => y), n if g((a) => a) => 1 }",
+                "match (x) { n if n => f(fn(y){return y;}), n if g(fn(a){return a;}) => 1 }",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let program = p.parse_program().unwrap_or_else(|e| {
+                panic!("parse_program() returned an error: {:?}", e);
+            });
+            let stmt = program.statements[0].as_expression().unwrap();
+            let expression = stmt.expression.as_ref().as_match().unwrap();
+            assert_eq!(expression.string(), expected);
+        }
+
+        let tests = vec![
+            (
+                "match (x) { 1 => a 2 => b }",
+                "1:20: expected next token to be \",\"",
+            ),
+            (
+                "match (x) { [a] | b => a }",
+                "1:19: every alternative of a pattern must bind the same names",
+            ),
+            (
+                "let [1, a] = xs;",
+                "1:5: refutable pattern in let binding: [1, a]",
+            ),
+            (
+                "const a = 1; match (a) { b => { a = b } }",
+                "1:33: assignment to constant: a",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let l = Lexer::new(input.to_string());
+            let mut p = Parser::new(l);
+            let err = match p.parse_program() {
+                Ok(_) => panic!("expected a parser error for {:?}", input),
+                Err(err) => err.to_string(),
+            };
+            assert!(
+                err.contains(expected),
+                "wrong error for {:?}. want {:?} in {:?}",
+                input,
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_expression_parsing() {
+        let input = "myArray[1 + 1]";
+        let l = Lexer::new(input.to_string());
+        let mut p = Parser::new(l);
+        let program = p.parse_program();
+        if program.is_err() {
+            panic!("parse_program() returned an error: {:?}", program.err());
+        }
+        let program = program.unwrap();
+
+        let stmt = program.statements[0].as_expression().unwrap();
+        let index = stmt.expression.as_ref().as_index().unwrap();
+
+        assert_eq!(index.left.string(), "myArray");
+        assert_eq!(index.index.string(), "(1 + 1)");
+    }
+
     #[test]
     fn test_call_expression_parsing() {
         let input = "add(1, 2 * 3, 4 + 5);";
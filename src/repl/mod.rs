@@ -3,9 +3,12 @@ use std::{
     rc::Rc,
 };
 
-use crate::{evaluator::eval, lexer::Lexer, object::environment::Environment, parser::Parser};
+use crate::{
+    cli::Engine, evaluator::Context, lexer::Lexer, object::environment::Environment,
+    parser::Parser, resolver::resolve,
+};
 
-pub fn run_repl() {
+pub fn run_repl(mut ctx: Context, engine: Engine) {
     println!("Welcome to the REPL CLI. Type 'exit' to quit.");
     let env = Environment::new();
     loop {
@@ -31,8 +34,20 @@ pub fn run_repl() {
             continue;
         }
 
-        let x = eval(Box::new(program.unwrap().to_node()), Rc::clone(&env));
-        println!("{:?}", x);
+        let program = match resolve(program.unwrap(), &env) {
+            Ok(program) => program,
+            Err(errors) => {
+                for err in errors {
+                    println!("error: {}", err.report());
+                }
+                continue;
+            }
+        };
+
+        match engine.run(program, Rc::clone(&env), &mut ctx) {
+            Ok(x) => println!("{:?}", x),
+            Err(err) => println!("error: {}", err.report()),
+        }
     }
 
     println!("Exit REPL!");
@@ -0,0 +1,341 @@
+use std::{cell::RefCell, ops::Deref, rc::Rc};
+
+use crate::{
+    ast::{
+        expression::{node::FunctionLiteral, ExpressionType},
+        pattern::Pattern,
+        statement::{node::BlockStatement, StatementType},
+        Program, Scope, Slot, TNode,
+    },
+    evaluator::{ErrorKind, RuntimeError},
+    object::environment::Environment,
+};
+
+pub mod test;
+
+/// Resolves every variable in `program` to the slot it lives in, so the evaluator reads
+/// variables by index instead of searching scopes by name. `env` is the environment the
+/// program will run in; its top-level names are added to it.
+///
+/// A name refers to the innermost scope that declares it anywhere, before or after the
+/// use. Reading a variable before its declaration runs therefore fails at runtime with
+/// `ErrorKind::UsedBeforeDeclaration` instead of falling back to an outer variable of the
+/// same name. Names no scope declares are reported here, before anything runs.
+pub fn resolve(
+    mut program: Program,
+    env: &Rc<RefCell<Environment>>,
+) -> Result<ResolvedProgram, Vec<RuntimeError>> {
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        captured: Vec::new(),
+        functions: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let mut enclosing = Vec::new();
+    let mut outer = env.borrow().outer().cloned();
+    while let Some(env) = outer {
+        enclosing.push(env.borrow().names().to_vec());
+        outer = env.borrow().outer().cloned();
+    }
+    for names in enclosing.into_iter().rev() {
+        resolver.push_scope(names);
+    }
+
+    {
+        let mut env = env.borrow_mut();
+        for name in declared_names(&program.statements) {
+            env.slot_of(&name);
+        }
+        resolver.push_scope(env.names().iter().cloned());
+    }
+    resolver.resolve_statements(&mut program.statements);
+
+    match resolver.errors.is_empty() {
+        true => Ok(ResolvedProgram(program)),
+        false => Err(resolver.errors),
+    }
+}
+
+/// A program whose variables were resolved against the environment it runs in, which is
+/// what `evaluator::eval` and `compiler::compile` need. Only `resolve` makes one.
+#[derive(Debug)]
+pub struct ResolvedProgram(Program);
+
+impl Deref for ResolvedProgram {
+    type Target = Program;
+
+    fn deref(&self) -> &Program {
+        &self.0
+    }
+}
+
+/// The names a list of statements declares directly in its own scope, in source order.
+fn declared_names(stmts: &[Box<StatementType>]) -> Vec<String> {
+    let mut names = Vec::new();
+    for stmt in stmts {
+        match stmt.as_ref() {
+            StatementType::Let(let_stmt) if let_stmt.token.is_some() => {
+                names.extend(let_stmt.pattern.names())
+            }
+            StatementType::Function(decl) => names.push(decl.name()),
+            _ => {}
+        }
+    }
+    names
+}
+
+struct Resolver {
+    /// The names of every enclosing scope, innermost last, each in slot order.
+    scopes: Vec<Vec<String>>,
+    /// Whether a nested function refers to each variable of `scopes`.
+    captured: Vec<Vec<bool>>,
+    /// The index in `scopes` of the scope of each enclosing function, innermost last.
+    functions: Vec<usize>,
+    errors: Vec<RuntimeError>,
+}
+
+impl Resolver {
+    fn push_scope(&mut self, names: impl IntoIterator<Item = String>) {
+        let mut scope = Vec::new();
+        for name in names {
+            if !scope.contains(&name) {
+                scope.push(name);
+            }
+        }
+        self.captured.push(vec![false; scope.len()]);
+        self.scopes.push(scope);
+    }
+
+    fn pop_scope(&mut self) -> Scope {
+        Scope {
+            names: self.scopes.pop().unwrap().into(),
+            captured: self.captured.pop().unwrap().into(),
+        }
+    }
+
+    /// Finds the slot of `name`, marking it captured when it lies outside the innermost
+    /// function.
+    fn lookup(&mut self, name: &str) -> Option<Slot> {
+        let slot = self
+            .scopes
+            .iter()
+            .rev()
+            .enumerate()
+            .find_map(|(depth, scope)| {
+                let index = scope.iter().position(|n| n == name)?;
+                Some(Slot { depth, index })
+            })?;
+        let scope = self.scopes.len() - 1 - slot.depth;
+        if self
+            .functions
+            .last()
+            .is_some_and(|&function| scope < function)
+        {
+            self.captured[scope][slot.index] = true;
+        }
+        Some(slot)
+    }
+
+    fn resolve_statements(&mut self, stmts: &mut [Box<StatementType>]) {
+        for stmt in stmts {
+            self.resolve_statement(stmt);
+        }
+    }
+
+    /// Resolves a function, `for` or `catch` body, which declares its names in the scope
+    /// already pushed for the parameters or binding.
+    fn resolve_body(&mut self, body: &mut StatementType) {
+        match body {
+            StatementType::Block(BlockStatement { statements, .. }) => {
+                let names = declared_names(statements);
+                let scope = self.scopes.last_mut().unwrap();
+                let captured = self.captured.last_mut().unwrap();
+                for name in names {
+                    if !scope.contains(&name) {
+                        scope.push(name);
+                        captured.push(false);
+                    }
+                }
+                self.resolve_statements(statements);
+            }
+            body => self.resolve_statement(body),
+        }
+    }
+
+    fn resolve_statement(&mut self, stmt: &mut StatementType) {
+        match stmt {
+            StatementType::Expression(stmt) => self.resolve_expression(&mut stmt.expression),
+            StatementType::Block(block) => {
+                self.push_scope(declared_names(&block.statements));
+                self.resolve_statements(&mut block.statements);
+                block.scope = self.pop_scope();
+            }
+            StatementType::Function(decl) => self.resolve_function(&mut decl.function),
+            StatementType::While(ws) => {
+                self.resolve_expression(&mut ws.condition);
+                self.resolve_statement(&mut ws.body);
+            }
+            StatementType::For(fs) => {
+                self.resolve_expression(&mut fs.iterable);
+                self.push_scope([fs.name.clone()]);
+                self.resolve_body(&mut fs.body);
+                fs.scope = self.pop_scope();
+            }
+            StatementType::Switch(ss) => {
+                self.resolve_expression(&mut ss.discriminant);
+                for case in ss.cases.iter_mut() {
+                    if let Some(test) = &mut case.test {
+                        self.resolve_expression(test);
+                    }
+                }
+                let names: Vec<String> = ss
+                    .cases
+                    .iter()
+                    .flat_map(|case| declared_names(&case.body))
+                    .collect();
+                self.push_scope(names);
+                for case in ss.cases.iter_mut() {
+                    self.resolve_statements(&mut case.body);
+                }
+                ss.scope = self.pop_scope();
+            }
+            StatementType::Throw(ts) => self.resolve_expression(&mut ts.value),
+            StatementType::Try(ts) => {
+                self.resolve_statement(&mut ts.block);
+                if let Some(catch) = &mut ts.catch {
+                    self.push_scope(catch.name.clone());
+                    self.resolve_body(&mut catch.body);
+                    catch.scope = self.pop_scope();
+                }
+                if let Some(finally) = &mut ts.finally {
+                    self.resolve_statement(finally);
+                }
+            }
+            StatementType::Break(_) | StatementType::Continue(_) => {}
+            StatementType::Return(rs) => self.resolve_expression(&mut rs.value),
+            StatementType::Let(let_stmt) => {
+                self.resolve_expression(&mut let_stmt.value);
+                if let_stmt.token.is_some() {
+                    self.resolve_pattern(&mut let_stmt.pattern);
+                    return;
+                }
+                let name = let_stmt.pattern.string();
+                let_stmt.slot = self.lookup(&name);
+                if let_stmt.slot.is_none() {
+                    self.errors
+                        .push(ErrorKind::UndefinedIdentifier(name).at(let_stmt.position));
+                }
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, function: &mut FunctionLiteral) {
+        let parameters = Rc::get_mut(&mut function.parameters)
+            .expect("function literals are resolved before they are shared");
+        self.functions.push(self.scopes.len());
+        self.push_scope(parameters.iter().map(|p| p.name.clone()));
+        for param in parameters.iter_mut() {
+            if let Some(default) = &mut param.default {
+                self.resolve_expression(default);
+            }
+        }
+        let body = Rc::get_mut(&mut function.body)
+            .expect("function literals are resolved before they are shared");
+        self.resolve_body(body);
+        function.scope = self.pop_scope();
+        self.functions.pop();
+    }
+
+    /// Resolves the literals a pattern compares against. Its names are declared by the
+    /// scope the pattern binds in.
+    fn resolve_pattern(&mut self, pattern: &mut Pattern) {
+        match pattern {
+            Pattern::Identifier(_) | Pattern::Wildcard => {}
+            Pattern::Literal(literal) => self.resolve_expression(literal),
+            Pattern::Or(alternatives) => alternatives
+                .iter_mut()
+                .for_each(|alternative| self.resolve_pattern(alternative)),
+            Pattern::Array(pattern) => pattern
+                .elements
+                .iter_mut()
+                .for_each(|element| self.resolve_pattern(element)),
+            Pattern::Hash(pattern) => pattern
+                .entries
+                .iter_mut()
+                .for_each(|entry| self.resolve_pattern(&mut entry.value)),
+        }
+    }
+
+    fn resolve_expression(&mut self, expr: &mut ExpressionType) {
+        match expr {
+            ExpressionType::Identifier(ident) => {
+                let name = ident.token.to_string();
+                ident.slot = self.lookup(&name);
+                if ident.slot.is_none() {
+                    self.errors
+                        .push(ErrorKind::UndefinedIdentifier(name).at(ident.position));
+                }
+            }
+            ExpressionType::IntegerLiteral(_)
+            | ExpressionType::BigIntLiteral(_)
+            | ExpressionType::StringLiteral(_)
+            | ExpressionType::Boolean(_) => {}
+            ExpressionType::Prefix(prefix) => self.resolve_expression(&mut prefix.right),
+            ExpressionType::Infix(infix) => {
+                self.resolve_expression(&mut infix.left);
+                self.resolve_expression(&mut infix.right);
+            }
+            ExpressionType::If(ie) => {
+                self.resolve_expression(&mut ie.condition);
+                self.resolve_statement(&mut ie.consequence);
+                if let Some(alternative) = &mut ie.alternative {
+                    self.resolve_statement(alternative);
+                }
+            }
+            ExpressionType::Conditional(ce) => {
+                self.resolve_expression(&mut ce.condition);
+                self.resolve_expression(&mut ce.consequence);
+                self.resolve_expression(&mut ce.alternative);
+            }
+            ExpressionType::Fn(function) => self.resolve_function(function),
+            ExpressionType::Call(call) => {
+                self.resolve_expression(&mut call.function);
+                for argument in call.arguments.iter_mut() {
+                    self.resolve_expression(argument);
+                }
+                for argument in call.named.iter_mut() {
+                    self.resolve_expression(&mut argument.value);
+                }
+            }
+            ExpressionType::Array(array) => {
+                for element in array.elements.iter_mut() {
+                    self.resolve_expression(element);
+                }
+            }
+            ExpressionType::Hash(hash) => {
+                for (key, value) in hash.pairs.iter_mut() {
+                    self.resolve_expression(key);
+                    self.resolve_expression(value);
+                }
+            }
+            ExpressionType::Index(ie) => {
+                self.resolve_expression(&mut ie.left);
+                self.resolve_expression(&mut ie.index);
+            }
+            ExpressionType::Match(me) => {
+                self.resolve_expression(&mut me.subject);
+                for arm in me.arms.iter_mut() {
+                    self.push_scope(arm.pattern.names());
+                    self.resolve_pattern(&mut arm.pattern);
+                    if let Some(guard) = &mut arm.guard {
+                        self.resolve_expression(guard);
+                    }
+                    self.resolve_statement(&mut arm.body);
+                    arm.scope = self.pop_scope();
+                }
+            }
+            ExpressionType::Spread(spread) => self.resolve_expression(&mut spread.value),
+        }
+    }
+}
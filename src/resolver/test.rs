@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{expression::ExpressionType, statement::StatementType, Program, ScopeNames, Slot},
+        lexer::Lexer,
+        object::environment::Environment,
+        parser::Parser,
+        resolver::{resolve, ResolvedProgram},
+    };
+
+    #[test]
+    fn test_slots() {
+        let program = test_resolve("let a = 1; let f = fn(x, y) { let z = x; a + y + z }; f");
+
+        let StatementType::Let(f) = program.statements[1].as_ref() else {
+            panic!("expected a let statement");
+        };
+        let ExpressionType::Fn(function) = f.value.as_ref() else {
+            panic!("expected a function literal");
+        };
+        assert_eq!(*function.scope.names, ["x", "y", "z"]);
+
+        let body = function.body.as_block().unwrap();
+        let StatementType::Expression(sum) = body.statements[1].as_ref() else {
+            panic!("expected an expression statement");
+        };
+        let mut slots = Vec::new();
+        collect_slots(&sum.expression, &mut slots);
+        assert_eq!(
+            slots,
+            vec![
+                ("a".to_string(), Slot { depth: 1, index: 0 }),
+                ("y".to_string(), Slot { depth: 0, index: 1 }),
+                ("z".to_string(), Slot { depth: 0, index: 2 }),
+            ]
+        );
+
+        let StatementType::Expression(last) = program.statements[2].as_ref() else {
+            panic!("expected an expression statement");
+        };
+        let ExpressionType::Identifier(f) = last.expression.as_ref() else {
+            panic!("expected an identifier");
+        };
+        assert_eq!(f.slot, Some(Slot { depth: 0, index: 1 }));
+    }
+
+    #[test]
+    fn test_scopes() {
+        let tests = vec![
+            (
+                "if (true) { let a = 1; fn b() {} let a = 2; }",
+                vec!["a", "b"],
+            ),
+            ("for (x in []) { let y = x; }", vec!["x", "y"]),
+            ("try {} catch (e) { let m = e; }", vec!["e", "m"]),
+            (
+                "switch (1) { case 1: let a = 1; default: let b = 2; }",
+                vec!["a", "b"],
+            ),
+        ];
+        for (input, expected) in tests {
+            let program = test_resolve(input);
+            let scope: ScopeNames = match program.statements[0].as_ref() {
+                StatementType::Expression(stmt) => match stmt.expression.as_ref() {
+                    ExpressionType::If(ie) => {
+                        ie.consequence.as_block().unwrap().scope.names.clone()
+                    }
+                    _ => panic!("unexpected expression in {}", input),
+                },
+                StatementType::For(fs) => fs.scope.names.clone(),
+                StatementType::Try(ts) => ts.catch.as_ref().unwrap().scope.names.clone(),
+                StatementType::Switch(ss) => ss.scope.names.clone(),
+                _ => panic!("unexpected statement in {}", input),
+            };
+            assert_eq!(*scope, expected, "wrong scope for {}", input);
+        }
+    }
+
+    #[test]
+    fn test_captures() {
+        let program = test_resolve("fn f(a, b) { let c = fn(d) { a + d }; b + c(1) }");
+
+        let StatementType::Function(f) = program.statements[0].as_ref() else {
+            panic!("expected a function declaration");
+        };
+        assert_eq!(*f.function.scope.names, ["a", "b", "c"]);
+        assert_eq!(*f.function.scope.captured, [true, false, false]);
+    }
+
+    #[test]
+    fn test_undefined_identifiers() {
+        let input = "let a = b;\nfn f() { c = 1; a }\nlet d = 1;";
+        let errors = resolve(parse(input), &Environment::new()).unwrap_err();
+        let errors: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "1:9: identifier not found: b",
+                "2:10: identifier not found: c",
+            ]
+        );
+
+        // Names declared by earlier programs in the same environment resolve.
+        let env = Environment::new();
+        resolve(parse("let a = 1;"), &env).unwrap();
+        resolve(parse("a + 1"), &env).unwrap();
+        assert_eq!(**env.borrow().names(), ["a"]);
+    }
+
+    fn collect_slots(expr: &ExpressionType, out: &mut Vec<(String, Slot)>) {
+        match expr {
+            ExpressionType::Identifier(ident) => {
+                out.push((ident.token.to_string(), ident.slot.unwrap()))
+            }
+            ExpressionType::Infix(infix) => {
+                collect_slots(&infix.left, out);
+                collect_slots(&infix.right, out);
+            }
+            _ => panic!("unexpected expression {:?}", expr),
+        }
+    }
+
+    fn parse(input: &str) -> Program {
+        Parser::new(Lexer::new(input.to_string()))
+            .parse_program()
+            .unwrap_or_else(|e| panic!("parse_program() returned an error: {:?}", e))
+    }
+
+    fn test_resolve(input: &str) -> ResolvedProgram {
+        resolve(parse(input), &Environment::new())
+            .unwrap_or_else(|e| panic!("resolve returned errors: {:?}", e))
+    }
+}
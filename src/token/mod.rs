@@ -6,36 +6,45 @@ use std::mem::{discriminant, Discriminant};
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precedence {
     LOWEST,
+    TERNARY,     // a ? b : c
     EQUALS,      // ==
     LESSGREATER, // > or <
+    RANGE,       // .. or ..=
     SUM,         // +
     PRODUCT,     // *
     PREFIX,      // -X or !X
     CALL,        // myFunction(X)
+    INDEX,       // array[index]
 }
 
 impl Precedence {
     pub fn to_int(&self) -> i32 {
         match self {
             Precedence::LOWEST => 1,
-            Precedence::EQUALS => 2,
-            Precedence::LESSGREATER => 3,
-            Precedence::SUM => 4,
-            Precedence::PRODUCT => 5,
-            Precedence::PREFIX => 6,
-            Precedence::CALL => 7,
+            Precedence::TERNARY => 2,
+            Precedence::EQUALS => 3,
+            Precedence::LESSGREATER => 4,
+            Precedence::RANGE => 5,
+            Precedence::SUM => 6,
+            Precedence::PRODUCT => 7,
+            Precedence::PREFIX => 8,
+            Precedence::CALL => 9,
+            Precedence::INDEX => 10,
         }
     }
 
     pub fn from_int(i: i32) -> Self {
         match i {
             1 => Precedence::LOWEST,
-            2 => Precedence::EQUALS,
-            3 => Precedence::LESSGREATER,
-            4 => Precedence::SUM,
-            5 => Precedence::PRODUCT,
-            6 => Precedence::PREFIX,
-            7 => Precedence::CALL,
+            2 => Precedence::TERNARY,
+            3 => Precedence::EQUALS,
+            4 => Precedence::LESSGREATER,
+            5 => Precedence::RANGE,
+            6 => Precedence::SUM,
+            7 => Precedence::PRODUCT,
+            8 => Precedence::PREFIX,
+            9 => Precedence::CALL,
+            10 => Precedence::INDEX,
             _ => Precedence::LOWEST,
         }
     }
@@ -55,6 +64,7 @@ pub enum Token {
     IDENT(String),
     STRING(String),
     INT(i64),
+    BIGINT(String),
 
     // Operators
     ASSIGN,   // =
@@ -69,31 +79,59 @@ pub enum Token {
     EQ,     // ==
     NOT_EQ, // !=
 
+    DOTDOT,    // ..
+    DOTDOT_EQ, // ..=
+    ARROW,     // =>
+    ELLIPSIS,  // ...
+    DOT,       // .
+    PIPE,      // |
+
     //Delimeters
     COMMA,     // ,
     SEMICOLON, // ;
+    QUESTION,  // ?
+    COLON,     // :
     LPAREN,    // (
     RPAREN,    // )
     LBRACE,    // {
     RBRACE,    // }
+    LBRACKET,  // [
+    RBRACKET,  // ]
 
     // keywords
     FUNCTION,
     LET,
+    CONST,
     // REASSIGN,
     TRUE,
     FALSE,
     IF,
     ELSE,
     RETURN,
+    WHILE,
+    BREAK,
+    CONTINUE,
+    FOR,
+    IN,
+    MATCH,
+    SWITCH,
+    CASE,
+    DEFAULT,
+    TRY,
+    CATCH,
+    FINALLY,
+    THROW,
 }
 
 impl Token {
+    // Predates a `Display` impl and is called throughout.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         match self {
             Token::ILLEGAL(c) => c.to_string(),
             Token::IDENT(s) => s.to_string(),
             Token::INT(i) => i.to_string(),
+            Token::BIGINT(digits) => format!("{}n", digits),
             Token::EOF => "EOF".into(),
             Token::ASSIGN => "=".into(),
             Token::PLUS => "+".into(),
@@ -105,19 +143,43 @@ impl Token {
             Token::LT => "<".into(),
             Token::EQ => "==".into(),
             Token::NOT_EQ => "!=".into(),
+            Token::DOTDOT => "..".into(),
+            Token::DOTDOT_EQ => "..=".into(),
+            Token::ARROW => "=>".into(),
+            Token::ELLIPSIS => "...".into(),
+            Token::DOT => ".".into(),
+            Token::PIPE => "|".into(),
             Token::COMMA => ",".into(),
             Token::SEMICOLON => ";".into(),
+            Token::QUESTION => "?".into(),
+            Token::COLON => ":".into(),
             Token::LPAREN => "(".into(),
             Token::RPAREN => ")".into(),
             Token::LBRACE => "{".into(),
             Token::RBRACE => "}".into(),
+            Token::LBRACKET => "[".into(),
+            Token::RBRACKET => "]".into(),
             Token::FUNCTION => "fn".into(),
             Token::LET => "let".into(),
+            Token::CONST => "const".into(),
             Token::TRUE => "true".into(),
             Token::FALSE => "false".into(),
             Token::IF => "if".into(),
             Token::ELSE => "else".into(),
             Token::RETURN => "return".into(),
+            Token::WHILE => "while".into(),
+            Token::BREAK => "break".into(),
+            Token::CONTINUE => "continue".into(),
+            Token::FOR => "for".into(),
+            Token::IN => "in".into(),
+            Token::MATCH => "match".into(),
+            Token::SWITCH => "switch".into(),
+            Token::CASE => "case".into(),
+            Token::DEFAULT => "default".into(),
+            Token::TRY => "try".into(),
+            Token::CATCH => "catch".into(),
+            Token::FINALLY => "finally".into(),
+            Token::THROW => "throw".into(),
             Token::STRING(s) => s.to_string(),
         }
     }
@@ -127,20 +189,24 @@ impl Token {
     }
 
     pub fn to_original_type(&self) -> Discriminant<Self> {
-        return discriminant(&self);
+        discriminant(self)
     }
 
     pub fn to_precedence(&self) -> Precedence {
         match self {
+            Token::QUESTION => Precedence::TERNARY,
             Token::EQ => Precedence::EQUALS,
             Token::NOT_EQ => Precedence::EQUALS,
             Token::LT => Precedence::LESSGREATER,
             Token::GT => Precedence::LESSGREATER,
+            Token::DOTDOT => Precedence::RANGE,
+            Token::DOTDOT_EQ => Precedence::RANGE,
             Token::PLUS => Precedence::SUM,
             Token::MINUS => Precedence::SUM,
             Token::SLASH => Precedence::PRODUCT,
             Token::ASTERISK => Precedence::PRODUCT,
             Token::LPAREN => Precedence::CALL,
+            Token::LBRACKET | Token::DOT => Precedence::INDEX,
             _ => Precedence::LOWEST,
         }
     }
@@ -151,11 +217,25 @@ lazy_static! {
         let mut m = HashMap::new();
         m.insert("fn", Token::FUNCTION);
         m.insert("let", Token::LET);
+        m.insert("const", Token::CONST);
         m.insert("true", Token::TRUE);
         m.insert("false", Token::FALSE);
         m.insert("if", Token::IF);
         m.insert("else", Token::ELSE);
         m.insert("return", Token::RETURN);
+        m.insert("while", Token::WHILE);
+        m.insert("break", Token::BREAK);
+        m.insert("continue", Token::CONTINUE);
+        m.insert("for", Token::FOR);
+        m.insert("in", Token::IN);
+        m.insert("match", Token::MATCH);
+        m.insert("switch", Token::SWITCH);
+        m.insert("case", Token::CASE);
+        m.insert("default", Token::DEFAULT);
+        m.insert("try", Token::TRY);
+        m.insert("catch", Token::CATCH);
+        m.insert("finally", Token::FINALLY);
+        m.insert("throw", Token::THROW);
         m
     };
 }
@@ -0,0 +1,731 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+use crate::{
+    ast::{expression::ExpressionType, Slot},
+    compiler::{code, Chunk, Location, Opcode},
+    evaluator::{
+        apply_infix_operator, bind_arguments, caught_value, check_infix_operands, check_value_size,
+        eval_bigint_literal, eval_index_expression, eval_integer_infix_expression,
+        eval_prefix_expression, is_truthy,
+        pattern::{self, Mismatch},
+        values_equal, Context, ErrorKind, Frame, RuntimeError,
+    },
+    lexer::Position,
+    object::{
+        environment::{Binding, BindingError, Environment},
+        Array, Boolean, Closure, Hash, HashPair, Integer, Null, Object, ReturnValue, StringObj,
+    },
+    token::Token,
+};
+
+pub mod test;
+
+/// Runs a compiled program in `env` and returns its value, exactly as `evaluator::eval`
+/// would for the program it was compiled from.
+///
+/// Calls push a frame on the machine's own stack instead of recursing, so the depth of
+/// interpreted calls is bounded only by `Limits::max_call_depth`. Limits, interrupts, the
+/// call stack behind backtraces and the cycle collector all live in `ctx`, shared with the
+/// evaluator.
+pub fn run(
+    chunk: Rc<Chunk>,
+    env: Rc<RefCell<Environment>>,
+    ctx: &mut Context,
+) -> Result<Object, RuntimeError> {
+    let mut vm = Vm {
+        stack: Vec::new(),
+        locals: vec![None; chunk.locals.len()],
+        frames: Vec::new(),
+        pending: Vec::new(),
+        ctx,
+    };
+    vm.frames.push(CallFrame {
+        chunk,
+        ip: 0,
+        env,
+        base: 0,
+        locals: 0,
+        pending: 0,
+        call_site: None,
+        blocks: Vec::new(),
+        returning: None,
+    });
+    vm.run()
+}
+
+struct Vm<'c> {
+    stack: Vec<Object>,
+    /// The variables of each call that no closure captures, declared or not.
+    locals: Vec<Option<Binding>>,
+    frames: Vec<CallFrame>,
+    /// The errors `catch` and `finally` clauses are handling, innermost last.
+    pending: Vec<RuntimeError>,
+    ctx: &'c mut Context,
+}
+
+/// The program or a function call being run.
+struct CallFrame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    env: Rc<RefCell<Environment>>,
+    /// The stack height, where its locals start and the number of pending errors when the
+    /// call started.
+    base: usize,
+    locals: usize,
+    pending: usize,
+    /// `None` for the program itself.
+    call_site: Option<Position>,
+    blocks: Vec<Block>,
+    /// The value a `return` hands back once the `finally` clauses it passes have run.
+    returning: Option<Object>,
+}
+
+/// A loop, `switch` or `try` in progress, with the state to restore when it is left early.
+struct Block {
+    /// Where errors raised inside a `try` continue; `None` for loops and switches.
+    handler: Option<usize>,
+    /// The items a `for` loop has yet to visit.
+    items: Option<Box<dyn Iterator<Item = Object>>>,
+    height: usize,
+    env: Rc<RefCell<Environment>>,
+    pending: usize,
+}
+
+impl Vm<'_> {
+    fn run(&mut self) -> Result<Object, RuntimeError> {
+        loop {
+            // Exceeded limits and interrupts cannot be caught, so they need no position.
+            if let Err(err) = self.ctx.step() {
+                self.unwind(err)?;
+                continue;
+            }
+
+            let start = self.frame().ip;
+            match self.execute() {
+                Ok(None) => {}
+                Ok(Some(value)) => return Ok(value),
+                Err(err) => {
+                    let err = match self.frame().chunk.position(start) {
+                        Some(position) => err.at(position),
+                        None => err,
+                    };
+                    self.unwind(err)?;
+                }
+            }
+        }
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames
+            .last()
+            .expect("the program frame is never popped while running")
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames
+            .last_mut()
+            .expect("the program frame is never popped while running")
+    }
+
+    fn push(&mut self, value: Object) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().expect("the stack is never popped empty")
+    }
+
+    fn peek(&self) -> &Object {
+        self.stack.last().expect("the stack is never read empty")
+    }
+
+    /// The local `index` of the current call, with its name for errors.
+    fn local(&mut self, index: usize) -> (&mut Option<Binding>, &str) {
+        let frame = self
+            .frames
+            .last()
+            .expect("the program frame is never popped while running");
+        let name = &frame.chunk.locals[index];
+        (&mut self.locals[frame.locals + index], name)
+    }
+
+    /// Declares the local `index` like `Environment::init` declares a name: only a `let` may
+    /// redeclare a `let`.
+    fn define_local(
+        &mut self,
+        index: usize,
+        value: Object,
+        mutable: bool,
+    ) -> Result<(), BindingError> {
+        let (local, name) = self.local(index);
+        if let Some(existing) = local {
+            if !existing.mutable || !mutable {
+                return Err(BindingError::AlreadyDeclared(name.to_string()));
+            }
+        }
+        *local = Some(Binding { value, mutable });
+        Ok(())
+    }
+
+    /// Runs one instruction, returning the program's value once it finished.
+    fn execute(&mut self) -> Result<Option<Object>, RuntimeError> {
+        let frame = self.frames.last_mut().unwrap();
+        let start = frame.ip;
+        let op = Opcode::from_byte(frame.chunk.code[start]).expect("invalid opcode");
+        let ([a, b], next) = code::read_operands(op, &frame.chunk.code, start + 1);
+        frame.ip = next;
+
+        match op {
+            Opcode::Constant => {
+                let value = self.frame().chunk.constants[a].clone();
+                self.push(value);
+            }
+            Opcode::Null => self.push(Object::Null(Null {})),
+            Opcode::True => self.push(Object::Boolean(Boolean { value: true })),
+            Opcode::False => self.push(Object::Boolean(Boolean { value: false })),
+            Opcode::Pop => {
+                self.pop();
+            }
+            Opcode::Add
+            | Opcode::Sub
+            | Opcode::Mul
+            | Opcode::Div
+            | Opcode::Eq
+            | Opcode::NotEq
+            | Opcode::Lt
+            | Opcode::Gt
+            | Opcode::Range
+            | Opcode::RangeInclusive => {
+                let right = self.pop();
+                let left = self.pop();
+                let result = binary(op, left, right, self.ctx)?;
+                check_value_size(&result, self.ctx)?;
+                self.push(result);
+            }
+            Opcode::Not | Opcode::Negate => {
+                let operator = match op {
+                    Opcode::Not => Token::BANG,
+                    _ => Token::MINUS,
+                };
+                let right = self.pop();
+                self.push(eval_prefix_expression(operator, right)?);
+            }
+            Opcode::Jump => self.frame_mut().ip = a,
+            Opcode::JumpIfFalse => {
+                if !is_truthy(self.pop()) {
+                    self.frame_mut().ip = a;
+                }
+            }
+            Opcode::GetVar | Opcode::CheckVar => {
+                let slot = Slot { depth: a, index: b };
+                let env = self.frame().env.borrow();
+                match env.get(slot) {
+                    Some(value) if op == Opcode::GetVar => {
+                        drop(env);
+                        self.push(value);
+                    }
+                    Some(_) => {}
+                    None => {
+                        let name = env.name(slot).unwrap_or_default();
+                        return Err(ErrorKind::UsedBeforeDeclaration(name).into());
+                    }
+                }
+            }
+            Opcode::SetVar => {
+                let value = self.peek().clone();
+                let slot = Slot { depth: a, index: b };
+                self.frame().env.borrow_mut().assign(slot, value)?;
+            }
+            Opcode::Define => {
+                let value = self.peek().clone();
+                let frame = self.frame();
+                let name = frame.chunk.names[a].clone();
+                match b {
+                    0 => frame.env.borrow_mut().init_const(name, value)?,
+                    _ => frame.env.borrow_mut().init(name, value)?,
+                };
+            }
+            Opcode::GetLocal | Opcode::CheckLocal => match self.local(a) {
+                (Some(binding), _) if op == Opcode::GetLocal => {
+                    let value = binding.value.clone();
+                    self.push(value);
+                }
+                (Some(_), _) => {}
+                (None, name) => return Err(ErrorKind::UsedBeforeDeclaration(name.into()).into()),
+            },
+            Opcode::SetLocal => {
+                let value = self.peek().clone();
+                match self.local(a) {
+                    (Some(binding), _) if binding.mutable => binding.value = value,
+                    (Some(_), name) => {
+                        return Err(BindingError::AssignmentToConstant(name.into()).into())
+                    }
+                    (None, name) => {
+                        return Err(ErrorKind::UsedBeforeDeclaration(name.into()).into())
+                    }
+                }
+            }
+            Opcode::DefineLocal => {
+                let value = self.peek().clone();
+                self.define_local(a, value, b != 0)?;
+            }
+            Opcode::ClearLocals => {
+                let start = self.frame().locals + a;
+                self.locals[start..start + b].fill(None);
+            }
+            Opcode::Destructure => {
+                let value = self.peek().clone();
+                let pattern = Rc::clone(&self.frame().chunk);
+                let pattern = &pattern.patterns[a];
+                let literal =
+                    &mut |literal: &ExpressionType, _: &mut Context| literal_value(literal);
+                let bindings = pattern::bindings(&pattern.pattern, value, self.ctx, literal)?;
+                self.bind(&pattern.locals, bindings, b != 0)?;
+            }
+            Opcode::EnterScope => {
+                let frame = self.frame_mut();
+                let outer = Rc::clone(&frame.env);
+                frame.env = Environment::new_enclosed_environment(outer, &frame.chunk.scopes[a]);
+            }
+            Opcode::LeaveScope => {
+                let frame = self.frame_mut();
+                let outer = frame.env.borrow().outer().cloned();
+                frame.env = outer.expect("scopes are left in the order they were entered");
+            }
+            Opcode::Closure => {
+                let frame = self.frame();
+                let function = Rc::clone(&frame.chunk.functions[a]);
+                let env = Rc::clone(&frame.env);
+                self.ctx.track_closure(&env);
+                self.push(Object::Closure(Rc::new(Closure { function, env })));
+            }
+            Opcode::Call | Opcode::TailCall => {
+                let args = self.stack.split_off(self.stack.len() - a);
+                self.call_op(op == Opcode::TailCall, args, Vec::new(), start)?;
+            }
+            Opcode::CallSpread | Opcode::TailCallSpread => {
+                let args = self.pop_arguments();
+                self.call_op(op == Opcode::TailCallSpread, args, Vec::new(), start)?;
+            }
+            Opcode::CallNamed | Opcode::TailCallNamed => {
+                let names = Rc::clone(&self.frame().chunk.argument_names[a]);
+                let values = self.stack.split_off(self.stack.len() - names.len());
+                let named = names.iter().cloned().zip(values).collect();
+                let args = self.pop_arguments();
+                self.call_op(op == Opcode::TailCallNamed, args, named, start)?;
+            }
+            Opcode::SkipIfLocalArg => {
+                if self.local(a).0.is_some() {
+                    self.frame_mut().ip = b;
+                }
+            }
+            Opcode::SkipIfArg => {
+                let frame = self.frame_mut();
+                if frame
+                    .env
+                    .borrow()
+                    .get(Slot { depth: 0, index: a })
+                    .is_some()
+                {
+                    frame.ip = b;
+                }
+            }
+            Opcode::Return => {
+                let value = self.pop();
+                self.leave_frame();
+                if self.frames.is_empty() {
+                    return Ok(Some(value));
+                }
+                self.push(value);
+            }
+            Opcode::SaveReturn => {
+                let value = self.pop();
+                self.frame_mut().returning = Some(value);
+            }
+            Opcode::LoadReturn => {
+                let value = self.frame_mut().returning.take();
+                self.push(value.expect("a saved return value"));
+            }
+            Opcode::MakeReturn => {
+                let value = self.pop();
+                self.push(Object::Return(ReturnValue {
+                    value: Box::new(value),
+                }));
+            }
+            Opcode::Array => {
+                let elements = self.stack.split_off(self.stack.len() - a);
+                self.ctx.check_collection_len(elements.len())?;
+                self.push(Object::Array(Array { elements }));
+            }
+            Opcode::Append => {
+                let value = self.pop();
+                self.top_array().push(value);
+            }
+            Opcode::Extend => {
+                let iterable = self.pop();
+                let items = match iterable.iterate() {
+                    Some(items) => items,
+                    None => return Err(ErrorKind::NotIterable(iterable.object_type()).into()),
+                };
+                // Checked item by item, since spreading a range builds its elements lazily.
+                for item in items {
+                    self.ctx.step()?;
+                    let elements = self.top_array();
+                    elements.push(item);
+                    let len = elements.len();
+                    self.ctx.check_collection_len(len)?;
+                }
+            }
+            Opcode::CheckLen => {
+                let len = self.top_array().len();
+                self.ctx.check_collection_len(len)?;
+            }
+            Opcode::Hash => self.push(Object::Hash(Hash {
+                pairs: BTreeMap::new(),
+            })),
+            Opcode::HashKey => {
+                let key = self.peek();
+                if key.hash_key().is_none() {
+                    return Err(ErrorKind::UnusableHashKey(key.object_type()).into());
+                }
+            }
+            Opcode::InsertPair => {
+                let value = self.pop();
+                let key = self.pop();
+                let hash_key = key.hash_key().expect("keys are checked before their value");
+                let pairs = match self.stack.last_mut() {
+                    Some(Object::Hash(Hash { pairs })) => pairs,
+                    _ => unreachable!("pairs are inserted into a hash"),
+                };
+                pairs.insert(hash_key, HashPair { key, value });
+                let len = pairs.len();
+                self.ctx.check_collection_len(len)?;
+            }
+            Opcode::Index => {
+                let index = self.pop();
+                let left = self.pop();
+                self.push(eval_index_expression(left, index)?);
+            }
+            Opcode::PushLoop => self.push_block(None, None),
+            Opcode::PushIter => {
+                let iterable = self.pop();
+                match iterable.iterate() {
+                    Some(items) => self.push_block(None, Some(items)),
+                    None => return Err(ErrorKind::NotIterable(iterable.object_type()).into()),
+                }
+            }
+            Opcode::Next => {
+                let frame = self.frame_mut();
+                let block = frame.blocks.last_mut().expect("`for` loops push a block");
+                let items = block.items.as_mut().expect("`for` loops have items");
+                match items.next() {
+                    Some(item) => self.push(item),
+                    None => frame.ip = a,
+                }
+            }
+            Opcode::PushTry => self.push_block(Some(a), None),
+            Opcode::PopBlock => {
+                self.frame_mut().blocks.pop();
+            }
+            Opcode::UnwindBlock => {
+                let block = self.frame_mut().blocks.pop().expect("an enclosing block");
+                self.restore(block.height, block.env, block.pending);
+            }
+            Opcode::RestoreBlock => {
+                let block = self.frame().blocks.last().expect("an enclosing block");
+                let (height, env, pending) = (block.height, Rc::clone(&block.env), block.pending);
+                self.restore(height, env, pending);
+            }
+            Opcode::CaseEq => {
+                let test = self.pop();
+                if values_equal(self.peek(), &test) {
+                    self.pop();
+                    self.frame_mut().ip = a;
+                }
+            }
+            Opcode::Match => {
+                let subject = self.peek().clone();
+                let pattern = Rc::clone(&self.frame().chunk);
+                let pattern = &pattern.patterns[a];
+                let mut bindings = Vec::new();
+                let literal =
+                    &mut |literal: &ExpressionType, _: &mut Context| literal_value(literal);
+                match pattern::match_pattern(
+                    &pattern.pattern,
+                    subject,
+                    &mut bindings,
+                    self.ctx,
+                    literal,
+                ) {
+                    Ok(()) => self.bind(&pattern.locals, bindings, true)?,
+                    Err(Mismatch::Shape(_)) => self.frame_mut().ip = b,
+                    Err(Mismatch::Error(err)) => return Err(err),
+                }
+            }
+            Opcode::NoMatch => return Err(ErrorKind::NoMatchArm(self.pop()).into()),
+            Opcode::Throw => return Err(ErrorKind::Thrown(self.pop()).into()),
+            Opcode::Caught => {
+                let err = self.pending.pop().expect("an error being handled");
+                self.push(caught_value(err));
+            }
+            Opcode::Rethrow => return Err(self.pending.pop().expect("an error being handled")),
+            Opcode::Error => return Err(self.frame().chunk.errors[a].clone()),
+        }
+        Ok(None)
+    }
+
+    /// Declares the names a pattern bound, in the locals the compiler gave them or else in
+    /// the current scope.
+    fn bind(
+        &mut self,
+        locals: &[(String, usize)],
+        bindings: Vec<(String, Object)>,
+        mutable: bool,
+    ) -> Result<(), BindingError> {
+        for (name, value) in bindings {
+            match locals.iter().find(|(local, _)| *local == name) {
+                Some(&(_, index)) => self.define_local(index, value, mutable)?,
+                None => {
+                    let mut env = self.frame().env.borrow_mut();
+                    match mutable {
+                        true => env.init(name, value)?,
+                        false => env.init_const(name, value)?,
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Pops the array a call with spread or named arguments collects its positional
+    /// arguments in.
+    fn pop_arguments(&mut self) -> Vec<Object> {
+        match self.pop() {
+            Object::Array(Array { elements }) => elements,
+            _ => unreachable!("positional arguments are collected into an array"),
+        }
+    }
+
+    fn top_array(&mut self) -> &mut Vec<Object> {
+        match self.stack.last_mut() {
+            Some(Object::Array(Array { elements })) => elements,
+            _ => unreachable!("elements are added to an array"),
+        }
+    }
+
+    fn push_block(
+        &mut self,
+        handler: Option<usize>,
+        items: Option<Box<dyn Iterator<Item = Object>>>,
+    ) {
+        let block = Block {
+            handler,
+            items,
+            height: self.stack.len(),
+            env: Rc::clone(&self.frame().env),
+            pending: self.pending.len(),
+        };
+        self.frame_mut().blocks.push(block);
+    }
+
+    fn restore(&mut self, height: usize, env: Rc<RefCell<Environment>>, pending: usize) {
+        self.stack.truncate(height);
+        self.pending.truncate(pending);
+        self.frame_mut().env = env;
+    }
+
+    /// Makes the call the instruction at `start` compiled. A tail call first finishes the
+    /// current call, so the callee returns straight to its caller.
+    fn call_op(
+        &mut self,
+        tail: bool,
+        args: Vec<Object>,
+        named: Vec<(String, Object)>,
+        start: usize,
+    ) -> Result<(), RuntimeError> {
+        let function = self.pop();
+        let call_site = self
+            .frame()
+            .chunk
+            .position(start)
+            .expect("calls record their position");
+        if tail {
+            self.leave_frame();
+        }
+        self.call(function, args, named, call_site)
+    }
+
+    /// Starts a call of `function`, binding the arguments it was passed. Defaults for the
+    /// rest are evaluated by the function's own code, and a rest parameter's array is left
+    /// on the stack for it.
+    fn call(
+        &mut self,
+        function: Object,
+        args: Vec<Object>,
+        named: Vec<(String, Object)>,
+        call_site: Position,
+    ) -> Result<(), RuntimeError> {
+        let closure = match function {
+            Object::Closure(closure) => closure,
+            function => return Err(ErrorKind::NotAFunction(function.object_type()).at(call_site)),
+        };
+        self.ctx
+            .check_call_depth()
+            .map_err(|err| err.at(call_site))?;
+
+        let function = &closure.function;
+        self.ctx.push_frame(Frame {
+            function: function.name.clone(),
+            call_site,
+        });
+        let bound = match bind_arguments(&function.name, &function.parameters, args, named) {
+            Ok(bound) => bound,
+            Err(mut err) => {
+                err.backtrace = Some(Box::new(self.ctx.backtrace()));
+                self.ctx.pop_frame();
+                return Err(err.at(call_site));
+            }
+        };
+
+        let env = match &function.scope {
+            Some(names) => Environment::new_enclosed_environment(Rc::clone(&closure.env), names),
+            None => Rc::clone(&closure.env),
+        };
+        let base = self.stack.len();
+        let locals = self.locals.len();
+        self.locals
+            .resize(locals + function.chunk.locals.len(), None);
+        let parameters = function.parameters.iter().zip(&function.locations);
+        for ((param, location), value) in parameters.zip(bound.values) {
+            let Some(value) = value else { continue };
+            match location {
+                Location::Local(index) => {
+                    self.locals[locals + index] = Some(Binding {
+                        value,
+                        mutable: true,
+                    })
+                }
+                Location::Env(_) => {
+                    env.borrow_mut()
+                        .init(param.name.clone(), value)
+                        .expect("parameters can always be bound");
+                }
+            }
+        }
+        if let Some(elements) = bound.rest {
+            self.stack.push(Object::Array(Array { elements }));
+        }
+
+        self.frames.push(CallFrame {
+            chunk: Rc::clone(&function.chunk),
+            ip: 0,
+            env,
+            base,
+            locals,
+            pending: self.pending.len(),
+            call_site: Some(call_site),
+            blocks: Vec::new(),
+            returning: None,
+        });
+        Ok(())
+    }
+
+    /// Ends the current call, dropping everything it left behind.
+    fn leave_frame(&mut self) {
+        let frame = self.frames.pop().expect("a frame to leave");
+        self.stack.truncate(frame.base);
+        self.locals.truncate(frame.locals);
+        self.pending.truncate(frame.pending);
+        if frame.call_site.is_some() {
+            self.ctx.pop_frame();
+        }
+    }
+
+    /// Hands `err` to the innermost `try` that can catch it, ending the calls in between.
+    /// An error leaving a call records the backtrace and points at the call site, as in
+    /// the evaluator. Fails with the error once it leaves the program.
+    fn unwind(&mut self, mut err: RuntimeError) -> Result<(), RuntimeError> {
+        loop {
+            if err.is_catchable() {
+                while let Some(block) = self.frame_mut().blocks.pop() {
+                    if let Some(handler) = block.handler {
+                        self.restore(block.height, block.env, block.pending);
+                        self.pending.push(err);
+                        self.frame_mut().ip = handler;
+                        return Ok(());
+                    }
+                }
+            }
+
+            let call_site = self.frame().call_site;
+            match call_site {
+                Some(call_site) => {
+                    err.backtrace
+                        .get_or_insert_with(|| Box::new(self.ctx.backtrace()));
+                    self.leave_frame();
+                    err = err.at(call_site);
+                }
+                None => {
+                    self.leave_frame();
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+fn binary(op: Opcode, left: Object, right: Object, ctx: &Context) -> Result<Object, RuntimeError> {
+    let operator = match op {
+        Opcode::Add => Token::PLUS,
+        Opcode::Sub => Token::MINUS,
+        Opcode::Mul => Token::ASTERISK,
+        Opcode::Div => Token::SLASH,
+        Opcode::Eq => Token::EQ,
+        Opcode::NotEq => Token::NOT_EQ,
+        Opcode::Lt => Token::LT,
+        Opcode::Gt => Token::GT,
+        Opcode::Range => Token::DOTDOT,
+        Opcode::RangeInclusive => Token::DOTDOT_EQ,
+        _ => unreachable!("not a binary operator: {:?}", op),
+    };
+    match (&left, &right) {
+        (Object::Integer(Integer { value: left }), Object::Integer(Integer { value: right })) => {
+            eval_integer_infix_expression(operator, *left, *right)
+        }
+        _ => {
+            check_infix_operands(&operator, &left, &right, ctx)?;
+            apply_infix_operator(operator, left, right)
+        }
+    }
+}
+
+/// The value of a literal in a pattern. The parser only allows literals that need no
+/// environment there: numbers, possibly negated, strings and booleans.
+fn literal_value(literal: &ExpressionType) -> Result<Object, RuntimeError> {
+    match literal {
+        ExpressionType::IntegerLiteral(literal) => Ok(Object::Integer(Integer {
+            value: *literal
+                .token
+                .as_int()
+                .expect("failed to convert token to int"),
+        })),
+        ExpressionType::BigIntLiteral(literal) => eval_bigint_literal(&literal.token),
+        ExpressionType::StringLiteral(literal) => Ok(Object::String(StringObj {
+            value: literal
+                .token
+                .as_string()
+                .expect("failed to convert token to string")
+                .as_str()
+                .into(),
+        })),
+        ExpressionType::Boolean(literal) => Ok(Object::Boolean(Boolean {
+            value: literal.token == Token::TRUE,
+        })),
+        ExpressionType::Prefix(prefix) => {
+            let right = literal_value(&prefix.right)?;
+            eval_prefix_expression(prefix.operator.clone(), right)
+                .map_err(|err| err.at(prefix.position))
+        }
+        _ => unreachable!("patterns only hold literals"),
+    }
+}
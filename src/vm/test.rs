@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        ast::{expression::ExpressionType, statement::StatementType},
+        compiler::compile,
+        evaluator::{eval, Context, ErrorKind, RuntimeError},
+        lexer::Lexer,
+        object::{environment::Environment, Integer, Object},
+        parser::Parser,
+        resolver::resolve,
+        token::Token,
+        vm::run,
+    };
+
+    #[test]
+    fn test_deep_recursion() {
+        // Calls do not recurse natively, so the default test thread stack is enough.
+        let mut ctx = Context::new();
+        ctx.limits.max_call_depth = 100_000;
+        let result = run_in(
+            "fn sum(n) { if (n == 0) { 0 } else { n + sum(n - 1) } } sum(50000)",
+            Environment::new(),
+            &mut ctx,
+        );
+        assert_eq!(result, Ok(Object::Integer(Integer { value: 1250025000 })));
+    }
+
+    #[test]
+    fn test_closures_across_programs() {
+        let env = Environment::new();
+        let mut ctx = Context::new();
+        run_in(
+            "let counter = fn() { let n = 0; fn() { n = n + 1; n } }(); counter();",
+            Rc::clone(&env),
+            &mut ctx,
+        )
+        .unwrap();
+        let result = run_in("counter(); counter()", env, &mut ctx);
+        assert_eq!(result, Ok(Object::Integer(Integer { value: 3 })));
+    }
+
+    #[test]
+    fn test_stack_after_errors() {
+        // A caught error drops whatever the failing expression left on the stack.
+        let result = run_in(
+            "let n = 0; for (i in 0..3) { try { n = n + [i, i + fn() { i / 0 }()][1]; } catch (e) { n = n + i; } } [n, n]",
+            Environment::new(),
+            &mut Context::new(),
+        );
+        assert_eq!(
+            result.map(|value| value.inspect()),
+            Ok("[3, 3]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_uncaught_error_position() {
+        let err = run_in(
+            "let a = 1;\nlet b = a + true;",
+            Environment::new(),
+            &mut Context::new(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err.kind, ErrorKind::TypeMismatch { .. }),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.position.map(|p| p.line), Some(2));
+    }
+
+    #[test]
+    fn test_unknown_operator() {
+        // Only a hand-built tree holds an operator the compiler has no instruction for. It is
+        // reported like the evaluator reports it, after the operands run.
+        for (input, operator) in [("-1", Token::PLUS), ("1 + 2", Token::BANG)] {
+            let mut program = Parser::new(Lexer::new(input.to_string()))
+                .parse_program()
+                .unwrap();
+            let StatementType::Expression(stmt) = &mut *program.statements[0] else {
+                panic!("expected an expression statement");
+            };
+            match &mut *stmt.expression {
+                ExpressionType::Prefix(prefix) => prefix.operator = operator,
+                ExpressionType::Infix(infix) => infix.operator = operator,
+                expr => panic!("expected an operator, got {:?}", expr),
+            }
+
+            let env = Environment::new();
+            let program = resolve(program, &env).unwrap();
+            let evaluated = eval(&program, Rc::clone(&env), &mut Context::new()).unwrap_err();
+            let err = run(Rc::new(compile(&program)), env, &mut Context::new()).unwrap_err();
+            assert!(
+                matches!(err.kind, ErrorKind::UnknownOperator { .. }),
+                "{:?}",
+                err
+            );
+            assert_eq!(err, evaluated);
+        }
+    }
+
+    fn run_in(
+        input: &str,
+        env: Rc<RefCell<Environment>>,
+        ctx: &mut Context,
+    ) -> Result<Object, RuntimeError> {
+        let program = Parser::new(Lexer::new(input.to_string()))
+            .parse_program()
+            .unwrap_or_else(|err| panic!("parse_program() returned an error: {}", err));
+        let program = resolve(program, &env).map_err(|mut errors| errors.remove(0))?;
+        run(Rc::new(compile(&program)), env, ctx)
+    }
+}
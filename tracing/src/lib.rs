@@ -19,8 +19,7 @@ pub fn auto_log(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let enabled = std::env::var("auto_log_enabled").unwrap_or("false".to_string());
 
-    let expanded =
-        quote! {
+    let expanded = quote! {
             #fn_vis #fn_async #fn_const #fn_unsafe #fn_abi #fn_generics fn #fn_name(#fn_inputs) #fn_output {
                 let indent = std::env::var("auto_log_indent").unwrap_or("0".to_string()).as_str().parse::<usize>().unwrap();
                 if #enabled == "true" {
@@ -34,7 +33,7 @@ pub fn auto_log(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     let new_indent = indent.to_string();
                     std::env::set_var("auto_log_indent", new_indent);
                 }
-              
+
                 result
             }
     };